@@ -0,0 +1,71 @@
+/* Prints the points and observation zones of a task file.
+ *
+ *   cargo build -p xcsoar-tasks-c
+ *   cc bindings/c/examples/read_task.c -I bindings/c/include \
+ *      target/debug/libxcsoar_tasks_c.a -lm -lpthread -ldl -o read_task
+ *   ./read_task fixtures/aat-task.tsk
+ */
+
+#include <math.h>
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "xcsoar_tasks.h"
+
+static char *read_file(const char *path, size_t *len) {
+  FILE *file = fopen(path, "rb");
+  if (!file) {
+    return NULL;
+  }
+  fseek(file, 0, SEEK_END);
+  long size = ftell(file);
+  fseek(file, 0, SEEK_SET);
+  char *data = malloc(size);
+  *len = fread(data, 1, size, file);
+  fclose(file);
+  return data;
+}
+
+int main(int argc, char **argv) {
+  if (argc != 2) {
+    fprintf(stderr, "usage: %s <task.tsk>\n", argv[0]);
+    return 2;
+  }
+
+  size_t len;
+  char *data = read_file(argv[1], &len);
+  if (!data) {
+    perror(argv[1]);
+    return 1;
+  }
+
+  XcsoarTask *task = NULL;
+  XcsoarStatus status = xcsoar_task_parse((const uint8_t *)data, len, &task);
+  free(data);
+  if (status != XCSOAR_STATUS_OK) {
+    fprintf(stderr, "%s: %s\n", argv[1], xcsoar_last_error());
+    return 1;
+  }
+
+  size_t count;
+  double distance;
+  xcsoar_task_point_count(task, &count);
+  xcsoar_task_distance(task, &distance);
+  printf("%zu points, %.1f km\n", count, distance / 1000.0);
+  for (size_t i = 0; i < count; i++) {
+    XcsoarPoint point;
+    xcsoar_task_point(task, i, &point);
+    printf("%zu %s (%.5f, %.5f)", i, point.name, point.latitude,
+           point.longitude);
+    if (!isnan(point.zone.radius)) {
+      printf(" radius %.0f m", point.zone.radius);
+    }
+    if (!isnan(point.zone.length)) {
+      printf(" line %.0f m", point.zone.length);
+    }
+    printf("\n");
+  }
+
+  xcsoar_task_free(task);
+  return 0;
+}
@@ -0,0 +1,263 @@
+/* Generated with cbindgen from bindings/c/src/lib.rs, do not edit. */
+
+#ifndef XCSOAR_TASKS_H
+#define XCSOAR_TASKS_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Result of a fallible call.
+ */
+typedef enum XcsoarStatus {
+  XCSOAR_STATUS_OK = 0,
+  /**
+   * A required pointer argument was null.
+   */
+  XCSOAR_STATUS_NULL_POINTER = 1,
+  /**
+   * The input is not a valid task file.
+   */
+  XCSOAR_STATUS_PARSE_ERROR = 2,
+  /**
+   * The task could not be written.
+   */
+  XCSOAR_STATUS_SERIALIZE_ERROR = 3,
+  /**
+   * A point index was out of range.
+   */
+  XCSOAR_STATUS_OUT_OF_RANGE = 4,
+  /**
+   * An enum argument had a value that isn't one of its variants.
+   */
+  XCSOAR_STATUS_INVALID_ARGUMENT = 5,
+} XcsoarStatus;
+
+typedef enum XcsoarTaskType {
+  XCSOAR_TASK_TYPE_AAT,
+  XCSOAR_TASK_TYPE_RT,
+  XCSOAR_TASK_TYPE_FAI_GENERAL,
+  XCSOAR_TASK_TYPE_FAI_TRIANGLE,
+  XCSOAR_TASK_TYPE_FAI_OR,
+  XCSOAR_TASK_TYPE_FAI_GOAL,
+  XCSOAR_TASK_TYPE_MAT,
+  XCSOAR_TASK_TYPE_MIXED,
+  XCSOAR_TASK_TYPE_TOURING,
+} XcsoarTaskType;
+
+typedef enum XcsoarPointType {
+  XCSOAR_POINT_TYPE_START,
+  XCSOAR_POINT_TYPE_TURN,
+  XCSOAR_POINT_TYPE_AREA,
+  XCSOAR_POINT_TYPE_FINISH,
+  XCSOAR_POINT_TYPE_OPTIONAL_START,
+} XcsoarPointType;
+
+typedef enum XcsoarZoneType {
+  XCSOAR_ZONE_TYPE_CYLINDER,
+  XCSOAR_ZONE_TYPE_LINE,
+  XCSOAR_ZONE_TYPE_KEYHOLE,
+  XCSOAR_ZONE_TYPE_FAI_SECTOR,
+  XCSOAR_ZONE_TYPE_SECTOR,
+  XCSOAR_ZONE_TYPE_SYMMETRIC_QUADRANT,
+  XCSOAR_ZONE_TYPE_CUSTOM_KEYHOLE,
+  XCSOAR_ZONE_TYPE_MAT_CYLINDER,
+  XCSOAR_ZONE_TYPE_BGA_START_SECTOR,
+  XCSOAR_ZONE_TYPE_BGA_FIXED_COURSE,
+  XCSOAR_ZONE_TYPE_BGA_ENHANCED_OPTION,
+} XcsoarZoneType;
+
+/**
+ * The shape of the earth that distances are measured on, see
+ * [`EarthModel`]. Passed as a `uint32_t`, so that unknown values from C can
+ * be rejected.
+ */
+typedef enum XcsoarEarthModel {
+  /**
+   * The sphere used by XCSoar.
+   */
+  XCSOAR_EARTH_MODEL_FAI_SPHERE = 0,
+  /**
+   * The WGS84 ellipsoid used for badges and records.
+   */
+  XCSOAR_EARTH_MODEL_WGS84 = 1,
+} XcsoarEarthModel;
+
+/**
+ * Opaque handle to a parsed task.
+ */
+typedef struct XcsoarTask XcsoarTask;
+
+/**
+ * Observation zone parameters in meters and degrees. Parameters that are not
+ * set or don't apply to the zone type are NaN.
+ */
+typedef struct XcsoarZone {
+  enum XcsoarZoneType zone_type;
+  double radius;
+  double inner_radius;
+  double length;
+  double angle;
+  double start_radial;
+  double end_radial;
+} XcsoarZone;
+
+/**
+ * A task point. `id` and `comment` are null if not set, `altitude` is NaN.
+ */
+typedef struct XcsoarPoint {
+  enum XcsoarPointType point_type;
+  const char *name;
+  const char *id;
+  const char *comment;
+  double latitude;
+  double longitude;
+  double altitude;
+  struct XcsoarZone zone;
+} XcsoarPoint;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Version of the library as a static NUL-terminated string.
+ */
+const char *xcsoar_version(void);
+
+/**
+ * Description of the last error on the current thread, or null. The string
+ * is valid until the next failing call on the same thread.
+ */
+const char *xcsoar_last_error(void);
+
+/**
+ * Parses a task file from a buffer, detecting its encoding. On success the
+ * new handle is stored in `out` and must be freed with `xcsoar_task_free`.
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes and `out` must be a valid
+ * pointer.
+ */
+enum XcsoarStatus xcsoar_task_parse(const uint8_t *data, size_t len, struct XcsoarTask **out);
+
+/**
+ * Frees a task handle. Passing null is a no-op.
+ *
+ * # Safety
+ *
+ * `task` must be null or a handle returned by `xcsoar_task_parse` that has
+ * not been freed yet.
+ */
+void xcsoar_task_free(struct XcsoarTask *task);
+
+/**
+ * Stores the task type in `out`.
+ *
+ * # Safety
+ *
+ * `task` must be a valid handle and `out` a valid pointer.
+ */
+enum XcsoarStatus xcsoar_task_type(const struct XcsoarTask *task, enum XcsoarTaskType *out);
+
+/**
+ * Stores the minimum task time of AAT tasks in seconds in `out`, or -1 if
+ * not set.
+ *
+ * # Safety
+ *
+ * `task` must be a valid handle and `out` a valid pointer.
+ */
+enum XcsoarStatus xcsoar_task_aat_min_time(const struct XcsoarTask *task, int64_t *out);
+
+/**
+ * Stores the opening of the start in seconds since midnight UTC in `out`,
+ * or -1 if not set.
+ *
+ * # Safety
+ *
+ * `task` must be a valid handle and `out` a valid pointer.
+ */
+enum XcsoarStatus xcsoar_task_start_open_time(const struct XcsoarTask *task, int64_t *out);
+
+/**
+ * Stores the closing of the start in seconds since midnight UTC in `out`,
+ * or -1 if not set.
+ *
+ * # Safety
+ *
+ * `task` must be a valid handle and `out` a valid pointer.
+ */
+enum XcsoarStatus xcsoar_task_start_close_time(const struct XcsoarTask *task, int64_t *out);
+
+/**
+ * Stores the nominal task distance in meters, through the center of each
+ * point, in `out`.
+ *
+ * # Safety
+ *
+ * `task` must be a valid handle and `out` a valid pointer.
+ */
+enum XcsoarStatus xcsoar_task_distance(const struct XcsoarTask *task, double *out);
+
+/**
+ * Like [`xcsoar_task_distance`], measured on the earth model given as one
+ * of the [`XcsoarEarthModel`] values. Fails with `InvalidArgument` for any
+ * other value.
+ *
+ * # Safety
+ *
+ * `task` must be a valid handle and `out` a valid pointer.
+ */
+enum XcsoarStatus xcsoar_task_distance_with_model(const struct XcsoarTask *task,
+                                                  uint32_t model,
+                                                  double *out);
+
+/**
+ * Stores the number of points in `out`.
+ *
+ * # Safety
+ *
+ * `task` must be a valid handle and `out` a valid pointer.
+ */
+enum XcsoarStatus xcsoar_task_point_count(const struct XcsoarTask *task, size_t *out);
+
+/**
+ * Reads the point at `index` into `out`.
+ *
+ * # Safety
+ *
+ * `task` must be a valid handle and `out` a valid pointer.
+ */
+enum XcsoarStatus xcsoar_task_point(const struct XcsoarTask *task,
+                                    size_t index,
+                                    struct XcsoarPoint *out);
+
+/**
+ * Writes the task as a `.tsk` file. On success the NUL-terminated UTF-8
+ * string is stored in `out` and must be freed with `xcsoar_string_free`.
+ *
+ * # Safety
+ *
+ * `task` must be a valid handle and `out` a valid pointer.
+ */
+enum XcsoarStatus xcsoar_task_serialize(const struct XcsoarTask *task, bool pretty, char **out);
+
+/**
+ * Frees a string returned by `xcsoar_task_serialize`. Passing null is a
+ * no-op.
+ *
+ * # Safety
+ *
+ * `string` must be null or a string returned by this library that has not
+ * been freed yet.
+ */
+void xcsoar_string_free(char *string);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* XCSOAR_TASKS_H */
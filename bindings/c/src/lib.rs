@@ -0,0 +1,514 @@
+//! C bindings for [`xcsoar_tasks`].
+//!
+//! Tasks are parsed into an opaque [`XcsoarTask`] handle that owns all data
+//! returned by the accessors. Strings returned through [`XcsoarPoint`] stay
+//! valid until the handle is freed with [`xcsoar_task_free`].
+//!
+//! Functions that take a handle return an [`XcsoarStatus`] and store their
+//! result through an out pointer, failing with `NullPointer` rather than
+//! dereferencing a null argument. A description of the last error on the
+//! current thread is available from [`xcsoar_last_error`]. The header in
+//! `include/xcsoar_tasks.h` is generated with cbindgen, see `tests/header.rs`.
+
+use std::cell::RefCell;
+use std::ffi::{CString, c_char};
+use std::ptr;
+use xcsoar_tasks::{EarthModel, ObservationZone, PointType, Task, TaskType};
+
+/// Result of a fallible call.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XcsoarStatus {
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// The input is not a valid task file.
+    ParseError = 2,
+    /// The task could not be written.
+    SerializeError = 3,
+    /// A point index was out of range.
+    OutOfRange = 4,
+    /// An enum argument had a value that isn't one of its variants.
+    InvalidArgument = 5,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XcsoarTaskType {
+    Aat,
+    Rt,
+    FaiGeneral,
+    FaiTriangle,
+    FaiOr,
+    FaiGoal,
+    Mat,
+    Mixed,
+    Touring,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XcsoarPointType {
+    Start,
+    Turn,
+    Area,
+    Finish,
+    OptionalStart,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XcsoarZoneType {
+    Cylinder,
+    Line,
+    Keyhole,
+    FaiSector,
+    Sector,
+    SymmetricQuadrant,
+    CustomKeyhole,
+    MatCylinder,
+    BgaStartSector,
+    BgaFixedCourse,
+    BgaEnhancedOption,
+}
+
+/// The shape of the earth that distances are measured on, see
+/// [`EarthModel`]. Passed as a `uint32_t`, so that unknown values from C can
+/// be rejected.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XcsoarEarthModel {
+    /// The sphere used by XCSoar.
+    FaiSphere = 0,
+    /// The WGS84 ellipsoid used for badges and records.
+    Wgs84 = 1,
+}
+
+/// Observation zone parameters in meters and degrees. Parameters that are not
+/// set or don't apply to the zone type are NaN.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct XcsoarZone {
+    pub zone_type: XcsoarZoneType,
+    pub radius: f64,
+    pub inner_radius: f64,
+    pub length: f64,
+    pub angle: f64,
+    pub start_radial: f64,
+    pub end_radial: f64,
+}
+
+/// A task point. `id` and `comment` are null if not set, `altitude` is NaN.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct XcsoarPoint {
+    pub point_type: XcsoarPointType,
+    pub name: *const c_char,
+    pub id: *const c_char,
+    pub comment: *const c_char,
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: f64,
+    pub zone: XcsoarZone,
+}
+
+/// Opaque handle to a parsed task.
+pub struct XcsoarTask {
+    task: Task,
+    /// NUL-terminated copies of each point's name, id and comment.
+    strings: Vec<[Option<CString>; 3]>,
+}
+
+impl XcsoarTask {
+    fn new(task: Task) -> Self {
+        let strings = task
+            .points
+            .iter()
+            .map(|point| {
+                let waypoint = &point.waypoint;
+                [
+                    Some(c_string(&waypoint.name)),
+                    waypoint.id.as_deref().map(c_string),
+                    waypoint.comment.as_deref().map(c_string),
+                ]
+            })
+            .collect();
+        XcsoarTask { task, strings }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: impl ToString) {
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(c_string(&message.to_string())));
+}
+
+/// Converts to a C string, dropping interior NUL bytes.
+fn c_string(value: &str) -> CString {
+    CString::new(value.replace('\0', "")).expect("NUL bytes have been removed")
+}
+
+/// Version of the library as a static NUL-terminated string.
+#[unsafe(no_mangle)]
+pub extern "C" fn xcsoar_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+/// Description of the last error on the current thread, or null. The string
+/// is valid until the next failing call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn xcsoar_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |error| error.as_ptr())
+    })
+}
+
+/// Parses a task file from a buffer, detecting its encoding. On success the
+/// new handle is stored in `out` and must be freed with `xcsoar_task_free`.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out` must be a valid
+/// pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xcsoar_task_parse(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut XcsoarTask,
+) -> XcsoarStatus {
+    if data.is_null() || out.is_null() {
+        return null_pointer();
+    }
+    let bytes = unsafe { std::slice::from_raw_parts(data, len) };
+    match xcsoar_tasks::from_slice(bytes) {
+        Ok(task) => {
+            unsafe { *out = Box::into_raw(Box::new(XcsoarTask::new(task))) };
+            XcsoarStatus::Ok
+        }
+        Err(error) => {
+            set_last_error(error);
+            XcsoarStatus::ParseError
+        }
+    }
+}
+
+/// Frees a task handle. Passing null is a no-op.
+///
+/// # Safety
+///
+/// `task` must be null or a handle returned by `xcsoar_task_parse` that has
+/// not been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xcsoar_task_free(task: *mut XcsoarTask) {
+    if !task.is_null() {
+        drop(unsafe { Box::from_raw(task) });
+    }
+}
+
+/// Stores the result of `read` in `out`, or fails if either pointer is null.
+///
+/// # Safety
+///
+/// `task` must be null or a valid handle and `out` null or a valid pointer.
+unsafe fn read<T>(
+    task: *const XcsoarTask,
+    out: *mut T,
+    read: impl FnOnce(&Task) -> T,
+) -> XcsoarStatus {
+    if task.is_null() || out.is_null() {
+        return null_pointer();
+    }
+    let value = read(unsafe { &(*task).task });
+    unsafe { out.write(value) };
+    XcsoarStatus::Ok
+}
+
+fn null_pointer() -> XcsoarStatus {
+    set_last_error("null pointer argument");
+    XcsoarStatus::NullPointer
+}
+
+/// Stores the task type in `out`.
+///
+/// # Safety
+///
+/// `task` must be a valid handle and `out` a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xcsoar_task_type(
+    task: *const XcsoarTask,
+    out: *mut XcsoarTaskType,
+) -> XcsoarStatus {
+    unsafe {
+        read(task, out, |task| match task.task_type {
+            TaskType::AAT => XcsoarTaskType::Aat,
+            TaskType::RT => XcsoarTaskType::Rt,
+            TaskType::FAIGeneral => XcsoarTaskType::FaiGeneral,
+            TaskType::FAITriangle => XcsoarTaskType::FaiTriangle,
+            TaskType::FAIOR => XcsoarTaskType::FaiOr,
+            TaskType::FAIGoal => XcsoarTaskType::FaiGoal,
+            TaskType::MAT => XcsoarTaskType::Mat,
+            TaskType::Mixed => XcsoarTaskType::Mixed,
+            TaskType::Touring => XcsoarTaskType::Touring,
+        })
+    }
+}
+
+/// Stores the minimum task time of AAT tasks in seconds in `out`, or -1 if
+/// not set.
+///
+/// # Safety
+///
+/// `task` must be a valid handle and `out` a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xcsoar_task_aat_min_time(
+    task: *const XcsoarTask,
+    out: *mut i64,
+) -> XcsoarStatus {
+    unsafe {
+        read(task, out, |task| {
+            task.aat_min_time
+                .map_or(-1, |time| i64::try_from(time.as_secs()).unwrap_or(i64::MAX))
+        })
+    }
+}
+
+/// Stores the opening of the start in seconds since midnight UTC in `out`,
+/// or -1 if not set.
+///
+/// # Safety
+///
+/// `task` must be a valid handle and `out` a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xcsoar_task_start_open_time(
+    task: *const XcsoarTask,
+    out: *mut i64,
+) -> XcsoarStatus {
+    unsafe {
+        read(task, out, |task| {
+            task.start_open_time
+                .map_or(-1, |time| i64::from(time.as_seconds()))
+        })
+    }
+}
+
+/// Stores the closing of the start in seconds since midnight UTC in `out`,
+/// or -1 if not set.
+///
+/// # Safety
+///
+/// `task` must be a valid handle and `out` a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xcsoar_task_start_close_time(
+    task: *const XcsoarTask,
+    out: *mut i64,
+) -> XcsoarStatus {
+    unsafe {
+        read(task, out, |task| {
+            task.start_close_time
+                .map_or(-1, |time| i64::from(time.as_seconds()))
+        })
+    }
+}
+
+/// Stores the nominal task distance in meters, through the center of each
+/// point, in `out`.
+///
+/// # Safety
+///
+/// `task` must be a valid handle and `out` a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xcsoar_task_distance(
+    task: *const XcsoarTask,
+    out: *mut f64,
+) -> XcsoarStatus {
+    unsafe { read(task, out, Task::distance) }
+}
+
+/// Like [`xcsoar_task_distance`], measured on the earth model given as one
+/// of the [`XcsoarEarthModel`] values. Fails with `InvalidArgument` for any
+/// other value.
+///
+/// # Safety
+///
+/// `task` must be a valid handle and `out` a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xcsoar_task_distance_with_model(
+    task: *const XcsoarTask,
+    model: u32,
+    out: *mut f64,
+) -> XcsoarStatus {
+    let model = if model == XcsoarEarthModel::FaiSphere as u32 {
+        EarthModel::FaiSphere
+    } else if model == XcsoarEarthModel::Wgs84 as u32 {
+        EarthModel::Wgs84
+    } else {
+        set_last_error(format!("unknown earth model {model}"));
+        return XcsoarStatus::InvalidArgument;
+    };
+    unsafe { read(task, out, |task| task.distance_with_model(model)) }
+}
+
+/// Stores the number of points in `out`.
+///
+/// # Safety
+///
+/// `task` must be a valid handle and `out` a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xcsoar_task_point_count(
+    task: *const XcsoarTask,
+    out: *mut usize,
+) -> XcsoarStatus {
+    unsafe { read(task, out, |task| task.points.len()) }
+}
+
+/// Reads the point at `index` into `out`.
+///
+/// # Safety
+///
+/// `task` must be a valid handle and `out` a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xcsoar_task_point(
+    task: *const XcsoarTask,
+    index: usize,
+    out: *mut XcsoarPoint,
+) -> XcsoarStatus {
+    if task.is_null() || out.is_null() {
+        return null_pointer();
+    }
+    let handle = unsafe { &*task };
+    let (Some(point), Some(strings)) = (handle.task.points.get(index), handle.strings.get(index))
+    else {
+        set_last_error(format!("point index {index} out of range"));
+        return XcsoarStatus::OutOfRange;
+    };
+
+    let string = |value: &Option<CString>| value.as_ref().map_or(ptr::null(), |s| s.as_ptr());
+    let waypoint = &point.waypoint;
+    let point = XcsoarPoint {
+        point_type: match point.point_type {
+            PointType::Start => XcsoarPointType::Start,
+            PointType::Turn => XcsoarPointType::Turn,
+            PointType::Area => XcsoarPointType::Area,
+            PointType::Finish => XcsoarPointType::Finish,
+            PointType::OptionalStart => XcsoarPointType::OptionalStart,
+        },
+        name: string(&strings[0]),
+        id: string(&strings[1]),
+        comment: string(&strings[2]),
+        latitude: waypoint.location.latitude,
+        longitude: waypoint.location.longitude,
+        altitude: waypoint.altitude.unwrap_or(f64::NAN),
+        zone: zone(&point.observation_zone),
+    };
+    unsafe { *out = point };
+    XcsoarStatus::Ok
+}
+
+fn zone(zone: &ObservationZone) -> XcsoarZone {
+    let empty = |zone_type| XcsoarZone {
+        zone_type,
+        radius: f64::NAN,
+        inner_radius: f64::NAN,
+        length: f64::NAN,
+        angle: f64::NAN,
+        start_radial: f64::NAN,
+        end_radial: f64::NAN,
+    };
+    let set = |value: Option<f64>| value.unwrap_or(f64::NAN);
+
+    match *zone {
+        ObservationZone::Cylinder { radius } => XcsoarZone {
+            radius,
+            ..empty(XcsoarZoneType::Cylinder)
+        },
+        ObservationZone::Line { length } => XcsoarZone {
+            length,
+            ..empty(XcsoarZoneType::Line)
+        },
+        ObservationZone::Keyhole => empty(XcsoarZoneType::Keyhole),
+        ObservationZone::FAISector => empty(XcsoarZoneType::FaiSector),
+        ObservationZone::Sector {
+            radius,
+            start_radial,
+            end_radial,
+            inner_radius,
+        } => XcsoarZone {
+            radius,
+            start_radial,
+            end_radial,
+            inner_radius: set(inner_radius),
+            ..empty(XcsoarZoneType::Sector)
+        },
+        ObservationZone::SymmetricQuadrant { radius, angle } => XcsoarZone {
+            radius: set(radius),
+            angle: set(angle),
+            ..empty(XcsoarZoneType::SymmetricQuadrant)
+        },
+        ObservationZone::CustomKeyhole {
+            radius,
+            angle,
+            inner_radius,
+        } => XcsoarZone {
+            radius: set(radius),
+            angle: set(angle),
+            inner_radius: set(inner_radius),
+            ..empty(XcsoarZoneType::CustomKeyhole)
+        },
+        ObservationZone::MatCylinder => empty(XcsoarZoneType::MatCylinder),
+        ObservationZone::BGAStartSector => empty(XcsoarZoneType::BgaStartSector),
+        ObservationZone::BGAFixedCourse => empty(XcsoarZoneType::BgaFixedCourse),
+        ObservationZone::BGAEnhancedOption => empty(XcsoarZoneType::BgaEnhancedOption),
+    }
+}
+
+/// Writes the task as a `.tsk` file. On success the NUL-terminated UTF-8
+/// string is stored in `out` and must be freed with `xcsoar_string_free`.
+///
+/// # Safety
+///
+/// `task` must be a valid handle and `out` a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xcsoar_task_serialize(
+    task: *const XcsoarTask,
+    pretty: bool,
+    out: *mut *mut c_char,
+) -> XcsoarStatus {
+    if task.is_null() || out.is_null() {
+        return null_pointer();
+    }
+    let task = unsafe { &(*task).task };
+    let result = if pretty {
+        xcsoar_tasks::to_string_pretty(task)
+    } else {
+        xcsoar_tasks::to_string(task)
+    };
+    match result {
+        Ok(xml) => {
+            unsafe { *out = c_string(&xml).into_raw() };
+            XcsoarStatus::Ok
+        }
+        Err(error) => {
+            set_last_error(error);
+            XcsoarStatus::SerializeError
+        }
+    }
+}
+
+/// Frees a string returned by `xcsoar_task_serialize`. Passing null is a
+/// no-op.
+///
+/// # Safety
+///
+/// `string` must be null or a string returned by this library that has not
+/// been freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn xcsoar_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(unsafe { CString::from_raw(string) });
+    }
+}
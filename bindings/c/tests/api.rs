@@ -0,0 +1,174 @@
+use std::ffi::CStr;
+use std::ptr;
+use xcsoar_tasks_c::*;
+
+const AAT_TASK: &[u8] = include_bytes!("../../../fixtures/aat-task.tsk");
+const ALL_OZ_TYPES: &[u8] = include_bytes!("../../../fixtures/all-oz-types.tsk");
+
+fn parse(data: &[u8]) -> *mut XcsoarTask {
+    let mut task = ptr::null_mut();
+    let status = unsafe { xcsoar_task_parse(data.as_ptr(), data.len(), &mut task) };
+    assert_eq!(status, XcsoarStatus::Ok);
+    task
+}
+
+/// Calls an accessor that stores its result through an out pointer.
+fn get<T: Default>(
+    task: *const XcsoarTask,
+    accessor: unsafe extern "C" fn(*const XcsoarTask, *mut T) -> XcsoarStatus,
+) -> T {
+    let mut value = T::default();
+    let status = unsafe { accessor(task, &mut value) };
+    assert_eq!(status, XcsoarStatus::Ok);
+    value
+}
+
+fn distance(task: *const XcsoarTask, model: XcsoarEarthModel) -> f64 {
+    let mut distance = f64::NAN;
+    let status = unsafe { xcsoar_task_distance_with_model(task, model as u32, &mut distance) };
+    assert_eq!(status, XcsoarStatus::Ok);
+    distance
+}
+
+fn point(task: *const XcsoarTask, index: usize) -> XcsoarPoint {
+    let mut point = std::mem::MaybeUninit::uninit();
+    let status = unsafe { xcsoar_task_point(task, index, point.as_mut_ptr()) };
+    assert_eq!(status, XcsoarStatus::Ok);
+    unsafe { point.assume_init() }
+}
+
+#[test]
+fn iterate_points() {
+    let task = parse(AAT_TASK);
+    let mut task_type = XcsoarTaskType::Rt;
+    let status = unsafe { xcsoar_task_type(task, &mut task_type) };
+    assert_eq!(status, XcsoarStatus::Ok);
+    assert_eq!(task_type, XcsoarTaskType::Aat);
+    assert_eq!(get(task, xcsoar_task_aat_min_time), 12600);
+    assert_eq!(get(task, xcsoar_task_start_open_time), -1);
+    assert_eq!(get(task, xcsoar_task_start_close_time), -1);
+    assert_eq!(get(task, xcsoar_task_point_count), 6);
+
+    let nominal = get(task, xcsoar_task_distance);
+    assert!((nominal - 270_960.0).abs() < 1000.0);
+    assert_eq!(distance(task, XcsoarEarthModel::FaiSphere), nominal);
+    assert!((distance(task, XcsoarEarthModel::Wgs84) - nominal).abs() < 1500.0);
+
+    let start = point(task, 0);
+    assert_eq!(start.point_type, XcsoarPointType::Start);
+    let name = unsafe { CStr::from_ptr(start.name) };
+    assert_eq!(name.to_str().unwrap(), "Langenfeld-Wiescheid");
+    assert_eq!(start.zone.zone_type, XcsoarZoneType::Line);
+    assert_eq!(start.zone.length, 20000.0);
+    assert!(start.zone.radius.is_nan());
+
+    let area = point(task, 1);
+    let name = unsafe { CStr::from_ptr(area.name) };
+    assert_eq!(name.to_str().unwrap(), "Aachen Merzbrück");
+    assert_eq!(area.zone.zone_type, XcsoarZoneType::Cylinder);
+    assert_eq!(area.zone.radius, 10000.0);
+
+    let mut out = std::mem::MaybeUninit::uninit();
+    let status = unsafe { xcsoar_task_point(task, 6, out.as_mut_ptr()) };
+    assert_eq!(status, XcsoarStatus::OutOfRange);
+
+    unsafe { xcsoar_task_free(task) };
+}
+
+#[test]
+fn zone_parameters() {
+    let task = parse(ALL_OZ_TYPES);
+    let count = get(task, xcsoar_task_point_count);
+    let sector = (0..count)
+        .map(|index| point(task, index).zone)
+        .find(|zone| zone.zone_type == XcsoarZoneType::Sector)
+        .unwrap();
+    assert!(!sector.start_radial.is_nan());
+    assert!(!sector.end_radial.is_nan());
+    unsafe { xcsoar_task_free(task) };
+}
+
+#[test]
+fn serialize_roundtrip() {
+    let task = parse(AAT_TASK);
+    let mut xml = ptr::null_mut();
+    let status = unsafe { xcsoar_task_serialize(task, true, &mut xml) };
+    assert_eq!(status, XcsoarStatus::Ok);
+
+    let serialized = unsafe { CStr::from_ptr(xml) }.to_str().unwrap();
+    let expected = xcsoar_tasks::from_slice(AAT_TASK).unwrap();
+    assert_eq!(xcsoar_tasks::from_str(serialized).unwrap(), expected);
+
+    unsafe {
+        xcsoar_string_free(xml);
+        xcsoar_task_free(task);
+    }
+}
+
+#[test]
+fn parse_error() {
+    let data = b"<Task";
+    let mut task = ptr::null_mut();
+    let status = unsafe { xcsoar_task_parse(data.as_ptr(), data.len(), &mut task) };
+    assert_eq!(status, XcsoarStatus::ParseError);
+    assert!(task.is_null());
+
+    let error = unsafe { CStr::from_ptr(xcsoar_last_error()) };
+    assert!(error.to_str().unwrap().contains("XML"));
+}
+
+#[test]
+fn unknown_earth_model() {
+    let task = parse(AAT_TASK);
+    let mut distance = f64::NAN;
+    let status = unsafe { xcsoar_task_distance_with_model(task, 2, &mut distance) };
+    assert_eq!(status, XcsoarStatus::InvalidArgument);
+    assert!(distance.is_nan());
+    let error = unsafe { CStr::from_ptr(xcsoar_last_error()) };
+    assert_eq!(error.to_str().unwrap(), "unknown earth model 2");
+    unsafe { xcsoar_task_free(task) };
+}
+
+#[test]
+fn null_arguments() {
+    let status = unsafe { xcsoar_task_parse(ptr::null(), 0, ptr::null_mut()) };
+    assert_eq!(status, XcsoarStatus::NullPointer);
+    unsafe { xcsoar_task_free(ptr::null_mut()) };
+    unsafe { xcsoar_string_free(ptr::null_mut()) };
+
+    let mut task_type = XcsoarTaskType::Rt;
+    let mut seconds = 0;
+    let mut distance = 0.0;
+    let mut count = 0;
+    let statuses = unsafe {
+        [
+            xcsoar_task_type(ptr::null(), &mut task_type),
+            xcsoar_task_aat_min_time(ptr::null(), &mut seconds),
+            xcsoar_task_start_open_time(ptr::null(), &mut seconds),
+            xcsoar_task_start_close_time(ptr::null(), &mut seconds),
+            xcsoar_task_distance(ptr::null(), &mut distance),
+            xcsoar_task_distance_with_model(
+                ptr::null(),
+                XcsoarEarthModel::Wgs84 as u32,
+                &mut distance,
+            ),
+            xcsoar_task_point_count(ptr::null(), &mut count),
+            xcsoar_task_point(ptr::null(), 0, ptr::null_mut()),
+            xcsoar_task_serialize(ptr::null(), false, ptr::null_mut()),
+        ]
+    };
+    assert_eq!(statuses, [XcsoarStatus::NullPointer; 9]);
+    let error = unsafe { CStr::from_ptr(xcsoar_last_error()) };
+    assert_eq!(error.to_str().unwrap(), "null pointer argument");
+
+    let task = parse(AAT_TASK);
+    let statuses = unsafe {
+        [
+            xcsoar_task_aat_min_time(task, ptr::null_mut()),
+            xcsoar_task_distance(task, ptr::null_mut()),
+            xcsoar_task_point_count(task, ptr::null_mut()),
+        ]
+    };
+    assert_eq!(statuses, [XcsoarStatus::NullPointer; 3]);
+    unsafe { xcsoar_task_free(task) };
+}
@@ -0,0 +1,516 @@
+//! Python bindings for [`xcsoar_tasks`].
+//!
+//! The task model is exposed as read-only classes. To build or modify a task
+//! from Python, go through dictionaries in the JSON representation of
+//! [`xcsoar_tasks::json`] with `Task.to_dict()` and `Task.from_dict()`.
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyTime};
+use std::time::Duration;
+use xcsoar_tasks::format::{self, UnitSystem};
+use xcsoar_tasks::{
+    Altitude, AltitudeReference, EarthModel, Location, ObservationZone, Point, Task, TimeOfDay,
+    Waypoint,
+};
+
+create_exception!(
+    xcsoar_tasks,
+    ParseError,
+    PyValueError,
+    "Raised when a task file can't be parsed."
+);
+
+fn parse_error(error: xcsoar_tasks::ParseError) -> PyErr {
+    ParseError::new_err(error.to_string())
+}
+
+fn value_error(error: impl ToString) -> PyErr {
+    PyValueError::new_err(error.to_string())
+}
+
+fn earth_model(name: &str) -> PyResult<EarthModel> {
+    match name {
+        "FaiSphere" => Ok(EarthModel::FaiSphere),
+        "Wgs84" => Ok(EarthModel::Wgs84),
+        _ => Err(value_error(format!(
+            "unknown earth model {name:?}, expected \"FaiSphere\" or \"Wgs84\""
+        ))),
+    }
+}
+
+/// A soaring task.
+#[pyclass(
+    name = "Task",
+    module = "xcsoar_tasks",
+    frozen,
+    eq,
+    skip_from_py_object
+)]
+#[derive(Clone, PartialEq)]
+struct PyTask(Task);
+
+#[pymethods]
+impl PyTask {
+    /// Parses the contents of a `.tsk` file.
+    #[staticmethod]
+    fn from_str(xml: &str) -> PyResult<Self> {
+        xcsoar_tasks::from_str(xml).map(PyTask).map_err(parse_error)
+    }
+
+    /// Parses a `.tsk` file from bytes, detecting its encoding.
+    #[staticmethod]
+    fn from_bytes(data: &[u8]) -> PyResult<Self> {
+        xcsoar_tasks::from_slice(data)
+            .map(PyTask)
+            .map_err(parse_error)
+    }
+
+    /// Creates a task from a dictionary as returned by `to_dict()`.
+    #[staticmethod]
+    fn from_dict(dict: &Bound<'_, PyDict>) -> PyResult<Self> {
+        let json = dict.py().import("json")?.call_method1("dumps", (dict,))?;
+        xcsoar_tasks::json::from_str(&json.extract::<String>()?)
+            .map(PyTask)
+            .map_err(value_error)
+    }
+
+    /// Writes the task as a `.tsk` file.
+    #[pyo3(signature = (pretty = false))]
+    fn to_string(&self, pretty: bool) -> PyResult<String> {
+        let result = if pretty {
+            xcsoar_tasks::to_string_pretty(&self.0)
+        } else {
+            xcsoar_tasks::to_string(&self.0)
+        };
+        result.map_err(value_error)
+    }
+
+    /// Converts the task into nested dictionaries and lists.
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        json_loads(py, &xcsoar_tasks::json::to_string(&self.0))
+    }
+
+    /// GeoJSON feature collection with the course line and the task points.
+    fn to_geojson<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        json_loads(py, &xcsoar_tasks::geojson::to_string(&self.0))
+    }
+
+    /// Nominal task distance in meters, through the center of each point.
+    #[pyo3(signature = (earth_model = "FaiSphere"))]
+    fn distance(&self, earth_model: &str) -> PyResult<f64> {
+        Ok(self.0.distance_with_model(self::earth_model(earth_model)?))
+    }
+
+    /// Distances of the individual legs in meters.
+    #[pyo3(signature = (earth_model = "FaiSphere"))]
+    fn leg_distances(&self, earth_model: &str) -> PyResult<Vec<f64>> {
+        Ok(self
+            .0
+            .leg_distances_with_model(self::earth_model(earth_model)?))
+    }
+
+    #[getter]
+    fn task_type(&self) -> String {
+        format!("{:?}", self.0.task_type)
+    }
+
+    #[getter]
+    fn aat_min_time(&self) -> Option<Duration> {
+        self.0.aat_min_time
+    }
+
+    #[getter]
+    fn start_requires_arm(&self) -> Option<bool> {
+        self.0.start_requires_arm
+    }
+
+    #[getter]
+    fn start_score_exit(&self) -> Option<bool> {
+        self.0.start_score_exit
+    }
+
+    /// Maximum start speed in meters per second, `0` meaning no limit.
+    #[getter]
+    fn start_max_speed(&self) -> Option<f64> {
+        self.0
+            .start_max_speed
+            .map(|speed| speed.meters_per_second())
+    }
+
+    /// Maximum start height, `0` meters meaning no limit.
+    #[getter]
+    fn start_max_height(&self) -> Option<PyAltitude> {
+        self.0.start_max_height.map(PyAltitude)
+    }
+
+    /// Opening of the start in UTC.
+    #[getter]
+    fn start_open_time<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyTime>>> {
+        self.0.start_open_time.map(|t| to_time(py, t)).transpose()
+    }
+
+    /// Closing of the start in UTC. Tasks closing at 24:00:00 or later
+    /// fail to load, like other out of range values.
+    #[getter]
+    fn start_close_time<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyTime>>> {
+        self.0.start_close_time.map(|t| to_time(py, t)).transpose()
+    }
+
+    #[getter]
+    fn finish_min_height(&self) -> Option<PyAltitude> {
+        self.0.finish_min_height.map(PyAltitude)
+    }
+
+    #[getter]
+    fn fai_finish(&self) -> Option<bool> {
+        self.0.fai_finish
+    }
+
+    /// Time between the pilot event and the opening of the start.
+    #[getter]
+    fn pev_start_wait_time(&self) -> Option<Duration> {
+        self.0.pev_start_wait_time
+    }
+
+    /// How long the start stays open after a pilot event.
+    #[getter]
+    fn pev_start_window(&self) -> Option<Duration> {
+        self.0.pev_start_window
+    }
+
+    #[getter]
+    fn points(&self) -> Vec<PyPoint> {
+        self.0.points.iter().cloned().map(PyPoint).collect()
+    }
+
+    fn __str__(&self) -> String {
+        format::task(&self.0, UnitSystem::METRIC)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Task(task_type='{}', points={})",
+            self.task_type(),
+            self.0.points.len()
+        )
+    }
+}
+
+/// A point of a task: a waypoint with its role and observation zone.
+#[pyclass(
+    name = "Point",
+    module = "xcsoar_tasks",
+    frozen,
+    eq,
+    skip_from_py_object
+)]
+#[derive(Clone, PartialEq)]
+struct PyPoint(Point);
+
+#[pymethods]
+impl PyPoint {
+    #[getter]
+    fn point_type(&self) -> String {
+        format!("{:?}", self.0.point_type)
+    }
+
+    #[getter]
+    fn score_exit(&self) -> Option<bool> {
+        self.0.score_exit
+    }
+
+    #[getter]
+    fn waypoint(&self) -> PyWaypoint {
+        PyWaypoint(self.0.waypoint.clone())
+    }
+
+    #[getter]
+    fn observation_zone(&self) -> PyObservationZone {
+        PyObservationZone(self.0.observation_zone.clone())
+    }
+
+    fn __str__(&self) -> String {
+        format::point(&self.0, UnitSystem::METRIC)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Point(point_type='{}', waypoint='{}')",
+            self.point_type(),
+            self.0.waypoint.name
+        )
+    }
+}
+
+#[pyclass(
+    name = "Waypoint",
+    module = "xcsoar_tasks",
+    frozen,
+    eq,
+    skip_from_py_object
+)]
+#[derive(Clone, PartialEq)]
+struct PyWaypoint(Waypoint);
+
+#[pymethods]
+impl PyWaypoint {
+    #[getter]
+    fn name(&self) -> &str {
+        &self.0.name
+    }
+
+    #[getter]
+    fn id(&self) -> Option<&str> {
+        self.0.id.as_deref()
+    }
+
+    #[getter]
+    fn comment(&self) -> Option<&str> {
+        self.0.comment.as_deref()
+    }
+
+    /// Elevation in meters MSL.
+    #[getter]
+    fn altitude(&self) -> Option<f64> {
+        self.0.altitude
+    }
+
+    #[getter]
+    fn location(&self) -> PyLocation {
+        PyLocation(self.0.location)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Waypoint(name='{}')", self.0.name)
+    }
+}
+
+#[pyclass(
+    name = "Location",
+    module = "xcsoar_tasks",
+    frozen,
+    eq,
+    skip_from_py_object
+)]
+#[derive(Clone, PartialEq)]
+struct PyLocation(Location);
+
+#[pymethods]
+impl PyLocation {
+    #[new]
+    fn new(latitude: f64, longitude: f64) -> PyResult<Self> {
+        Location::new(latitude, longitude)
+            .map(PyLocation)
+            .map_err(value_error)
+    }
+
+    #[getter]
+    fn latitude(&self) -> f64 {
+        self.0.latitude
+    }
+
+    #[getter]
+    fn longitude(&self) -> f64 {
+        self.0.longitude
+    }
+
+    /// Distance in meters.
+    #[pyo3(signature = (other, earth_model = "FaiSphere"))]
+    fn distance_to(&self, other: &PyLocation, earth_model: &str) -> PyResult<f64> {
+        Ok(self::earth_model(earth_model)?.distance(&self.0, &other.0))
+    }
+
+    /// Initial true bearing in degrees.
+    #[pyo3(signature = (other, earth_model = "FaiSphere"))]
+    fn bearing_to(&self, other: &PyLocation, earth_model: &str) -> PyResult<f64> {
+        Ok(self::earth_model(earth_model)?.bearing(&self.0, &other.0))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Location(latitude={}, longitude={})",
+            self.0.latitude, self.0.longitude
+        )
+    }
+}
+
+/// A height limit and the reference it is measured from, `"AGL"` or `"MSL"`.
+#[pyclass(
+    name = "Altitude",
+    module = "xcsoar_tasks",
+    frozen,
+    eq,
+    skip_from_py_object
+)]
+#[derive(Clone, PartialEq)]
+struct PyAltitude(Altitude);
+
+#[pymethods]
+impl PyAltitude {
+    #[getter]
+    fn meters(&self) -> u32 {
+        self.0.meters
+    }
+
+    #[getter]
+    fn reference(&self) -> &'static str {
+        match self.0.reference {
+            AltitudeReference::AGL => "AGL",
+            AltitudeReference::MSL => "MSL",
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Altitude(meters={}, reference='{}')",
+            self.meters(),
+            self.reference()
+        )
+    }
+}
+
+/// An observation zone. Parameters that don't apply to the zone type are
+/// `None`.
+#[pyclass(
+    name = "ObservationZone",
+    module = "xcsoar_tasks",
+    frozen,
+    eq,
+    skip_from_py_object
+)]
+#[derive(Clone, PartialEq)]
+struct PyObservationZone(ObservationZone);
+
+#[pymethods]
+impl PyObservationZone {
+    #[getter(r#type)]
+    fn zone_type(&self) -> &'static str {
+        match self.0 {
+            ObservationZone::Cylinder { .. } => "Cylinder",
+            ObservationZone::Line { .. } => "Line",
+            ObservationZone::Keyhole => "Keyhole",
+            ObservationZone::FAISector => "FAISector",
+            ObservationZone::Sector { .. } => "Sector",
+            ObservationZone::SymmetricQuadrant { .. } => "SymmetricQuadrant",
+            ObservationZone::CustomKeyhole { .. } => "CustomKeyhole",
+            ObservationZone::MatCylinder => "MatCylinder",
+            ObservationZone::BGAStartSector => "BGAStartSector",
+            ObservationZone::BGAFixedCourse => "BGAFixedCourse",
+            ObservationZone::BGAEnhancedOption => "BGAEnhancedOption",
+        }
+    }
+
+    #[getter]
+    fn radius(&self) -> Option<f64> {
+        match self.0 {
+            ObservationZone::Cylinder { radius } | ObservationZone::Sector { radius, .. } => {
+                Some(radius)
+            }
+            ObservationZone::SymmetricQuadrant { radius, .. }
+            | ObservationZone::CustomKeyhole { radius, .. } => radius,
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn inner_radius(&self) -> Option<f64> {
+        match self.0 {
+            ObservationZone::Sector { inner_radius, .. }
+            | ObservationZone::CustomKeyhole { inner_radius, .. } => inner_radius,
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn length(&self) -> Option<f64> {
+        match self.0 {
+            ObservationZone::Line { length } => Some(length),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn angle(&self) -> Option<f64> {
+        match self.0 {
+            ObservationZone::SymmetricQuadrant { angle, .. }
+            | ObservationZone::CustomKeyhole { angle, .. } => angle,
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn start_radial(&self) -> Option<f64> {
+        match self.0 {
+            ObservationZone::Sector { start_radial, .. } => Some(start_radial),
+            _ => None,
+        }
+    }
+
+    #[getter]
+    fn end_radial(&self) -> Option<f64> {
+        match self.0 {
+            ObservationZone::Sector { end_radial, .. } => Some(end_radial),
+            _ => None,
+        }
+    }
+
+    fn __str__(&self) -> String {
+        format::zone(&self.0, UnitSystem::METRIC)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("ObservationZone(type='{}')", self.zone_type())
+    }
+}
+
+fn json_loads<'py>(py: Python<'py>, json: &str) -> PyResult<Bound<'py, PyAny>> {
+    py.import("json")?.call_method1("loads", (json,))
+}
+
+fn to_time(py: Python<'_>, time: TimeOfDay) -> PyResult<Bound<'_, PyTime>> {
+    PyTime::new(
+        py,
+        time.hours() as u8,
+        time.minutes() as u8,
+        time.seconds() as u8,
+        0,
+        None,
+    )
+}
+
+/// Parses the contents of a `.tsk` file.
+#[pyfunction]
+fn from_str(xml: &str) -> PyResult<PyTask> {
+    PyTask::from_str(xml)
+}
+
+/// Parses a `.tsk` file from bytes, detecting its encoding.
+#[pyfunction]
+fn from_bytes(data: &[u8]) -> PyResult<PyTask> {
+    PyTask::from_bytes(data)
+}
+
+/// Writes a task as a `.tsk` file.
+#[pyfunction]
+#[pyo3(signature = (task, pretty = false))]
+fn to_string(task: &PyTask, pretty: bool) -> PyResult<String> {
+    task.to_string(pretty)
+}
+
+/// Parse and write XCSoar task files.
+#[pymodule(name = "xcsoar_tasks")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("ParseError", m.py().get_type::<ParseError>())?;
+    m.add_class::<PyTask>()?;
+    m.add_class::<PyPoint>()?;
+    m.add_class::<PyWaypoint>()?;
+    m.add_class::<PyLocation>()?;
+    m.add_class::<PyAltitude>()?;
+    m.add_class::<PyObservationZone>()?;
+    m.add_function(wrap_pyfunction!(from_str, m)?)?;
+    m.add_function(wrap_pyfunction!(from_bytes, m)?)?;
+    m.add_function(wrap_pyfunction!(to_string, m)?)?;
+    Ok(())
+}
@@ -0,0 +1,126 @@
+import datetime
+from pathlib import Path
+
+import pytest
+
+import xcsoar_tasks
+
+FIXTURES = Path(__file__).parents[3] / "fixtures"
+
+
+def load(name):
+    return xcsoar_tasks.from_str((FIXTURES / name).read_text(encoding="utf-8"))
+
+
+def test_parse():
+    task = load("aat-task.tsk")
+    assert task.task_type == "AAT"
+    assert task.aat_min_time == datetime.timedelta(hours=3, minutes=30)
+    assert len(task.points) == 6
+
+    start = task.points[0]
+    assert start.point_type == "Start"
+    assert start.waypoint.name == "Langenfeld-Wiescheid"
+    assert start.observation_zone.type == "Line"
+    assert start.observation_zone.length == 20000.0
+    assert start.observation_zone.radius is None
+
+
+def test_start_and_finish_rules():
+    task = load("aat-task.tsk")
+    assert task.start_max_speed == 60.0
+    assert task.start_max_height.meters == 0
+    assert task.start_max_height.reference == "MSL"
+    assert task.finish_min_height.reference == "AGL"
+    assert task.fai_finish is False
+    assert task.start_requires_arm is None
+    assert task.start_score_exit is None
+    assert task.pev_start_wait_time is None
+    assert task.pev_start_window is None
+
+    data = task.to_dict()
+    data["start_requires_arm"] = True
+    data["start_score_exit"] = False
+    data["start_max_height"] = {"meters": 1500, "reference": "MSL"}
+    data["pev_start_wait_time"] = 600
+    data["pev_start_window"] = 300
+    task = xcsoar_tasks.Task.from_dict(data)
+    assert task.start_requires_arm is True
+    assert task.start_score_exit is False
+    assert task.start_max_height.meters == 1500
+    assert task.pev_start_wait_time == datetime.timedelta(minutes=10)
+    assert task.pev_start_window == datetime.timedelta(minutes=5)
+
+
+def test_parse_bytes_detects_encoding():
+    data = (FIXTURES / "aat-task-latin1.tsk").read_bytes()
+    task = xcsoar_tasks.from_bytes(data)
+    assert task.points[1].waypoint.name == "Aachen Merzbrück"
+
+
+def test_parse_error():
+    with pytest.raises(xcsoar_tasks.ParseError):
+        xcsoar_tasks.from_str("<Task")
+
+
+def test_start_times():
+    xml = (FIXTURES / "racing-task.tsk").read_text(encoding="utf-8")
+
+    def with_close_time(seconds):
+        return xml.replace("<Task ", f'<Task start_close_time="{seconds}" ', 1)
+
+    task = xcsoar_tasks.from_str(with_close_time(86399))
+    assert task.start_close_time == datetime.time(23, 59, 59)
+    assert xcsoar_tasks.from_str(task.to_string()) == task
+
+    # 24:00:00 has no `datetime.time`, and isn't a time of day in XCSoar
+    with pytest.raises(ValueError):
+        xcsoar_tasks.from_str(with_close_time(86400))
+    data = task.to_dict()
+    data["start_close_time"] = 86400
+    with pytest.raises(ValueError):
+        xcsoar_tasks.Task.from_dict(data)
+
+
+def test_roundtrip():
+    task = load("fai-task.tsk")
+    assert xcsoar_tasks.from_str(task.to_string(pretty=True)) == task
+    assert xcsoar_tasks.from_str(xcsoar_tasks.to_string(task)) == task
+
+
+def test_distances():
+    task = load("fai-task.tsk")
+    legs = task.leg_distances()
+    assert len(legs) == len(task.points) - 1
+    assert task.distance() == pytest.approx(sum(legs))
+    assert task.distance() == pytest.approx(132939, abs=1)
+
+    first, second = (point.waypoint.location for point in task.points[:2])
+    assert first.distance_to(second) == pytest.approx(legs[0])
+
+    wgs84 = task.leg_distances(earth_model="Wgs84")
+    assert first.distance_to(second, earth_model="Wgs84") == pytest.approx(wgs84[0])
+    assert task.distance("Wgs84") == pytest.approx(sum(wgs84))
+    assert task.distance("Wgs84") != pytest.approx(task.distance())
+    with pytest.raises(ValueError):
+        task.distance("Flat")
+
+
+def test_dict_roundtrip():
+    task = load("all-oz-types.tsk")
+    data = task.to_dict()
+    assert data["points"][0]["waypoint"]["location"]["latitude"] == pytest.approx(
+        task.points[0].waypoint.location.latitude
+    )
+
+    data["points"][1]["observation_zone"] = {"type": "Cylinder", "radius": 3000.0}
+    modified = xcsoar_tasks.Task.from_dict(data)
+    assert modified.points[1].observation_zone.radius == 3000.0
+    assert modified != task
+
+
+def test_geojson():
+    geojson = load("racing-task.tsk").to_geojson()
+    assert geojson["type"] == "FeatureCollection"
+    assert geojson["features"][0]["geometry"]["type"] == "LineString"
+    assert len(geojson["features"]) == 6
@@ -0,0 +1,102 @@
+import datetime
+from typing import Any, Literal, Optional
+
+class ParseError(ValueError): ...
+
+EarthModel = Literal["FaiSphere", "Wgs84"]
+
+class Location:
+    def __init__(self, latitude: float, longitude: float) -> None: ...
+    @property
+    def latitude(self) -> float: ...
+    @property
+    def longitude(self) -> float: ...
+    def distance_to(self, other: Location, earth_model: EarthModel = "FaiSphere") -> float: ...
+    def bearing_to(self, other: Location, earth_model: EarthModel = "FaiSphere") -> float: ...
+
+class Altitude:
+    @property
+    def meters(self) -> int: ...
+    @property
+    def reference(self) -> Literal["AGL", "MSL"]: ...
+
+class Waypoint:
+    @property
+    def name(self) -> str: ...
+    @property
+    def id(self) -> Optional[str]: ...
+    @property
+    def comment(self) -> Optional[str]: ...
+    @property
+    def altitude(self) -> Optional[float]: ...
+    @property
+    def location(self) -> Location: ...
+
+class ObservationZone:
+    @property
+    def type(self) -> str: ...
+    @property
+    def radius(self) -> Optional[float]: ...
+    @property
+    def inner_radius(self) -> Optional[float]: ...
+    @property
+    def length(self) -> Optional[float]: ...
+    @property
+    def angle(self) -> Optional[float]: ...
+    @property
+    def start_radial(self) -> Optional[float]: ...
+    @property
+    def end_radial(self) -> Optional[float]: ...
+
+class Point:
+    @property
+    def point_type(self) -> str: ...
+    @property
+    def score_exit(self) -> Optional[bool]: ...
+    @property
+    def waypoint(self) -> Waypoint: ...
+    @property
+    def observation_zone(self) -> ObservationZone: ...
+
+class Task:
+    @staticmethod
+    def from_str(xml: str) -> Task: ...
+    @staticmethod
+    def from_bytes(data: bytes) -> Task: ...
+    @staticmethod
+    def from_dict(dict: dict[str, Any]) -> Task: ...
+    def to_string(self, pretty: bool = False) -> str: ...
+    def to_dict(self) -> dict[str, Any]: ...
+    def to_geojson(self) -> dict[str, Any]: ...
+    def distance(self, earth_model: EarthModel = "FaiSphere") -> float: ...
+    def leg_distances(self, earth_model: EarthModel = "FaiSphere") -> list[float]: ...
+    @property
+    def task_type(self) -> str: ...
+    @property
+    def aat_min_time(self) -> Optional[datetime.timedelta]: ...
+    @property
+    def start_requires_arm(self) -> Optional[bool]: ...
+    @property
+    def start_score_exit(self) -> Optional[bool]: ...
+    @property
+    def start_max_speed(self) -> Optional[float]: ...
+    @property
+    def start_max_height(self) -> Optional[Altitude]: ...
+    @property
+    def start_open_time(self) -> Optional[datetime.time]: ...
+    @property
+    def start_close_time(self) -> Optional[datetime.time]: ...
+    @property
+    def finish_min_height(self) -> Optional[Altitude]: ...
+    @property
+    def fai_finish(self) -> Optional[bool]: ...
+    @property
+    def pev_start_wait_time(self) -> Optional[datetime.timedelta]: ...
+    @property
+    def pev_start_window(self) -> Optional[datetime.timedelta]: ...
+    @property
+    def points(self) -> list[Point]: ...
+
+def from_str(xml: str) -> Task: ...
+def from_bytes(data: bytes) -> Task: ...
+def to_string(task: Task, pretty: bool = False) -> str: ...
@@ -0,0 +1,204 @@
+//! WebAssembly bindings for [`xcsoar_tasks`].
+//!
+//! Tasks are passed to and from JavaScript as plain objects in the JSON
+//! representation of [`xcsoar_tasks::json`], typed as `Task` in the generated
+//! TypeScript definitions.
+
+use wasm_bindgen::prelude::*;
+use xcsoar_tasks::{EarthModel, Task};
+
+/// Types of the JSON representation, checked against its schema in the
+/// tests.
+#[wasm_bindgen(typescript_custom_section)]
+const TYPESCRIPT_DEFINITIONS: &str = include_str!("task.d.ts");
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Task")]
+    pub type JsTask;
+}
+
+/// Parses the contents of a `.tsk` file.
+#[wasm_bindgen(js_name = fromStr)]
+pub fn from_str(xml: &str) -> Result<JsTask, JsError> {
+    let task = xcsoar_tasks::from_str(xml)?;
+    to_js(&task)
+}
+
+/// Writes a task as an indented `.tsk` file.
+#[wasm_bindgen(js_name = toStringPretty)]
+pub fn to_string_pretty(task: &JsTask) -> Result<String, JsError> {
+    Ok(xcsoar_tasks::to_string_pretty(&from_js(task)?)?)
+}
+
+/// Nominal task distance in meters, through the center of each point.
+/// Measured on the FAI sphere unless `earthModel` is `"Wgs84"`.
+#[wasm_bindgen]
+pub fn distance(
+    task: &JsTask,
+    #[wasm_bindgen(js_name = earthModel, unchecked_param_type = "EarthModel")] earth_model: Option<
+        String,
+    >,
+) -> Result<f64, JsError> {
+    Ok(from_js(task)?.distance_with_model(self::earth_model(earth_model)?))
+}
+
+/// Distances of the individual legs in meters.
+#[wasm_bindgen(js_name = legDistances)]
+pub fn leg_distances(
+    task: &JsTask,
+    #[wasm_bindgen(js_name = earthModel, unchecked_param_type = "EarthModel")] earth_model: Option<
+        String,
+    >,
+) -> Result<Vec<f64>, JsError> {
+    Ok(from_js(task)?.leg_distances_with_model(self::earth_model(earth_model)?))
+}
+
+/// GeoJSON feature collection with the course line and the task points.
+#[wasm_bindgen(js_name = toGeoJson)]
+pub fn to_geojson(task: &JsTask) -> Result<JsValue, JsError> {
+    let geojson = xcsoar_tasks::geojson::to_value(&from_js(task)?).to_string();
+    js_sys::JSON::parse(&geojson).map_err(|_| JsError::new("invalid GeoJSON"))
+}
+
+fn to_js(task: &Task) -> Result<JsTask, JsError> {
+    let json = xcsoar_tasks::json::to_string(task);
+    js_sys::JSON::parse(&json)
+        .map(JsCast::unchecked_into)
+        .map_err(|_| JsError::new("invalid task JSON"))
+}
+
+fn earth_model(name: Option<String>) -> Result<EarthModel, JsError> {
+    match name.as_deref() {
+        None | Some("FaiSphere") => Ok(EarthModel::FaiSphere),
+        Some("Wgs84") => Ok(EarthModel::Wgs84),
+        Some(name) => Err(JsError::new(&format!(
+            "unknown earth model {name:?}, expected \"FaiSphere\" or \"Wgs84\""
+        ))),
+    }
+}
+
+fn from_js(task: &JsTask) -> Result<Task, JsError> {
+    let json =
+        js_sys::JSON::stringify(task).map_err(|_| JsError::new("task is not serializable"))?;
+    Ok(xcsoar_tasks::json::from_str(&String::from(json))?)
+}
+
+/// The TypeScript definitions are written by hand, so check them against
+/// the JSON Schema of the representation they describe.
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use serde_json::Value;
+    use std::collections::BTreeMap;
+
+    /// Property names, mapped to whether they are required.
+    type Fields = BTreeMap<String, bool>;
+
+    fn definitions() -> String {
+        let mut text = include_str!("task.d.ts").to_string();
+        while let Some(start) = text.find("/**") {
+            let end = start + text[start..].find("*/").unwrap() + 2;
+            text.replace_range(start..end, "");
+        }
+        text
+    }
+
+    /// The declaration following `export {keyword} {name}`, up to the next
+    /// one.
+    fn declaration<'a>(text: &'a str, keyword: &str, name: &str) -> &'a str {
+        let start = text
+            .find(&format!("export {keyword} {name} "))
+            .unwrap_or_else(|| panic!("{name} isn't declared"));
+        let rest = &text[start + 1..];
+        &rest[..rest.find("export ").unwrap_or(rest.len())]
+    }
+
+    /// Fields of an object type body such as `a: number; b?: string`.
+    fn fields(body: &str) -> Fields {
+        body.split(['\n', ';'])
+            .filter_map(|field| field.split_once(':'))
+            .map(|(name, _)| name.trim())
+            .filter(|name| !name.is_empty())
+            .map(|name| match name.strip_suffix('?') {
+                Some(name) => (name.to_string(), false),
+                None => (name.to_string(), true),
+            })
+            .collect()
+    }
+
+    fn braces(text: &str) -> impl Iterator<Item = &str> {
+        text.split('{')
+            .skip(1)
+            .map(|part| &part[..part.find('}').unwrap()])
+    }
+
+    fn strings(text: &str) -> Vec<String> {
+        text.split('"')
+            .skip(1)
+            .step_by(2)
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Properties with a default, like `points`, are optional when reading
+    /// but always written, so the typings declare them as required.
+    fn schema_fields(schema: &Value) -> Fields {
+        let required = schema["required"].as_array().cloned().unwrap_or_default();
+        schema["properties"]
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(name, property)| {
+                let required =
+                    required.contains(&name.as_str().into()) || property.get("default").is_some();
+                (name.clone(), required)
+            })
+            .collect()
+    }
+
+    fn schema_strings(schema: &Value) -> Vec<String> {
+        let values = schema["enum"].as_array().unwrap();
+        values.iter().map(|v| v.as_str().unwrap().into()).collect()
+    }
+
+    #[test]
+    fn typescript_matches_schema() {
+        let text = definitions();
+        let schema = xcsoar_tasks::json::schema();
+        let schema = schema.as_value();
+        let defs = &schema["$defs"];
+
+        let interface = |name| {
+            fields(
+                braces(declaration(&text, "interface", name))
+                    .next()
+                    .unwrap(),
+            )
+        };
+        assert_eq!(interface("Task"), schema_fields(schema), "Task");
+        for name in ["Point", "Waypoint", "Location", "Altitude"] {
+            assert_eq!(interface(name), schema_fields(&defs[name]), "{name}");
+        }
+
+        for name in ["TaskType", "PointType"] {
+            let declared = strings(declaration(&text, "type", name));
+            assert_eq!(declared, schema_strings(&defs[name]), "{name}");
+        }
+        let altitude = declaration(&text, "interface", "Altitude");
+        let reference = altitude.split("reference:").nth(1).unwrap();
+        assert_eq!(
+            strings(&reference[..reference.find(';').unwrap()]),
+            schema_strings(&defs["AltitudeReference"])
+        );
+
+        let zones = braces(declaration(&text, "type", "ObservationZone"))
+            .map(fields)
+            .collect::<Vec<_>>();
+        let variants = defs["ObservationZone"]["oneOf"].as_array().unwrap();
+        assert_eq!(zones.len(), variants.len());
+        for (zone, variant) in zones.iter().zip(variants) {
+            let zone_type = &variant["properties"]["type"]["const"];
+            assert_eq!(zone, &schema_fields(variant), "{zone_type}");
+        }
+    }
+}
@@ -0,0 +1,80 @@
+export type TaskType =
+  | "AAT" | "RT" | "FAIGeneral" | "FAITriangle" | "FAIOR" | "FAIGoal"
+  | "MAT" | "Mixed" | "Touring";
+
+export type PointType = "Start" | "Turn" | "Area" | "Finish" | "OptionalStart";
+
+export type EarthModel = "FaiSphere" | "Wgs84";
+
+export interface Altitude {
+  meters: number;
+  reference: "AGL" | "MSL";
+}
+
+export interface Location {
+  latitude: number;
+  longitude: number;
+}
+
+export interface Waypoint {
+  name: string;
+  id?: string;
+  comment?: string;
+  /** Elevation in meters MSL. */
+  altitude?: number;
+  location: Location;
+}
+
+export type ObservationZone =
+  | { type: "Cylinder"; radius: number }
+  | { type: "Line"; length: number }
+  | { type: "Keyhole" }
+  | { type: "FAISector" }
+  | {
+      type: "Sector";
+      radius: number;
+      start_radial: number;
+      end_radial: number;
+      inner_radius?: number;
+    }
+  | { type: "SymmetricQuadrant"; radius?: number; angle?: number }
+  | { type: "CustomKeyhole"; radius?: number; angle?: number; inner_radius?: number }
+  | { type: "MatCylinder" }
+  | { type: "BGAStartSector" }
+  | { type: "BGAFixedCourse" }
+  | { type: "BGAEnhancedOption" };
+
+export interface Point {
+  type: PointType;
+  score_exit?: boolean;
+  waypoint: Waypoint;
+  observation_zone: ObservationZone;
+}
+
+export interface Task {
+  type: TaskType;
+  /** Minimum task time of AAT tasks, in seconds. */
+  aat_min_time?: number;
+  start_requires_arm?: boolean;
+  start_score_exit?: boolean;
+  /** Maximum start speed in m/s, `0` meaning no limit. */
+  start_max_speed?: number;
+  start_max_height?: Altitude;
+  /** Seconds since midnight UTC. */
+  start_open_time?: number;
+  /** Seconds since midnight UTC. */
+  start_close_time?: number;
+  finish_min_height?: Altitude;
+  fai_finish?: boolean;
+  /** Seconds. */
+  pev_start_wait_time?: number;
+  /** Seconds. */
+  pev_start_window?: number;
+  /** Written by XCSoar 6, kept for round trips. */
+  task_scored?: boolean;
+  min_points?: number;
+  max_points?: number;
+  homogeneous_tps?: boolean;
+  is_closed?: boolean;
+  points: Point[];
+}
@@ -0,0 +1,65 @@
+//! Runs under node with `cargo test --target wasm32-unknown-unknown`, using
+//! `wasm-bindgen-test-runner` as the test runner.
+
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_test::wasm_bindgen_test;
+use xcsoar_tasks_wasm::{distance, from_str, leg_distances, to_geojson, to_string_pretty};
+
+const RACING_TASK: &str = include_str!("../../../fixtures/racing-task.tsk");
+
+fn get(value: &JsValue, key: &str) -> JsValue {
+    js_sys::Reflect::get(value, &key.into()).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn parses_into_plain_objects() {
+    let task = from_str(RACING_TASK).unwrap();
+    assert_eq!(get(&task, "type"), "RT");
+
+    let points = get(&task, "points").unchecked_into::<js_sys::Array>();
+    assert_eq!(points.length(), 5);
+    let finish = points.get(4);
+    assert_eq!(get(&get(&finish, "waypoint"), "name"), "002Zielkreis");
+    assert_eq!(get(&get(&finish, "observation_zone"), "radius"), 2000.0);
+}
+
+#[wasm_bindgen_test]
+fn roundtrips_through_javascript() {
+    let task = from_str(RACING_TASK).unwrap();
+    let xml = to_string_pretty(&task).unwrap();
+    let expected = xcsoar_tasks::from_str(RACING_TASK).unwrap();
+    assert_eq!(xcsoar_tasks::from_str(&xml).unwrap(), expected);
+}
+
+#[wasm_bindgen_test]
+fn computes_distances() {
+    let task = from_str(RACING_TASK).unwrap();
+    let expected = xcsoar_tasks::from_str(RACING_TASK).unwrap();
+    assert_eq!(distance(&task, None).unwrap(), expected.distance());
+    assert_eq!(
+        leg_distances(&task, None).unwrap(),
+        expected.leg_distances()
+    );
+
+    assert_eq!(
+        distance(&task, Some("Wgs84".to_string())).unwrap(),
+        expected.distance_with_model(xcsoar_tasks::EarthModel::Wgs84)
+    );
+    assert!(distance(&task, Some("Flat".to_string())).is_err());
+}
+
+#[wasm_bindgen_test]
+fn exports_geojson() {
+    let task = from_str(RACING_TASK).unwrap();
+    let geojson = to_geojson(&task).unwrap();
+    assert_eq!(get(&geojson, "type"), "FeatureCollection");
+    let features = get(&geojson, "features").unchecked_into::<js_sys::Array>();
+    assert_eq!(features.length(), 6);
+}
+
+#[wasm_bindgen_test]
+fn rejects_invalid_xml() {
+    assert!(from_str("<Task").is_err());
+}
@@ -9,8 +9,8 @@ struct Args {
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let xml = std::fs::read_to_string(&args.path)?;
-    let task = xcsoar_tasks::from_str(&xml)?;
+    let bytes = std::fs::read(&args.path)?;
+    let task = xcsoar_tasks::from_slice(&bytes)?;
     println!("{task:#?}");
     Ok(())
 }
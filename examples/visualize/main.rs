@@ -12,8 +12,8 @@ struct Args {
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
-    let xml = std::fs::read_to_string(&args.path)?;
-    let task = xcsoar_tasks::from_str(&xml)?;
+    let bytes = std::fs::read(&args.path)?;
+    let task = xcsoar_tasks::from_slice(&bytes)?;
     let geojson = geojson::task_to_geojson(&task);
 
     let template = include_str!("template.html.j2");
@@ -0,0 +1,482 @@
+use anyhow::Context;
+use clap::{Parser, Subcommand, ValueEnum};
+use quick_xml::events::{BytesStart, Event};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::SystemTime;
+use xcsoar_tasks::coordinates::Notation;
+use xcsoar_tasks::format::{self, UnitSystem};
+use xcsoar_tasks::legs::{Declination, LegOptions};
+use xcsoar_tasks::magnetic::{MagneticModel, decimal_year};
+use xcsoar_tasks::{EarthModel, Profile, SerializeOptions, Task};
+
+/// Inspect, validate and convert XCSoar task files
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Show the task type, points, zones and leg distances
+    Info {
+        /// Path to the `.tsk` file
+        path: PathBuf,
+
+        /// Units used for distances, altitudes and speeds
+        #[arg(long, value_enum, default_value_t = Units::Metric)]
+        units: Units,
+    },
+
+    /// Show the bearing and distance of each leg, e.g. for a task sheet
+    Legs {
+        /// Path to the `.tsk` file
+        path: PathBuf,
+
+        /// Units used for distances
+        #[arg(long, value_enum, default_value_t = Units::Metric)]
+        units: Units,
+
+        /// Shape of the earth that distances and bearings are measured on
+        #[arg(long, value_enum, default_value_t = Earth::FaiSphere)]
+        earth_model: Earth,
+
+        /// Magnetic declination in degrees, positive to the east, instead of
+        /// the World Magnetic Model's
+        #[arg(long, allow_negative_numbers = true, conflicts_with_all = ["wmm", "year"])]
+        declination: Option<f64>,
+
+        /// World Magnetic Model coefficient file (`WMM.COF`) to use instead
+        /// of the bundled WMM2025
+        #[arg(long)]
+        wmm: Option<PathBuf>,
+
+        /// Decimal year for the magnetic model, defaults to now
+        #[arg(long)]
+        year: Option<f64>,
+    },
+
+    /// List the task points with their coordinates and elevation
+    Waypoints {
+        /// Path to the `.tsk` file
+        path: PathBuf,
+
+        /// Notation of the coordinates
+        #[arg(long, value_enum, default_value_t = Coordinates::Ddm)]
+        coordinates: Coordinates,
+
+        /// Units used for elevations
+        #[arg(long, value_enum, default_value_t = Units::Metric)]
+        units: Units,
+    },
+
+    /// Check that task files parse cleanly and follow the rules of their task type,
+    /// and warn about overlapping zones
+    Validate {
+        /// Paths to the `.tsk` files
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Shape of the earth that distances are measured on
+        #[arg(long, value_enum, default_value_t = Earth::FaiSphere)]
+        earth_model: Earth,
+    },
+
+    /// Convert a task into another format
+    Convert {
+        /// Path to the `.tsk` file
+        path: PathBuf,
+
+        /// Output format
+        #[arg(long, value_enum)]
+        to: OutputFormat,
+
+        /// Output file, defaults to stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Reformat task files in place, the way XCSoar writes them
+    Fmt {
+        /// Paths to the `.tsk` files
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        /// Don't write anything, fail if a file isn't formatted
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Show the differences between two tasks
+    Diff {
+        /// Path to the old `.tsk` file
+        old: PathBuf,
+
+        /// Path to the new `.tsk` file
+        new: PathBuf,
+
+        /// Shape of the earth that distances are measured on
+        #[arg(long, value_enum, default_value_t = Earth::FaiSphere)]
+        earth_model: Earth,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Units {
+    Metric,
+    Imperial,
+    Aviation,
+}
+
+impl From<Units> for UnitSystem {
+    fn from(units: Units) -> Self {
+        match units {
+            Units::Metric => UnitSystem::METRIC,
+            Units::Imperial => UnitSystem::IMPERIAL,
+            Units::Aviation => UnitSystem::AVIATION,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Earth {
+    FaiSphere,
+    Wgs84,
+}
+
+impl From<Earth> for EarthModel {
+    fn from(earth: Earth) -> Self {
+        match earth {
+            Earth::FaiSphere => EarthModel::FaiSphere,
+            Earth::Wgs84 => EarthModel::Wgs84,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Coordinates {
+    /// Decimal degrees, `51.06872°N 007.20575°E`
+    Decimal,
+    /// Degrees and decimal minutes, `5104.123N 00712.345E`
+    Ddm,
+    /// Degrees, minutes and seconds, `51°04'07"N 007°12'20"E`
+    Dms,
+    /// IGC file notation, `5104123N00712345E`
+    Igc,
+    /// UTM zone, easting and northing, `32U 374275 5658986`
+    Utm,
+}
+
+impl From<Coordinates> for Notation {
+    fn from(coordinates: Coordinates) -> Self {
+        match coordinates {
+            Coordinates::Decimal => Notation::DecimalDegrees,
+            Coordinates::Ddm => Notation::DegreesDecimalMinutes,
+            Coordinates::Dms => Notation::DegreesMinutesSeconds,
+            Coordinates::Igc => Notation::Igc,
+            Coordinates::Utm => Notation::Utm,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Cup,
+    Xctsk,
+    Gpx,
+    Kml,
+    Geojson,
+}
+
+fn main() -> anyhow::Result<ExitCode> {
+    let cli = Cli::parse();
+    match cli.command {
+        Command::Info { path, units } => info(&path, units.into()),
+        Command::Legs {
+            path,
+            units,
+            earth_model,
+            declination,
+            wmm,
+            year,
+        } => legs(
+            &path,
+            units.into(),
+            earth_model.into(),
+            declination,
+            wmm.as_deref(),
+            year,
+        ),
+        Command::Waypoints {
+            path,
+            coordinates,
+            units,
+        } => waypoints(&path, coordinates.into(), units.into()),
+        Command::Validate { paths, earth_model } => validate(&paths, earth_model.into()),
+        Command::Convert { path, to, output } => convert(&path, to, output.as_deref()),
+        Command::Fmt { paths, check } => fmt(&paths, check),
+        Command::Diff {
+            old,
+            new,
+            earth_model,
+        } => diff(&old, &new, earth_model.into()),
+    }
+}
+
+fn read(path: &Path) -> anyhow::Result<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))
+}
+
+fn read_task(path: &Path) -> anyhow::Result<Task> {
+    let bytes = read(path)?;
+    xcsoar_tasks::from_slice(&bytes).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+fn read_magnetic_model(path: &Path) -> anyhow::Result<MagneticModel> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    MagneticModel::from_cof(&text).with_context(|| format!("failed to parse {}", path.display()))
+}
+
+fn info(path: &Path, units: UnitSystem) -> anyhow::Result<ExitCode> {
+    let task = read_task(path)?;
+    print!("{}", format::task(&task, units));
+
+    if let Some(speed) = task.start_max_speed.filter(|s| s.meters_per_second() > 0.0) {
+        println!("Start max speed: {}", format::speed(speed, units));
+    }
+    if let Some(height) = task.start_max_height.filter(|h| h.meters > 0) {
+        println!("Start max height: {}", format::altitude(height, units));
+    }
+    if let Some(open) = task.start_open_time {
+        println!("Start opens: {open} UTC");
+    }
+    if let Some(close) = task.start_close_time {
+        println!("Start closes: {close} UTC");
+    }
+    if let Some(height) = task.finish_min_height.filter(|h| h.meters > 0) {
+        println!("Finish min height: {}", format::altitude(height, units));
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn legs(
+    path: &Path,
+    units: UnitSystem,
+    earth_model: EarthModel,
+    declination: Option<f64>,
+    wmm: Option<&Path>,
+    year: Option<f64>,
+) -> anyhow::Result<ExitCode> {
+    let task = read_task(path)?;
+    let model = wmm.map(read_magnetic_model).transpose()?;
+    let declination = match declination {
+        Some(declination) => Declination::Fixed(declination),
+        None => Declination::Model(
+            model.as_ref().unwrap_or_else(|| MagneticModel::wmm()),
+            year.unwrap_or_else(|| decimal_year(SystemTime::now())),
+        ),
+    };
+    let options = LegOptions {
+        earth_model,
+        declination: Some(declination),
+    };
+    print!(
+        "{}",
+        format::legs(&task, &task.legs_with_options(options), units)
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+fn waypoints(path: &Path, notation: Notation, units: UnitSystem) -> anyhow::Result<ExitCode> {
+    let task = read_task(path)?;
+    print!("{}", format::waypoints(&task, notation, units));
+    Ok(ExitCode::SUCCESS)
+}
+
+fn validate(paths: &[PathBuf], earth_model: EarthModel) -> anyhow::Result<ExitCode> {
+    let mut valid = true;
+    for path in paths {
+        let bytes = read(path)?;
+        match xcsoar_tasks::from_slice(&bytes) {
+            Ok(task) => {
+                let violations = task.validate_with_model(earth_model);
+                for violation in &violations {
+                    println!("{}: {violation}", path.display());
+                }
+                valid &= violations.is_empty();
+
+                // Badly placed zones don't stop XCSoar from flying the task
+                for warning in task.lint_with_model(earth_model) {
+                    println!("{}: warning: {warning}", path.display());
+                }
+
+                // Values the strict parser reads without complaint, like
+                // unknown height references. Anything it rejects has
+                // already been reported above.
+                if let Ok((_, diagnostics)) = xcsoar_tasks::lenient::from_slice(&bytes) {
+                    for diagnostic in &diagnostics {
+                        println!("{}: {diagnostic}", path.display());
+                    }
+                    valid &= diagnostics.is_empty();
+                }
+            }
+            Err(error) => {
+                println!("{}: {error}", path.display());
+                valid = false;
+            }
+        }
+    }
+
+    Ok(if valid {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn convert(path: &Path, to: OutputFormat, output: Option<&Path>) -> anyhow::Result<ExitCode> {
+    let task = read_task(path)?;
+    let converted = match to {
+        OutputFormat::Cup => xcsoar_tasks::cup::to_string(&task),
+        OutputFormat::Xctsk => xcsoar_tasks::xctsk::to_string(&task),
+        OutputFormat::Gpx => xcsoar_tasks::gpx::to_string(&task),
+        OutputFormat::Kml => xcsoar_tasks::kml::to_string(&task),
+        OutputFormat::Geojson => xcsoar_tasks::geojson::to_string(&task),
+    };
+
+    match output {
+        Some(output) => std::fs::write(output, converted)
+            .with_context(|| format!("failed to write {}", output.display()))?,
+        None => print!("{converted}"),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn fmt(paths: &[PathBuf], check: bool) -> anyhow::Result<ExitCode> {
+    let mut formatted = true;
+    for path in paths {
+        let original = read(path)?;
+        let text = xcsoar_tasks::decode(&original)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        let task = xcsoar_tasks::from_str(&text)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        // Keep the XCSoar version, line endings and declaration of the file.
+        // Only XCSoar 6 indented with tabs.
+        let profile = if text.contains("\n\t<") {
+            Profile::XCSoar6
+        } else {
+            Profile::XCSoar
+        };
+        let options = SerializeOptions {
+            xml_declaration: text.trim_start_matches('\u{feff}').starts_with("<?xml"),
+            profile,
+            crlf: text.contains("\r\n"),
+            ..Default::default()
+        };
+        let mut reformatted = String::new();
+        xcsoar_tasks::to_writer_with_options(&mut reformatted, &task, options)?;
+        if original == reformatted.as_bytes() {
+            continue;
+        }
+
+        let lost = lost_content(&text, &reformatted)
+            .with_context(|| format!("failed to parse {}", path.display()))?;
+        if !lost.is_empty() {
+            println!(
+                "{} can't be formatted without losing {}",
+                path.display(),
+                lost.join(", ")
+            );
+            formatted = false;
+        } else if check {
+            println!("{} is not formatted", path.display());
+            formatted = false;
+        } else {
+            std::fs::write(path, reformatted)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+    }
+
+    Ok(if formatted {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+/// Lists elements, attributes, comments and text of `original` that don't
+/// survive in `reformatted`, e.g. attributes the task model doesn't know.
+fn lost_content(original: &str, reformatted: &str) -> anyhow::Result<Vec<String>> {
+    let reformatted = content(reformatted)?;
+    Ok(content(original)?
+        .into_iter()
+        .filter(|(name, count)| reformatted.get(name).copied().unwrap_or_default() < *count)
+        .map(|(name, _)| name)
+        .collect())
+}
+
+/// Counts the element paths, attribute names, comments and non-blank text
+/// nodes of `xml`.
+fn content(xml: &str) -> anyhow::Result<BTreeMap<String, usize>> {
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let mut path = Vec::new();
+    let mut content = BTreeMap::new();
+    let mut count = |name: String| *content.entry(name).or_insert(0) += 1;
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) => {
+                path.push(String::from_utf8_lossy(element.name().as_ref()).into_owned());
+                count_element(&path, &element, &mut count)?;
+            }
+            Event::Empty(element) => {
+                path.push(String::from_utf8_lossy(element.name().as_ref()).into_owned());
+                count_element(&path, &element, &mut count)?;
+                path.pop();
+            }
+            Event::End(_) => {
+                path.pop();
+            }
+            Event::Comment(_) => count("comments".to_string()),
+            Event::Text(text) if !text.iter().all(u8::is_ascii_whitespace) => {
+                count(format!("text in {}", path.join("/")));
+            }
+            Event::CData(_) => count(format!("text in {}", path.join("/"))),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(content)
+}
+
+fn count_element(
+    path: &[String],
+    element: &BytesStart,
+    count: &mut impl FnMut(String),
+) -> anyhow::Result<()> {
+    let element_path = path.join("/");
+    for attribute in element.attributes() {
+        let key = attribute?.key;
+        count(format!(
+            "{element_path}@{}",
+            String::from_utf8_lossy(key.as_ref())
+        ));
+    }
+    count(element_path);
+    Ok(())
+}
+
+fn diff(old: &Path, new: &Path, earth_model: EarthModel) -> anyhow::Result<ExitCode> {
+    let diff = xcsoar_tasks::diff::diff_with_model(&read_task(old)?, &read_task(new)?, earth_model);
+    if diff.is_empty() {
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    print!("{diff}");
+    Ok(ExitCode::FAILURE)
+}
@@ -0,0 +1,224 @@
+//! Low-allocation parsing for bulk processing.
+//!
+//! [`Task`] mirrors [`crate::Task`], but waypoint names, ids and comments
+//! borrow from the input instead of being copied into new `String`s. Only
+//! values containing XML escapes like `&amp;` are allocated.
+//!
+//! ```
+//! let xml = std::fs::read_to_string("fixtures/racing-task.tsk").unwrap();
+//! let task = xcsoar_tasks::borrowed::from_str(&xml).unwrap();
+//! for point in &task.points {
+//!     println!("{}", point.waypoint.name);
+//! }
+//!
+//! // Convert to the owned model once the input goes away
+//! let task: xcsoar_tasks::Task = task.into_owned();
+//! ```
+
+use crate::{
+    Altitude, Location, ObservationZone, ParseError, PointType, RangeError, Speed, TaskRangeError,
+    TaskType, TaskXml, TimeOfDay, checks, de_opt_bool, de_opt_f64, seconds_to_duration,
+    to_altitude,
+};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use std::borrow::Cow;
+use std::time::Duration;
+
+/// Parses a task file, borrowing strings from `xml`.
+///
+/// Unlike [`crate::from_slice`], this requires the input to be decoded
+/// already.
+pub fn from_str(xml: &str) -> Result<Task<'_>, ParseError> {
+    let task = Task::from_xml(quick_xml::de::from_str(xml)?).map_err(TaskRangeError::Task)?;
+    task.check()?;
+    Ok(task)
+}
+
+/// A task borrowing its strings from the parsed input. See [`crate::Task`]
+/// for the meaning of the fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Task<'a> {
+    pub task_type: TaskType,
+    pub aat_min_time: Option<Duration>,
+    pub start_requires_arm: Option<bool>,
+    pub start_score_exit: Option<bool>,
+    pub start_max_speed: Option<Speed>,
+    pub start_max_height: Option<Altitude>,
+    pub start_open_time: Option<TimeOfDay>,
+    pub start_close_time: Option<TimeOfDay>,
+    pub finish_min_height: Option<Altitude>,
+    pub fai_finish: Option<bool>,
+    pub pev_start_wait_time: Option<Duration>,
+    pub pev_start_window: Option<Duration>,
+    pub task_scored: Option<bool>,
+    pub min_points: Option<u32>,
+    pub max_points: Option<u32>,
+    pub homogeneous_tps: Option<bool>,
+    pub is_closed: Option<bool>,
+    pub points: Vec<Point<'a>>,
+}
+
+impl Task<'_> {
+    /// See [`crate::Task::check`].
+    pub fn check(&self) -> Result<(), TaskRangeError> {
+        checks::task(
+            self.start_max_speed.map(|speed| speed.meters_per_second()),
+            self.points.iter().map(|point| {
+                let waypoint = &point.waypoint;
+                let result = checks::point(
+                    waypoint.altitude,
+                    &waypoint.location,
+                    &point.observation_zone,
+                );
+                (waypoint.name.as_ref(), result)
+            }),
+        )
+    }
+
+    pub fn into_owned(self) -> crate::Task {
+        crate::Task {
+            task_type: self.task_type,
+            aat_min_time: self.aat_min_time,
+            start_requires_arm: self.start_requires_arm,
+            start_score_exit: self.start_score_exit,
+            start_max_speed: self.start_max_speed,
+            start_max_height: self.start_max_height,
+            start_open_time: self.start_open_time,
+            start_close_time: self.start_close_time,
+            finish_min_height: self.finish_min_height,
+            fai_finish: self.fai_finish,
+            pev_start_wait_time: self.pev_start_wait_time,
+            pev_start_window: self.pev_start_window,
+            task_scored: self.task_scored,
+            min_points: self.min_points,
+            max_points: self.max_points,
+            homogeneous_tps: self.homogeneous_tps,
+            is_closed: self.is_closed,
+            points: self.points.into_iter().map(Point::into_owned).collect(),
+        }
+    }
+}
+
+/// Deserializing rejects out of range values like [`from_str`].
+impl<'de: 'a, 'a> Deserialize<'de> for Task<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let task = Task::from_xml(TaskXml::deserialize(deserializer)?).map_err(D::Error::custom)?;
+        task.check().map_err(D::Error::custom)?;
+        Ok(task)
+    }
+}
+
+impl<'a> Task<'a> {
+    fn from_xml(xml: TaskXml<'a, Point<'a>>) -> Result<Task<'a>, RangeError> {
+        Ok(Task {
+            task_type: xml.task_type,
+            aat_min_time: xml.aat_min_time.map(seconds_to_duration),
+            start_requires_arm: xml.start_requires_arm,
+            start_score_exit: xml.start_score_exit,
+            start_max_speed: xml.start_max_speed.map(Speed::from_meters_per_second),
+            start_max_height: to_altitude(xml.start_max_height, xml.start_max_height_ref),
+            start_open_time: checks::time_of_day("start_open_time", xml.start_open_time)?,
+            start_close_time: checks::time_of_day("start_close_time", xml.start_close_time)?,
+            finish_min_height: to_altitude(xml.finish_min_height, xml.finish_min_height_ref),
+            fai_finish: xml.fai_finish,
+            pev_start_wait_time: xml.pev_start_wait_time.map(seconds_to_duration),
+            pev_start_window: xml.pev_start_window.map(seconds_to_duration),
+            task_scored: xml.task_scored,
+            min_points: xml.min_points,
+            max_points: xml.max_points,
+            homogeneous_tps: xml.homogeneous_tps,
+            is_closed: xml.is_closed,
+            points: xml.points.into_owned(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Point<'a> {
+    #[serde(rename = "@type")]
+    pub point_type: PointType,
+
+    #[serde(rename = "@score_exit", default, deserialize_with = "de_opt_bool")]
+    pub score_exit: Option<bool>,
+
+    #[serde(rename = "Waypoint", borrow)]
+    pub waypoint: Waypoint<'a>,
+
+    #[serde(rename = "ObservationZone")]
+    pub observation_zone: ObservationZone,
+}
+
+impl Point<'_> {
+    pub fn into_owned(self) -> crate::Point {
+        crate::Point {
+            point_type: self.point_type,
+            score_exit: self.score_exit,
+            waypoint: self.waypoint.into_owned(),
+            observation_zone: self.observation_zone,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Waypoint<'a> {
+    #[serde(rename = "@name", borrow)]
+    pub name: Cow<'a, str>,
+
+    #[serde(rename = "@altitude", default, deserialize_with = "de_opt_f64")]
+    pub altitude: Option<f64>,
+
+    #[serde(rename = "@id", default, borrow)]
+    pub id: Option<Cow<'a, str>>,
+
+    #[serde(rename = "@comment", default, borrow)]
+    pub comment: Option<Cow<'a, str>>,
+
+    #[serde(rename = "Location")]
+    pub location: Location,
+}
+
+impl Waypoint<'_> {
+    pub fn into_owned(self) -> crate::Waypoint {
+        crate::Waypoint {
+            name: self.name.into_owned(),
+            altitude: self.altitude,
+            id: self.id.map(Cow::into_owned),
+            comment: self.comment.map(Cow::into_owned),
+            location: self.location,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_owned_parse() {
+        for xml in [
+            include_str!("../fixtures/aat-task.tsk"),
+            include_str!("../fixtures/all-oz-types.tsk"),
+            include_str!("../fixtures/fai-task.tsk"),
+            include_str!("../fixtures/racing-task.tsk"),
+        ] {
+            let borrowed = from_str(xml).unwrap();
+            assert_eq!(borrowed.into_owned(), crate::from_str(xml).unwrap());
+        }
+    }
+
+    #[test]
+    fn borrows_unescaped_strings() {
+        let xml = include_str!("../fixtures/racing-task.tsk")
+            .replace("021Bergheim", "021Berg &amp; Heim");
+        let task = from_str(&xml).unwrap();
+
+        let names = task
+            .points
+            .iter()
+            .map(|point| &point.waypoint.name)
+            .collect::<Vec<_>>();
+        assert!(matches!(names[0], Cow::Borrowed("095Monheim AB Dreieck")));
+        assert!(matches!(names[3], Cow::Owned(name) if name == "021Berg & Heim"));
+    }
+}
@@ -0,0 +1,384 @@
+//! Range checks ensuring that a parsed task is geometrically meaningful.
+//!
+//! `str::parse::<f64>` accepts `NaN` and `inf`, and the XML format puts no
+//! bounds on coordinates or radii, so every parser runs these checks before
+//! handing out a [`Task`].
+
+use crate::{Location, ObservationZone, Point, Task, TimeOfDay};
+
+/// A number outside the range allowed for it.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("{field} is {value}, expected {expected}")]
+pub struct RangeError {
+    /// Name of the attribute, e.g. `latitude` or `radius`.
+    pub field: &'static str,
+    pub value: f64,
+    /// Description of the allowed range.
+    pub expected: &'static str,
+}
+
+/// A [`RangeError`] together with where in the task it was found.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum TaskRangeError {
+    #[error("{0}")]
+    Task(RangeError),
+
+    #[error("point {} ({name}): {error}", index + 1)]
+    Point {
+        /// Zero-based position in `Task::points`.
+        index: usize,
+        name: String,
+        error: RangeError,
+    },
+}
+
+impl Location {
+    /// Creates a location, checking that latitude and longitude are finite
+    /// and within ±90° and ±180°.
+    pub fn new(latitude: f64, longitude: f64) -> Result<Location, RangeError> {
+        Ok(Location {
+            longitude: self::longitude("longitude", longitude)?,
+            latitude: self::latitude("latitude", latitude)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), RangeError> {
+        longitude("longitude", self.longitude)?;
+        latitude("latitude", self.latitude)?;
+        Ok(())
+    }
+}
+
+impl ObservationZone {
+    /// Checks that radii and lengths are positive, angles are finite and an
+    /// inner radius lies within the outer one.
+    pub fn check(&self) -> Result<(), RangeError> {
+        match *self {
+            ObservationZone::Cylinder { radius } => {
+                positive("radius", radius)?;
+            }
+            ObservationZone::Line { length } => {
+                positive("length", length)?;
+            }
+            ObservationZone::Sector {
+                radius,
+                start_radial,
+                end_radial,
+                inner_radius,
+            } => {
+                positive("radius", radius)?;
+                finite("start_radial", start_radial)?;
+                finite("end_radial", end_radial)?;
+                if let Some(inner_radius) = inner_radius {
+                    self::inner_radius(inner_radius, Some(radius))?;
+                }
+            }
+            ObservationZone::SymmetricQuadrant { radius, angle } => {
+                radius
+                    .map(|radius| positive("radius", radius))
+                    .transpose()?;
+                angle.map(|angle| self::angle("angle", angle)).transpose()?;
+            }
+            ObservationZone::CustomKeyhole {
+                radius,
+                angle,
+                inner_radius,
+            } => {
+                radius
+                    .map(|radius| positive("radius", radius))
+                    .transpose()?;
+                angle.map(|angle| self::angle("angle", angle)).transpose()?;
+                if let Some(inner_radius) = inner_radius {
+                    self::inner_radius(inner_radius, radius)?;
+                }
+            }
+            ObservationZone::Keyhole
+            | ObservationZone::FAISector
+            | ObservationZone::MatCylinder
+            | ObservationZone::BGAStartSector
+            | ObservationZone::BGAFixedCourse
+            | ObservationZone::BGAEnhancedOption => {}
+        }
+        Ok(())
+    }
+}
+
+impl Point {
+    pub fn check(&self) -> Result<(), RangeError> {
+        point(
+            self.waypoint.altitude,
+            &self.waypoint.location,
+            &self.observation_zone,
+        )
+    }
+}
+
+impl Task {
+    /// Checks every number in the task, reporting the first one that's out
+    /// of range.
+    ///
+    /// Tasks returned by the parsers of this crate always pass this check;
+    /// it's meant for tasks built or modified in code.
+    pub fn check(&self) -> Result<(), TaskRangeError> {
+        task(
+            self.start_max_speed.map(|speed| speed.meters_per_second()),
+            self.points
+                .iter()
+                .map(|point| (point.waypoint.name.as_str(), point.check())),
+        )
+    }
+}
+
+/// Shared by [`Task::check`] and the borrowed model, which has its own point
+/// type.
+pub(crate) fn task<'a>(
+    start_max_speed: Option<f64>,
+    points: impl IntoIterator<Item = (&'a str, Result<(), RangeError>)>,
+) -> Result<(), TaskRangeError> {
+    if let Some(speed) = start_max_speed {
+        non_negative("start_max_speed", speed).map_err(TaskRangeError::Task)?;
+    }
+    for (index, (name, result)) in points.into_iter().enumerate() {
+        result.map_err(|error| TaskRangeError::Point {
+            index,
+            name: name.to_string(),
+            error,
+        })?;
+    }
+    Ok(())
+}
+
+pub(crate) fn point(
+    altitude: Option<f64>,
+    location: &Location,
+    zone: &ObservationZone,
+) -> Result<(), RangeError> {
+    altitude
+        .map(|altitude| finite("altitude", altitude))
+        .transpose()?;
+    location.check()?;
+    zone.check()
+}
+
+/// Converts seconds since midnight, which the file formats don't bound, to
+/// a time of day.
+pub(crate) fn time_of_day(
+    field: &'static str,
+    seconds: Option<u32>,
+) -> Result<Option<TimeOfDay>, RangeError> {
+    seconds
+        .map(|seconds| {
+            TimeOfDay::from_seconds(seconds).ok_or(RangeError {
+                field,
+                value: f64::from(seconds),
+                expected: "0 to 86399 seconds",
+            })
+        })
+        .transpose()
+}
+
+// The functions below have the same signature so that the lenient parser
+// can pass them around when checking individual attributes.
+
+pub(crate) fn latitude(field: &'static str, value: f64) -> Result<f64, RangeError> {
+    check(field, value, (-90.0..=90.0).contains(&value), "-90 to 90")
+}
+
+pub(crate) fn longitude(field: &'static str, value: f64) -> Result<f64, RangeError> {
+    check(
+        field,
+        value,
+        (-180.0..=180.0).contains(&value),
+        "-180 to 180",
+    )
+}
+
+pub(crate) fn finite(field: &'static str, value: f64) -> Result<f64, RangeError> {
+    check(field, value, value.is_finite(), "a finite number")
+}
+
+pub(crate) fn positive(field: &'static str, value: f64) -> Result<f64, RangeError> {
+    check(
+        field,
+        value,
+        value.is_finite() && value > 0.0,
+        "a positive number",
+    )
+}
+
+pub(crate) fn non_negative(field: &'static str, value: f64) -> Result<f64, RangeError> {
+    check(
+        field,
+        value,
+        value.is_finite() && value >= 0.0,
+        "zero or a positive number",
+    )
+}
+
+pub(crate) fn angle(field: &'static str, value: f64) -> Result<f64, RangeError> {
+    check(
+        field,
+        value,
+        value > 0.0 && value <= 360.0,
+        "an angle above 0 and up to 360",
+    )
+}
+
+/// The inner radius may be zero, but must stay below the outer radius if
+/// that is known.
+pub(crate) fn inner_radius(value: f64, radius: Option<f64>) -> Result<f64, RangeError> {
+    non_negative("inner_radius", value)?;
+    match radius {
+        Some(radius) => check(
+            "inner_radius",
+            value,
+            value < radius,
+            "less than the radius",
+        ),
+        None => Ok(value),
+    }
+}
+
+fn check(
+    field: &'static str,
+    value: f64,
+    valid: bool,
+    expected: &'static str,
+) -> Result<f64, RangeError> {
+    if valid {
+        Ok(value)
+    } else {
+        Err(RangeError {
+            field,
+            value,
+            expected,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(xml: &str) -> String {
+        crate::from_str(xml).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn location_new() {
+        assert!(Location::new(47.5, 8.5).is_ok());
+        assert_eq!(
+            Location::new(90.5, 8.5).unwrap_err().to_string(),
+            "latitude is 90.5, expected -90 to 90"
+        );
+        assert_eq!(
+            Location::new(47.5, f64::NAN).unwrap_err().to_string(),
+            "longitude is NaN, expected -180 to 180"
+        );
+    }
+
+    #[test]
+    fn rejects_non_finite_coordinates() {
+        let xml = include_str!("../fixtures/racing-task.tsk");
+        assert_eq!(
+            parse_error(&replace_attribute(xml, 1, "latitude", "NaN")),
+            "point 2 (084Linnich Kontrollpunkt): latitude is NaN, expected -90 to 90"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_zone_sizes() {
+        let xml = include_str!("../fixtures/racing-task.tsk");
+        assert_eq!(
+            parse_error(&replace_attribute(xml, 0, "length", "inf")),
+            "point 1 (095Monheim AB Dreieck): length is inf, expected a positive number"
+        );
+        assert_eq!(
+            parse_error(&replace_attribute(xml, 0, "radius", "-500")),
+            "point 5 (002Zielkreis): radius is -500, expected a positive number"
+        );
+    }
+
+    #[test]
+    fn rejects_inner_radius_beyond_radius() {
+        let zone = ObservationZone::Sector {
+            radius: 1000.0,
+            start_radial: 0.0,
+            end_radial: 90.0,
+            inner_radius: Some(1000.0),
+        };
+        assert_eq!(
+            zone.check().unwrap_err().to_string(),
+            "inner_radius is 1000, expected less than the radius"
+        );
+    }
+
+    #[test]
+    fn rejects_times_past_midnight() {
+        let xml = include_str!("../fixtures/racing-task.tsk").replacen(
+            "<Task ",
+            r#"<Task start_close_time="86400" "#,
+            1,
+        );
+        let expected = "start_close_time is 86400, expected 0 to 86399 seconds";
+        assert_eq!(parse_error(&xml), expected);
+        assert_eq!(
+            crate::borrowed::from_str(&xml).unwrap_err().to_string(),
+            expected
+        );
+
+        let (task, diagnostics) = crate::lenient::from_str(&xml).unwrap();
+        assert_eq!(task.start_close_time, None);
+        assert_eq!(diagnostics.len(), 1);
+
+        let mut json = crate::json::to_value(
+            &crate::from_str(include_str!("../fixtures/racing-task.tsk")).unwrap(),
+        );
+        json["start_open_time"] = 90_000.into();
+        assert_eq!(
+            crate::json::from_value(json).unwrap_err().to_string(),
+            "start_open_time is 90000, expected 0 to 86399 seconds"
+        );
+    }
+
+    #[test]
+    fn deserialize_checks_ranges() {
+        let xml = replace_attribute(
+            include_str!("../fixtures/racing-task.tsk"),
+            1,
+            "latitude",
+            "95",
+        );
+        let expected = "point 2 (084Linnich Kontrollpunkt): latitude is 95, expected -90 to 90";
+        let error = quick_xml::de::from_str::<Task>(&xml).unwrap_err();
+        assert!(matches!(error, quick_xml::DeError::Custom(message) if message == expected));
+        let error = quick_xml::de::from_str::<crate::borrowed::Task>(&xml).unwrap_err();
+        assert!(matches!(error, quick_xml::DeError::Custom(message) if message == expected));
+
+        // The parsers still report range errors as such
+        assert!(matches!(
+            crate::from_str(&xml),
+            Err(crate::ParseError::Range(_))
+        ));
+    }
+
+    #[test]
+    fn fixtures_pass() {
+        for xml in [
+            include_str!("../fixtures/aat-task.tsk"),
+            include_str!("../fixtures/all-oz-types.tsk"),
+            include_str!("../fixtures/fai-task.tsk"),
+            include_str!("../fixtures/racing-task.tsk"),
+        ] {
+            crate::from_str(xml).unwrap().check().unwrap();
+        }
+    }
+
+    /// Replaces the value of the `nth` occurrence of `attribute`.
+    fn replace_attribute(xml: &str, nth: usize, attribute: &str, value: &str) -> String {
+        let pattern = format!(" {attribute}=\"");
+        let start = xml.match_indices(&pattern).nth(nth).unwrap().0 + pattern.len();
+        let end = start + xml[start..].find('"').unwrap();
+        format!("{}{value}{}", &xml[..start], &xml[end..])
+    }
+}
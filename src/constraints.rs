@@ -0,0 +1,654 @@
+//! Rules for each task type, following XCSoar's task factories.
+//!
+//! XCSoar's task editor only offers the point types, zone types and number
+//! of points that are valid for the selected task type. [`TaskType::constraints`]
+//! exposes the same tables as data, and [`Task::validate`] checks a task
+//! against them.
+//!
+//! ```
+//! use xcsoar_tasks::TaskType;
+//! use xcsoar_tasks::constraints::Role;
+//!
+//! let constraints = TaskType::FAITriangle.constraints();
+//! assert_eq!((constraints.min_points, constraints.max_points), (4, 4));
+//! for kind in constraints.allowed(Role::Intermediate) {
+//!     println!("{:?} {:?}", kind.point_type, kind.zone);
+//! }
+//! ```
+
+use crate::format::{self, UnitSystem};
+use crate::{EarthModel, Point, PointType, Task, TaskType, ZoneKind};
+use std::fmt;
+
+/// The position of a point within the task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// The first point. Optional starts share the rules of the start.
+    Start,
+    /// Any point between start and finish.
+    Intermediate,
+    /// The last point.
+    Finish,
+}
+
+/// A combination of point type and zone type, like XCSoar's
+/// `TaskPointFactoryType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PointKind {
+    pub point_type: PointType,
+    pub zone: ZoneKind,
+}
+
+/// What a task of a given type may contain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskConstraints {
+    /// Fewest points including start and finish, not counting optional
+    /// starts.
+    pub min_points: usize,
+
+    /// Most points including start and finish, not counting optional starts.
+    pub max_points: usize,
+
+    /// All intermediate points must use the same zone type.
+    pub homogeneous_turnpoints: bool,
+
+    /// The finish must be at the location of the start.
+    pub closed: bool,
+
+    /// Allowed kinds for each role. The first entry is the default that
+    /// [`Task::repair`] falls back to.
+    pub start: &'static [PointKind],
+    pub intermediate: &'static [PointKind],
+    pub finish: &'static [PointKind],
+}
+
+impl TaskConstraints {
+    /// The point and zone type combinations allowed for `role`.
+    pub fn allowed(&self, role: Role) -> &'static [PointKind] {
+        match role {
+            Role::Start => self.start,
+            Role::Intermediate => self.intermediate,
+            Role::Finish => self.finish,
+        }
+    }
+
+    /// Zone types allowed for a point of `point_type` in `role`, e.g. to fill
+    /// a selection list in an editor.
+    pub fn zones(&self, role: Role, point_type: PointType) -> impl Iterator<Item = ZoneKind> {
+        self.allowed(role)
+            .iter()
+            .filter(move |kind| kind.point_type == point_type)
+            .map(|kind| kind.zone)
+    }
+
+    pub fn is_allowed(&self, role: Role, point_type: PointType, zone: ZoneKind) -> bool {
+        self.allowed(role).contains(&PointKind { point_type, zone })
+    }
+}
+
+const fn start(zone: ZoneKind) -> PointKind {
+    PointKind {
+        point_type: PointType::Start,
+        zone,
+    }
+}
+
+const fn turn(zone: ZoneKind) -> PointKind {
+    PointKind {
+        point_type: PointType::Turn,
+        zone,
+    }
+}
+
+const fn area(zone: ZoneKind) -> PointKind {
+    PointKind {
+        point_type: PointType::Area,
+        zone,
+    }
+}
+
+const fn finish(zone: ZoneKind) -> PointKind {
+    PointKind {
+        point_type: PointType::Finish,
+        zone,
+    }
+}
+
+const STARTS: &[PointKind] = &[
+    start(ZoneKind::Line),
+    start(ZoneKind::Cylinder),
+    start(ZoneKind::FAISector),
+    start(ZoneKind::BGAStartSector),
+];
+
+const FINISHES: &[PointKind] = &[
+    finish(ZoneKind::Cylinder),
+    finish(ZoneKind::Line),
+    finish(ZoneKind::FAISector),
+];
+
+const RACING_TURNPOINTS: &[PointKind] = &[
+    turn(ZoneKind::Cylinder),
+    turn(ZoneKind::FAISector),
+    turn(ZoneKind::Keyhole),
+    turn(ZoneKind::BGAFixedCourse),
+    turn(ZoneKind::BGAEnhancedOption),
+    turn(ZoneKind::SymmetricQuadrant),
+    turn(ZoneKind::CustomKeyhole),
+];
+
+const AREAS: &[PointKind] = &[
+    area(ZoneKind::Cylinder),
+    area(ZoneKind::Sector),
+    area(ZoneKind::CustomKeyhole),
+];
+
+const MIXED_INTERMEDIATES: &[PointKind] = &[
+    turn(ZoneKind::Cylinder),
+    turn(ZoneKind::FAISector),
+    turn(ZoneKind::Keyhole),
+    turn(ZoneKind::BGAFixedCourse),
+    turn(ZoneKind::BGAEnhancedOption),
+    turn(ZoneKind::SymmetricQuadrant),
+    turn(ZoneKind::CustomKeyhole),
+    area(ZoneKind::Cylinder),
+    area(ZoneKind::Sector),
+    area(ZoneKind::CustomKeyhole),
+];
+
+const FAI_STARTS: &[PointKind] = &[
+    start(ZoneKind::Line),
+    start(ZoneKind::Cylinder),
+    start(ZoneKind::FAISector),
+];
+
+const FAI_TURNPOINTS: &[PointKind] = &[turn(ZoneKind::FAISector), turn(ZoneKind::Cylinder)];
+
+const RACING: TaskConstraints = TaskConstraints {
+    min_points: 2,
+    max_points: 13,
+    homogeneous_turnpoints: false,
+    closed: false,
+    start: STARTS,
+    intermediate: RACING_TURNPOINTS,
+    finish: FINISHES,
+};
+
+const AAT: TaskConstraints = TaskConstraints {
+    intermediate: AREAS,
+    ..RACING
+};
+
+const MAT: TaskConstraints = TaskConstraints {
+    intermediate: &[area(ZoneKind::MatCylinder)],
+    ..RACING
+};
+
+const MIXED: TaskConstraints = TaskConstraints {
+    intermediate: MIXED_INTERMEDIATES,
+    ..RACING
+};
+
+const FAI_GENERAL: TaskConstraints = TaskConstraints {
+    min_points: 3,
+    max_points: 10,
+    homogeneous_turnpoints: true,
+    closed: true,
+    start: FAI_STARTS,
+    intermediate: FAI_TURNPOINTS,
+    finish: FINISHES,
+};
+
+const FAI_TRIANGLE: TaskConstraints = TaskConstraints {
+    min_points: 4,
+    max_points: 4,
+    ..FAI_GENERAL
+};
+
+const FAI_OUT_AND_RETURN: TaskConstraints = TaskConstraints {
+    min_points: 3,
+    max_points: 3,
+    ..FAI_GENERAL
+};
+
+const FAI_GOAL: TaskConstraints = TaskConstraints {
+    min_points: 2,
+    max_points: 2,
+    closed: false,
+    ..FAI_GENERAL
+};
+
+const TOURING: TaskConstraints = TaskConstraints {
+    min_points: 2,
+    max_points: 10,
+    homogeneous_turnpoints: true,
+    closed: false,
+    start: &[start(ZoneKind::Cylinder)],
+    intermediate: &[turn(ZoneKind::Cylinder)],
+    finish: &[finish(ZoneKind::Cylinder)],
+};
+
+impl TaskType {
+    pub fn constraints(self) -> &'static TaskConstraints {
+        match self {
+            TaskType::AAT => &AAT,
+            TaskType::RT => &RACING,
+            TaskType::FAIGeneral => &FAI_GENERAL,
+            TaskType::FAITriangle => &FAI_TRIANGLE,
+            TaskType::FAIOR => &FAI_OUT_AND_RETURN,
+            TaskType::FAIGoal => &FAI_GOAL,
+            TaskType::MAT => &MAT,
+            TaskType::Mixed => &MIXED,
+            TaskType::Touring => &TOURING,
+        }
+    }
+}
+
+/// A way in which a task breaks the rules of its type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    TooFewPoints {
+        task_type: TaskType,
+        count: usize,
+        min: usize,
+    },
+
+    TooManyPoints {
+        task_type: TaskType,
+        count: usize,
+        max: usize,
+    },
+
+    /// The combination of point type and zone type isn't allowed for the
+    /// point's role. Indices are zero-based positions in `Task::points`.
+    PointNotAllowed {
+        task_type: TaskType,
+        index: usize,
+        name: String,
+        role: Role,
+        point_type: PointType,
+        zone: ZoneKind,
+    },
+
+    /// The zone differs from the first intermediate point in a task type
+    /// with homogeneous turnpoints.
+    NotHomogeneous {
+        task_type: TaskType,
+        index: usize,
+        name: String,
+        zone: ZoneKind,
+        expected: ZoneKind,
+    },
+
+    /// The finish isn't at the start of a closed task type.
+    NotClosed {
+        task_type: TaskType,
+        /// Distance between start and finish in meters.
+        distance: f64,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::TooFewPoints {
+                task_type,
+                count,
+                min,
+            } => write!(
+                f,
+                "{} tasks need at least {min} points, found {count}",
+                format::task_type(*task_type)
+            ),
+            Violation::TooManyPoints {
+                task_type,
+                count,
+                max,
+            } => write!(
+                f,
+                "{} tasks allow at most {max} points, found {count}",
+                format::task_type(*task_type)
+            ),
+            Violation::PointNotAllowed {
+                task_type,
+                index,
+                name,
+                role,
+                point_type,
+                zone,
+            } => {
+                let constraints = task_type.constraints();
+                let role_name = match role {
+                    Role::Start => "the start",
+                    Role::Intermediate => "turnpoints",
+                    Role::Finish => "the finish",
+                };
+                let task_type = format::task_type(*task_type);
+                write!(f, "point {} ({name}): ", index + 1)?;
+                let zones = constraints
+                    .zones(*role, role_point_type(*point_type))
+                    .collect::<Vec<_>>();
+                if zones.is_empty() {
+                    let mut point_types = Vec::new();
+                    for kind in constraints.allowed(*role) {
+                        if !point_types.contains(&kind.point_type) {
+                            point_types.push(kind.point_type);
+                        }
+                    }
+                    write!(
+                        f,
+                        "{point_type:?} points aren't allowed as {role_name} of {task_type} \
+                         tasks, expected {}",
+                        list(
+                            point_types
+                                .iter()
+                                .map(|point_type| format!("{point_type:?}"))
+                        )
+                    )
+                } else {
+                    write!(
+                        f,
+                        "{} zones aren't allowed on {role_name} of {task_type} tasks, \
+                         expected {}",
+                        format::zone_kind(*zone),
+                        list(zones.into_iter().map(format::zone_kind))
+                    )
+                }
+            }
+            Violation::NotHomogeneous {
+                task_type,
+                index,
+                name,
+                zone,
+                expected,
+            } => write!(
+                f,
+                "point {} ({name}): {} tasks need the same zone on every turnpoint, \
+                 found {} instead of {}",
+                index + 1,
+                format::task_type(*task_type),
+                format::zone_kind(*zone),
+                format::zone_kind(*expected)
+            ),
+            Violation::NotClosed {
+                task_type,
+                distance,
+            } => write!(
+                f,
+                "{} tasks must finish at the start, but the finish is {} away",
+                format::task_type(*task_type),
+                format::distance(*distance, UnitSystem::METRIC)
+            ),
+        }
+    }
+}
+
+/// Joins names as "A, B or C".
+fn list<T: AsRef<str>>(items: impl IntoIterator<Item = T>) -> String {
+    let items = items.into_iter().collect::<Vec<_>>();
+    let mut output = String::new();
+    for (index, item) in items.iter().enumerate() {
+        if index > 0 {
+            output.push_str(if index + 1 == items.len() {
+                " or "
+            } else {
+                ", "
+            });
+        }
+        output.push_str(item.as_ref());
+    }
+    output
+}
+
+impl Task {
+    /// Checks the task against the [`TaskConstraints`] of its type.
+    ///
+    /// Returns every violation found, in the order of the points. An empty
+    /// list means XCSoar would accept the task as it is. Distances are
+    /// measured on the FAI sphere.
+    pub fn validate(&self) -> Vec<Violation> {
+        self.validate_with_model(EarthModel::FaiSphere)
+    }
+
+    /// Like [`Task::validate`], with distances measured on `model`.
+    pub fn validate_with_model(&self, model: EarthModel) -> Vec<Violation> {
+        let task_type = self.task_type;
+        let constraints = task_type.constraints();
+        let mut violations = Vec::new();
+
+        let course = self.course();
+
+        let count = course.len();
+        if count < constraints.min_points {
+            violations.push(Violation::TooFewPoints {
+                task_type,
+                count,
+                min: constraints.min_points,
+            });
+        } else if count > constraints.max_points {
+            violations.push(Violation::TooManyPoints {
+                task_type,
+                count,
+                max: constraints.max_points,
+            });
+        }
+
+        let mut homogeneous_zone = None;
+        let roles = roles(self);
+        for (index, (point, role)) in self.points.iter().zip(roles).enumerate() {
+            let zone = point.observation_zone.kind();
+            let point_type = role_point_type(point.point_type);
+
+            if !constraints.is_allowed(role, point_type, zone) {
+                violations.push(Violation::PointNotAllowed {
+                    task_type,
+                    index,
+                    name: point.waypoint.name.clone(),
+                    role,
+                    point_type: point.point_type,
+                    zone,
+                });
+                continue;
+            }
+
+            if role == Role::Intermediate && constraints.homogeneous_turnpoints {
+                let expected = *homogeneous_zone.get_or_insert(zone);
+                if zone != expected {
+                    violations.push(Violation::NotHomogeneous {
+                        task_type,
+                        index,
+                        name: point.waypoint.name.clone(),
+                        zone,
+                        expected,
+                    });
+                }
+            }
+        }
+
+        if constraints.closed
+            && let [(_, first), .., (_, last)] = course.as_slice()
+            && !is_closed(first, last)
+        {
+            violations.push(Violation::NotClosed {
+                task_type,
+                distance: model.distance(&first.waypoint.location, &last.waypoint.location),
+            });
+        }
+
+        violations
+    }
+}
+
+/// The role of each point in `task.points`. Optional starts are left out
+/// when determining the first and last point of the course.
+pub(crate) fn roles(task: &Task) -> Vec<Role> {
+    let course = task.course();
+    let first = course.first().map(|(index, _)| *index);
+    let last = match course.as_slice() {
+        [_, .., (index, _)] => Some(*index),
+        _ => None,
+    };
+
+    (0..task.points.len())
+        .map(|index| {
+            if task.points[index].point_type == PointType::OptionalStart || Some(index) == first {
+                Role::Start
+            } else if Some(index) == last {
+                Role::Finish
+            } else {
+                Role::Intermediate
+            }
+        })
+        .collect()
+}
+
+/// Optional starts are checked like the start.
+pub(crate) fn role_point_type(point_type: PointType) -> PointType {
+    match point_type {
+        PointType::OptionalStart => PointType::Start,
+        point_type => point_type,
+    }
+}
+
+fn is_closed(start: &Point, finish: &Point) -> bool {
+    start.waypoint.location == finish.waypoint.location
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ObservationZone;
+    use insta::assert_snapshot;
+
+    fn render(violations: &[Violation]) -> String {
+        violations
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn fixtures_are_valid() {
+        for xml in [
+            include_str!("../fixtures/aat-task.tsk"),
+            include_str!("../fixtures/fai-task.tsk"),
+            include_str!("../fixtures/racing-task.tsk"),
+        ] {
+            let task = crate::from_str(xml).unwrap();
+            assert_eq!(task.validate(), vec![]);
+        }
+    }
+
+    #[test]
+    fn every_type_allows_its_own_roles() {
+        for task_type in [
+            TaskType::AAT,
+            TaskType::RT,
+            TaskType::FAIGeneral,
+            TaskType::FAITriangle,
+            TaskType::FAIOR,
+            TaskType::FAIGoal,
+            TaskType::MAT,
+            TaskType::Mixed,
+            TaskType::Touring,
+        ] {
+            let constraints = task_type.constraints();
+            assert!(constraints.min_points <= constraints.max_points);
+            assert!(
+                constraints
+                    .start
+                    .iter()
+                    .all(|kind| kind.point_type == PointType::Start)
+            );
+            assert!(
+                constraints
+                    .finish
+                    .iter()
+                    .all(|kind| kind.point_type == PointType::Finish)
+            );
+            assert!(
+                constraints
+                    .intermediate
+                    .iter()
+                    .all(|kind| matches!(kind.point_type, PointType::Turn | PointType::Area))
+            );
+        }
+    }
+
+    #[test]
+    fn editor_choices() {
+        let constraints = TaskType::FAITriangle.constraints();
+        assert_eq!(
+            constraints
+                .zones(Role::Intermediate, PointType::Turn)
+                .collect::<Vec<_>>(),
+            vec![ZoneKind::FAISector, ZoneKind::Cylinder]
+        );
+        assert_eq!(
+            constraints
+                .zones(Role::Intermediate, PointType::Area)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn racing_task_as_fai_triangle() {
+        let mut task = crate::from_str(include_str!("../fixtures/racing-task.tsk")).unwrap();
+        task.task_type = TaskType::FAITriangle;
+        task.points[2].observation_zone = ObservationZone::FAISector;
+        assert_snapshot!(render(&task.validate()));
+    }
+
+    #[test]
+    fn closing_distance_follows_earth_model() {
+        let mut task = crate::from_str(include_str!("../fixtures/racing-task.tsk")).unwrap();
+        task.task_type = TaskType::FAIOR;
+        task.points.drain(2..4);
+        let distance = |violations: Vec<Violation>| match violations.last() {
+            Some(Violation::NotClosed { distance, .. }) => *distance,
+            other => panic!("expected NotClosed, found {other:?}"),
+        };
+        let [start, finish] = [&task.points[0], &task.points[2]].map(|p| &p.waypoint.location);
+
+        let sphere = distance(task.validate());
+        assert_eq!(sphere, EarthModel::FaiSphere.distance(start, finish));
+        let wgs84 = distance(task.validate_with_model(EarthModel::Wgs84));
+        assert_eq!(wgs84, EarthModel::Wgs84.distance(start, finish));
+        assert_ne!(sphere, wgs84);
+    }
+
+    #[test]
+    fn fai_turnpoints_must_match() {
+        let mut task = crate::from_str(include_str!("../fixtures/fai-task.tsk")).unwrap();
+        task.points[2].observation_zone = ObservationZone::FAISector;
+        assert_eq!(
+            render(&task.validate()),
+            "point 3 (Langenfeld W): FAI badges/records tasks need the same zone on every \
+             turnpoint, found FAI sector instead of Cylinder"
+        );
+    }
+
+    #[test]
+    fn optional_start_zone() {
+        let xml = include_str!("../fixtures/optional-start-task.tsk");
+        let mut task = crate::from_str(xml).unwrap();
+        assert_eq!(task.validate(), vec![]);
+
+        task.points[5].observation_zone = ObservationZone::Keyhole;
+        assert_eq!(
+            render(&task.validate()),
+            "point 6 (Langenfeld-Wiescheid): DAeC keyhole zones aren't allowed on the start \
+             of Racing tasks, expected Line, Cylinder, FAI sector or BGA start sector"
+        );
+    }
+
+    #[test]
+    fn area_points_in_racing_task() {
+        let mut task = crate::from_str(include_str!("../fixtures/aat-task.tsk")).unwrap();
+        task.task_type = TaskType::RT;
+        task.points.swap(0, 5);
+        assert_snapshot!(render(&task.validate()));
+    }
+}
@@ -0,0 +1,647 @@
+//! Coordinates in the notations pilots and task setters use.
+//!
+//! [`format()`] writes a [`Location`] in one of the [`Notation`]s, and
+//! [`parse`] (or `str::parse::<Location>`) reads any of them back, along
+//! with the compact `DDMMSSN DDDMMSSE` form used in NOTAMs:
+//!
+//! ```
+//! use xcsoar_tasks::Location;
+//! use xcsoar_tasks::coordinates::{self, Notation};
+//!
+//! let location: Location = "51°04'07\"N 007°12'20\"E".parse().unwrap();
+//! assert_eq!(coordinates::format(&location, Notation::Igc), "5104117N00712333E");
+//! assert_eq!(coordinates::format(&location, Notation::Utm), "32U 374275 5658986");
+//! ```
+
+use crate::{Location, RangeError};
+use std::fmt;
+use std::str::FromStr;
+
+/// Scale factor on the central meridian of each UTM zone.
+const UTM_SCALE: f64 = 0.9996;
+const UTM_FALSE_EASTING: f64 = 500_000.0;
+const UTM_FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
+
+/// Latitude bands from 80°S, eight degrees each, with X extended to 84°N.
+const UTM_BANDS: &str = "CDEFGHJKLMNPQRSTUVWX";
+
+/// Semi-major axis and flattening of the WGS84 ellipsoid.
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Notation {
+    /// `51.06872°N 007.20575°E`
+    DecimalDegrees,
+
+    /// `5104.123N 00712.345E`, as in SeeYou `.cup` files.
+    DegreesDecimalMinutes,
+
+    /// `51°04'07"N 007°12'20"E`
+    DegreesMinutesSeconds,
+
+    /// `5104123N00712345E`, as in IGC files and FLARM declarations.
+    Igc,
+
+    /// `32U 374275 5658986`, zone and latitude band followed by easting and
+    /// northing in meters on the WGS84 ellipsoid.
+    Utm,
+}
+
+/// Text that couldn't be read as a coordinate.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum CoordinateError {
+    #[error("{0:?} isn't a coordinate in a known notation")]
+    Unrecognized(String),
+
+    #[error("{0}")]
+    Range(#[from] RangeError),
+}
+
+/// Formats `location` in the given notation. Minutes and seconds are rounded
+/// rather than truncated, carrying into the degrees where needed.
+pub fn format(location: &Location, notation: Notation) -> String {
+    let Location {
+        latitude,
+        longitude,
+    } = *location;
+    match notation {
+        Notation::DecimalDegrees => {
+            let (lat, lon) = (Axis::Latitude, Axis::Longitude);
+            format!(
+                "{:0lat_width$.5}°{} {:0lon_width$.5}°{}",
+                latitude.abs(),
+                lat.hemisphere(latitude),
+                longitude.abs(),
+                lon.hemisphere(longitude),
+                lat_width = lat.digits() + 6,
+                lon_width = lon.digits() + 6,
+            )
+        }
+        Notation::DegreesDecimalMinutes => format!(
+            "{} {}",
+            degrees_decimal_minutes(latitude, Axis::Latitude),
+            degrees_decimal_minutes(longitude, Axis::Longitude)
+        ),
+        Notation::DegreesMinutesSeconds => format!(
+            "{} {}",
+            degrees_minutes_seconds(latitude, Axis::Latitude),
+            degrees_minutes_seconds(longitude, Axis::Longitude)
+        ),
+        Notation::Igc => format!(
+            "{}{}",
+            igc(latitude, Axis::Latitude),
+            igc(longitude, Axis::Longitude)
+        ),
+        Notation::Utm => Utm::from_location(location).to_string(),
+    }
+}
+
+/// Reads a location in any [`Notation`], or as the compact
+/// `DDMMSSN DDDMMSSE` of NOTAMs.
+///
+/// Hemisphere letters may come before or after each coordinate. Without
+/// them, the coordinates are signed decimal degrees or degrees, minutes
+/// and seconds separated by a comma or a space, latitude first.
+pub fn parse(text: &str) -> Result<Location, CoordinateError> {
+    let text = text.trim();
+    let unrecognized = || CoordinateError::Unrecognized(text.to_string());
+
+    if let Some(utm) = Utm::parse(text) {
+        return Ok(utm.ok_or_else(unrecognized)?.to_location()?);
+    }
+
+    let ((latitude, south), (longitude, west)) = split(text).ok_or_else(unrecognized)?;
+    let latitude = parse_coordinate(latitude, Axis::Latitude)?.ok_or_else(unrecognized)?;
+    let longitude = parse_coordinate(longitude, Axis::Longitude)?.ok_or_else(unrecognized)?;
+    let sign = |value: f64, negative: bool| if negative { -value } else { value };
+    Ok(Location::new(sign(latitude, south), sign(longitude, west))?)
+}
+
+impl FromStr for Location {
+    type Err = CoordinateError;
+
+    /// See [`parse`].
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        parse(text)
+    }
+}
+
+/// A position on the Universal Transverse Mercator grid.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Utm {
+    /// Zone from 1 to 60.
+    pub zone: u8,
+
+    /// Latitude band from `C` to `X`, `N` and above being north of the
+    /// equator.
+    pub band: char,
+
+    /// Meters east of the false origin 500 km west of the central meridian.
+    pub easting: f64,
+
+    /// Meters north of the equator, or of 10000 km south of it on the
+    /// southern hemisphere.
+    pub northing: f64,
+}
+
+impl Utm {
+    /// Projects `location` into its UTM zone, including the exceptions for
+    /// Norway and Svalbard.
+    ///
+    /// Latitudes beyond 80°S and 84°N, which UTM doesn't cover, end up in
+    /// the southernmost and northernmost band.
+    pub fn from_location(location: &Location) -> Utm {
+        let Location {
+            latitude,
+            longitude,
+        } = *location;
+        let zone = utm_zone(latitude, longitude);
+        let band_index = (((latitude + 80.0) / 8.0).floor().max(0.0) as usize).min(19);
+        let band = char::from(UTM_BANDS.as_bytes()[band_index]);
+
+        let series = KruegerSeries::new();
+        let lat = latitude.to_radians();
+        let lon = (longitude - central_meridian(zone)).to_radians();
+        let root_n = 2.0 * series.n.sqrt() / (1.0 + series.n);
+        let t = (lat.sin().atanh() - root_n * (root_n * lat.sin()).atanh()).sinh();
+        let xi_prime = t.atan2(lon.cos());
+        let eta_prime = (lon.sin() / (1.0 + t * t).sqrt()).atanh();
+
+        let (mut xi, mut eta) = (xi_prime, eta_prime);
+        for (j, alpha) in (1..).zip(series.alpha) {
+            let j = f64::from(j);
+            xi += alpha * (2.0 * j * xi_prime).sin() * (2.0 * j * eta_prime).cosh();
+            eta += alpha * (2.0 * j * xi_prime).cos() * (2.0 * j * eta_prime).sinh();
+        }
+
+        let false_northing = if latitude < 0.0 {
+            UTM_FALSE_NORTHING_SOUTH
+        } else {
+            0.0
+        };
+        Utm {
+            zone,
+            band,
+            easting: UTM_FALSE_EASTING + UTM_SCALE * series.a * eta,
+            northing: false_northing + UTM_SCALE * series.a * xi,
+        }
+    }
+
+    /// Converts the grid position back to latitude and longitude.
+    pub fn to_location(&self) -> Result<Location, RangeError> {
+        let series = KruegerSeries::new();
+        let false_northing = if self.band < 'N' {
+            UTM_FALSE_NORTHING_SOUTH
+        } else {
+            0.0
+        };
+        let xi = (self.northing - false_northing) / (UTM_SCALE * series.a);
+        let eta = (self.easting - UTM_FALSE_EASTING) / (UTM_SCALE * series.a);
+
+        let (mut xi_prime, mut eta_prime) = (xi, eta);
+        for (j, beta) in (1..).zip(series.beta) {
+            let j = f64::from(j);
+            xi_prime -= beta * (2.0 * j * xi).sin() * (2.0 * j * eta).cosh();
+            eta_prime -= beta * (2.0 * j * xi).cos() * (2.0 * j * eta).sinh();
+        }
+        let chi = (xi_prime.sin() / eta_prime.cosh()).asin();
+        let mut latitude = chi;
+        for (j, delta) in (1..).zip(series.delta) {
+            latitude += delta * (2.0 * f64::from(j) * chi).sin();
+        }
+        let longitude =
+            central_meridian(self.zone) + eta_prime.sinh().atan2(xi_prime.cos()).to_degrees();
+
+        Location::new(
+            latitude.to_degrees(),
+            (longitude + 180.0).rem_euclid(360.0) - 180.0,
+        )
+    }
+
+    /// `Some(None)` for text shaped like `32U 374275 5658986` with an invalid
+    /// zone or band, `None` for text that isn't UTM at all.
+    fn parse(text: &str) -> Option<Option<Utm>> {
+        let [grid_zone, easting, northing] = text
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .try_into()
+            .ok()?;
+        let band = grid_zone.chars().last()?;
+        let zone = grid_zone[..grid_zone.len() - band.len_utf8()]
+            .parse::<u8>()
+            .ok()?;
+        let easting = easting.parse::<f64>().ok()?;
+        let northing = northing.parse::<f64>().ok()?;
+        if !band.is_ascii_alphabetic() {
+            return None;
+        }
+
+        let band = band.to_ascii_uppercase();
+        let valid = (1..=60).contains(&zone)
+            && UTM_BANDS.contains(band)
+            && easting.is_finite()
+            && northing.is_finite();
+        Some(valid.then_some(Utm {
+            zone,
+            band,
+            easting,
+            northing,
+        }))
+    }
+}
+
+impl fmt::Display for Utm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{} {:.0} {:.0}",
+            self.zone, self.band, self.easting, self.northing
+        )
+    }
+}
+
+fn utm_zone(latitude: f64, longitude: f64) -> u8 {
+    // Southwest Norway and Svalbard use wider zones
+    if (56.0..64.0).contains(&latitude) && (3.0..12.0).contains(&longitude) {
+        return 32;
+    }
+    if latitude >= 72.0 && (0.0..42.0).contains(&longitude) {
+        return match longitude {
+            ..9.0 => 31,
+            ..21.0 => 33,
+            ..33.0 => 35,
+            _ => 37,
+        };
+    }
+    (((longitude + 180.0) / 6.0).floor() as i32).rem_euclid(60) as u8 + 1
+}
+
+fn central_meridian(zone: u8) -> f64 {
+    f64::from(zone) * 6.0 - 183.0
+}
+
+/// Coefficients of Krüger's series for the transverse Mercator projection,
+/// to third order in the third flattening, which is accurate to about a
+/// millimeter within a UTM zone.
+struct KruegerSeries {
+    n: f64,
+    /// Rectifying radius.
+    a: f64,
+    alpha: [f64; 3],
+    beta: [f64; 3],
+    delta: [f64; 3],
+}
+
+impl KruegerSeries {
+    fn new() -> KruegerSeries {
+        let n = WGS84_F / (2.0 - WGS84_F);
+        let (n2, n3) = (n * n, n * n * n);
+        KruegerSeries {
+            n,
+            a: WGS84_A / (1.0 + n) * (1.0 + n2 / 4.0 + n2 * n2 / 64.0),
+            alpha: [
+                n / 2.0 - 2.0 * n2 / 3.0 + 5.0 * n3 / 16.0,
+                13.0 * n2 / 48.0 - 3.0 * n3 / 5.0,
+                61.0 * n3 / 240.0,
+            ],
+            beta: [
+                n / 2.0 - 2.0 * n2 / 3.0 + 37.0 * n3 / 96.0,
+                n2 / 48.0 + n3 / 15.0,
+                17.0 * n3 / 480.0,
+            ],
+            delta: [
+                2.0 * n - 2.0 * n2 / 3.0 - 2.0 * n3,
+                7.0 * n2 / 3.0 - 8.0 * n3 / 5.0,
+                56.0 * n3 / 15.0,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Axis {
+    Latitude,
+    Longitude,
+}
+
+impl Axis {
+    /// Number of digits of the degrees in fixed-width notations.
+    fn digits(self) -> usize {
+        match self {
+            Axis::Latitude => 2,
+            Axis::Longitude => 3,
+        }
+    }
+
+    fn hemisphere(self, value: f64) -> char {
+        match (self, value < 0.0) {
+            (Axis::Latitude, false) => 'N',
+            (Axis::Latitude, true) => 'S',
+            (Axis::Longitude, false) => 'E',
+            (Axis::Longitude, true) => 'W',
+        }
+    }
+}
+
+/// Formats a latitude as `DDMM.mmmN` or a longitude as `DDDMM.mmmE`.
+pub(crate) fn degrees_decimal_minutes(value: f64, axis: Axis) -> String {
+    let (degrees, milli_minutes) = split_units(value, 60_000.0);
+    format!(
+        "{degrees:0width$}{:06.3}{}",
+        f64::from(milli_minutes) / 1000.0,
+        axis.hemisphere(value),
+        width = axis.digits()
+    )
+}
+
+/// Formats a latitude as `DDMMmmmN` or a longitude as `DDDMMmmmE`.
+pub(crate) fn igc(value: f64, axis: Axis) -> String {
+    let (degrees, milli_minutes) = split_units(value, 60_000.0);
+    format!(
+        "{degrees:0width$}{milli_minutes:05}{}",
+        axis.hemisphere(value),
+        width = axis.digits()
+    )
+}
+
+fn degrees_minutes_seconds(value: f64, axis: Axis) -> String {
+    let (degrees, seconds) = split_units(value, 3600.0);
+    format!(
+        "{degrees:0width$}°{:02}'{:02}\"{}",
+        seconds / 60,
+        seconds % 60,
+        axis.hemisphere(value),
+        width = axis.digits()
+    )
+}
+
+/// Splits the absolute value of `degrees` into whole degrees and the
+/// rounded remainder in units of `1 / per_degree` degrees.
+fn split_units(degrees: f64, per_degree: f64) -> (u32, u32) {
+    let per_degree_units = per_degree as u32;
+    let total = (degrees.abs() * per_degree).round() as u32;
+    (total / per_degree_units, total % per_degree_units)
+}
+
+/// Unsigned text of a coordinate and whether it's south or west.
+type Half<'a> = (&'a str, bool);
+
+/// Splits `text` into latitude and longitude.
+fn split(text: &str) -> Option<(Half<'_>, Half<'_>)> {
+    let is_separator = |c: char| c.is_whitespace() || c == ',' || c == ';';
+    let north_south = text.find(['N', 'S', 'n', 's']);
+    let east_west = text.find(['E', 'W', 'e', 'w']);
+
+    let (latitude, longitude, south, west) = match (north_south, east_west) {
+        (Some(ns), Some(ew)) if ns < ew => {
+            let (latitude, longitude, rest) = if ns == 0 {
+                (&text[1..ew], &text[ew + 1..], "")
+            } else {
+                (&text[..ns], &text[ns + 1..ew], &text[ew + 1..])
+            };
+            if !rest.trim().is_empty() {
+                return None;
+            }
+            let south = text[ns..].starts_with(['S', 's']);
+            let west = text[ew..].starts_with(['W', 'w']);
+            (latitude, longitude, south, west)
+        }
+        (None, None) => {
+            let (latitude, longitude) = match text.split_once([',', ';']) {
+                Some(halves) => halves,
+                None => {
+                    let [latitude, longitude] = text
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .try_into()
+                        .ok()?;
+                    (latitude, longitude)
+                }
+            };
+            let latitude = latitude.trim();
+            let longitude = longitude.trim();
+            let south = latitude.starts_with('-');
+            let west = longitude.starts_with('-');
+            (
+                latitude.trim_start_matches(['-', '+']),
+                longitude.trim_start_matches(['-', '+']),
+                south,
+                west,
+            )
+        }
+        _ => return None,
+    };
+    Some((
+        (latitude.trim_matches(is_separator), south),
+        (longitude.trim_matches(is_separator), west),
+    ))
+}
+
+/// Reads an unsigned coordinate in degrees, with minutes and seconds either
+/// separated by symbols or spaces or packed into a single number like
+/// `5104.123` or `0071220`.
+fn parse_coordinate(text: &str, axis: Axis) -> Result<Option<f64>, RangeError> {
+    let normalized = text.replace(['°', 'º', '˚', '\'', '′', '’', '"', '″', '”', ':'], " ");
+    let parts = normalized.split_whitespace().collect::<Vec<_>>();
+    let numbers = parts
+        .iter()
+        .map(|part| {
+            let valid = !part.is_empty()
+                && part.chars().all(|c| c.is_ascii_digit() || c == '.')
+                && part.matches('.').count() <= 1;
+            valid.then(|| part.parse::<f64>().ok()).flatten()
+        })
+        .collect::<Option<Vec<_>>>();
+    let Some(numbers) = numbers else {
+        return Ok(None);
+    };
+    // Only the last part may have a fraction
+    if parts.iter().rev().skip(1).any(|part| part.contains('.')) {
+        return Ok(None);
+    }
+
+    let (degrees, minutes, seconds) = match numbers.as_slice() {
+        [packed] => {
+            let digits = parts[0].split('.').next().unwrap_or_default();
+            let whole = digits.parse::<u64>().unwrap_or(0) as f64;
+            let fraction = packed - whole;
+            match digits.len().checked_sub(axis.digits()) {
+                // Decimal degrees, with leading zeros optional
+                None | Some(0) => (*packed, 0.0, 0.0),
+                // Degrees and minutes
+                Some(2) => ((whole / 100.0).floor(), whole % 100.0 + fraction, 0.0),
+                // Degrees, minutes and seconds
+                Some(4) => (
+                    (whole / 10_000.0).floor(),
+                    (whole / 100.0).floor() % 100.0,
+                    whole % 100.0 + fraction,
+                ),
+                // Degrees and thousandths of minutes, as in IGC files
+                Some(5) if fraction == 0.0 => {
+                    ((whole / 100_000.0).floor(), whole % 100_000.0 / 1000.0, 0.0)
+                }
+                _ => return Ok(None),
+            }
+        }
+        [degrees, minutes] => (*degrees, *minutes, 0.0),
+        [degrees, minutes, seconds] => (*degrees, *minutes, *seconds),
+        _ => return Ok(None),
+    };
+
+    let below_sixty = |field, value: f64| {
+        if value < 60.0 {
+            Ok(value)
+        } else {
+            Err(RangeError {
+                field,
+                value,
+                expected: "less than 60",
+            })
+        }
+    };
+    below_sixty("minutes", minutes)?;
+    below_sixty("seconds", seconds)?;
+    Ok(Some(degrees + minutes / 60.0 + seconds / 3600.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EarthModel;
+
+    fn at(latitude: f64, longitude: f64) -> Location {
+        Location::new(latitude, longitude).unwrap()
+    }
+
+    fn assert_close(a: &Location, b: &Location, tolerance: f64) {
+        let distance = EarthModel::Wgs84.distance(a, b);
+        assert!(distance < tolerance, "{a:?} is {distance} m from {b:?}");
+    }
+
+    #[test]
+    fn formats() {
+        let location = at(51.068_72, -7.205_75);
+        let formatted = [
+            Notation::DecimalDegrees,
+            Notation::DegreesDecimalMinutes,
+            Notation::DegreesMinutesSeconds,
+            Notation::Igc,
+            Notation::Utm,
+        ]
+        .map(|notation| format(&location, notation));
+        assert_eq!(
+            formatted,
+            [
+                "51.06872°N 007.20575°W",
+                "5104.123N 00712.345W",
+                "51°04'07\"N 007°12'21\"W",
+                "5104123N00712345W",
+                "29U 625711 5658998",
+            ]
+        );
+
+        // Rounding must carry into the degrees instead of producing 60'
+        let location = at(-0.999_999_9, 6.999_999_9);
+        assert_eq!(
+            format(&location, Notation::DegreesMinutesSeconds),
+            "01°00'00\"S 007°00'00\"E"
+        );
+    }
+
+    #[test]
+    fn parses_every_notation() {
+        let location = at(51.068_72, -7.205_75);
+        for notation in [
+            Notation::DecimalDegrees,
+            Notation::DegreesDecimalMinutes,
+            Notation::DegreesMinutesSeconds,
+            Notation::Igc,
+            Notation::Utm,
+        ] {
+            let formatted = format(&location, notation);
+            let parsed = parse(&formatted).unwrap();
+            assert_close(&parsed, &location, 20.0);
+        }
+    }
+
+    #[test]
+    fn parses_common_variants() {
+        let expected = at(51.068_611, 7.205_556);
+        for text in [
+            "510407N0071220E",
+            "510407N 0071220E",
+            "N 51° 04' 07\" E 7° 12' 20\"",
+            "51°04′07″N, 7°12′20″E",
+            "51 04 07 N 007 12 20 E",
+            "51:04:07N 7:12:20E",
+            "n51 04.117 e7 12.333",
+            "51.068611, 7.205556",
+            "51.068611 7.205556",
+            "51°04'07\" 7°12'20\"",
+        ] {
+            let parsed = parse(text).unwrap_or_else(|error| panic!("{text}: {error}"));
+            assert_close(&parsed, &expected, 10.0);
+        }
+
+        let parsed = parse("-33.9, -70.5").unwrap();
+        assert_eq!((parsed.latitude, parsed.longitude), (-33.9, -70.5));
+        assert_eq!(
+            "3354S 07030W".parse::<Location>().unwrap(),
+            at(-33.9, -70.5)
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_coordinates() {
+        for text in ["", "51N", "hello", "51.5.5N 7E", "51 04.5 07N 7E", "7E 51N"] {
+            assert_eq!(
+                parse(text),
+                Err(CoordinateError::Unrecognized(text.to_string())),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            parse("5160.000N 00712.345E").unwrap_err().to_string(),
+            "minutes is 60, expected less than 60"
+        );
+        assert_eq!(
+            parse("91.5, 7.2").unwrap_err().to_string(),
+            "latitude is 91.5, expected -90 to 90"
+        );
+        assert!(matches!(
+            parse("61U 374275 5658986"),
+            Err(CoordinateError::Unrecognized(_))
+        ));
+    }
+
+    #[test]
+    fn utm_on_the_central_meridian() {
+        // The northing is the scaled length of the meridian arc
+        for latitude in [0.0, 23.5, 51.0, 71.9] {
+            let location = at(latitude, 9.0);
+            let utm = Utm::from_location(&location);
+            let arc = EarthModel::Wgs84.distance(&at(0.0, 9.0), &location);
+            assert_eq!(utm.zone, 32);
+            assert!((utm.easting - UTM_FALSE_EASTING).abs() < 1e-6);
+            assert!((utm.northing - UTM_SCALE * arc).abs() < 0.001, "{utm:?}");
+        }
+    }
+
+    #[test]
+    fn utm_roundtrip() {
+        for (latitude, longitude, zone, band) in [
+            (51.068_72, 7.205_75, 32, 'U'),
+            (-33.9, 151.2, 56, 'H'),
+            (60.0, 5.0, 32, 'V'),
+            (78.2, 15.6, 33, 'X'),
+            (0.1, -179.9, 1, 'N'),
+            (-0.1, 179.9, 60, 'M'),
+        ] {
+            let location = at(latitude, longitude);
+            let utm = Utm::from_location(&location);
+            assert_eq!((utm.zone, utm.band), (zone, band), "{location:?}");
+            assert_close(&utm.to_location().unwrap(), &location, 0.001);
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! SeeYou `.cup` waypoint and task files.
+//!
+//! The waypoints of the task are written as a waypoint list, followed by a
+//! "Related Tasks" section describing the task and its observation zones.
+//! Optional starts are listed as waypoints and offered as alternative
+//! starts on the `STARTS` line, but are not part of the task line.
+
+use crate::coordinates::{Axis, degrees_decimal_minutes};
+use crate::{ObservationZone, Orientation, PointType, Task, TaskType, ZoneShape};
+use std::fmt::Write as FmtWrite;
+
+const HEADER: &str = "name,code,country,lat,lon,elev,style,rwdir,rwlen,freq,desc";
+
+/// Writes `task` in SeeYou `.cup` format.
+pub fn to_writer(mut writer: impl FmtWrite, task: &Task) -> std::fmt::Result {
+    writeln!(writer, "{HEADER}")?;
+    for point in &task.points {
+        let waypoint = &point.waypoint;
+        writeln!(
+            writer,
+            "{},{},,{},{},{},1,,,,{}",
+            quote(&waypoint.name),
+            quote(&waypoint.name),
+            degrees_decimal_minutes(waypoint.location.latitude, Axis::Latitude),
+            degrees_decimal_minutes(waypoint.location.longitude, Axis::Longitude),
+            waypoint
+                .altitude
+                .map(|altitude| format!("{altitude:.1}m"))
+                .unwrap_or_default(),
+            quote(waypoint.comment.as_deref().unwrap_or_default()),
+        )?;
+    }
+
+    writeln!(writer, "-----Related Tasks-----")?;
+
+    // The task line starts with the description and wraps the task points in
+    // takeoff and landing, for which the start and finish are reused.
+    let course = task.course();
+    let names = course
+        .iter()
+        .map(|(_, point)| quote(&point.waypoint.name))
+        .collect::<Vec<_>>();
+    let takeoff = names.first().cloned().unwrap_or_default();
+    let landing = names.last().cloned().unwrap_or_default();
+    writeln!(
+        writer,
+        "{},{takeoff},{},{landing}",
+        quote(crate::format::task_type(task.task_type)),
+        names.join(",")
+    )?;
+
+    if let Some(min_time) = task
+        .aat_min_time
+        .filter(|_| task.task_type == TaskType::AAT)
+    {
+        let seconds = min_time.as_secs();
+        writeln!(
+            writer,
+            "Options,TaskTime={:02}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )?;
+    }
+
+    // Every start the pilot may use, the course's own first
+    let starts = task
+        .points
+        .iter()
+        .filter(|point| point.point_type == PointType::OptionalStart)
+        .map(|point| quote(&point.waypoint.name))
+        .collect::<Vec<_>>();
+    if !starts.is_empty() {
+        let first = names.first().cloned().unwrap_or_default();
+        writeln!(writer, "STARTS={first},{}", starts.join(","))?;
+    }
+
+    for (index, (_, point)) in course.iter().enumerate() {
+        let style = match point.point_type {
+            PointType::Start | PointType::OptionalStart => 2,
+            PointType::Finish => 3,
+            PointType::Turn | PointType::Area => 1,
+        };
+        let zone = zone_parameters(&point.observation_zone);
+        write!(
+            writer,
+            "ObsZone={index},Style={}",
+            zone.style.unwrap_or(style)
+        )?;
+        write!(writer, ",R1={:.0}m,A1={}", zone.r1, zone.a1)?;
+        if let Some((r2, a2)) = zone.inner {
+            write!(writer, ",R2={r2:.0}m,A2={a2}")?;
+        }
+        if let Some(a12) = zone.a12 {
+            write!(writer, ",A12={a12:.1}")?;
+        }
+        if zone.line {
+            write!(writer, ",Line=1")?;
+        }
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+pub fn to_string(task: &Task) -> String {
+    let mut buffer = String::new();
+    to_writer(&mut buffer, task).expect("writing to a String cannot fail");
+    buffer
+}
+
+/// SeeYou observation zone parameters: `R1`/`A1` describe the outer sector
+/// (`A1` being half the opening angle), `R2`/`A2` an optional inner one.
+struct ZoneParameters {
+    style: Option<u8>,
+    r1: f64,
+    a1: f64,
+    inner: Option<(f64, f64)>,
+    a12: Option<f64>,
+    line: bool,
+}
+
+fn zone_parameters(zone: &ObservationZone) -> ZoneParameters {
+    let zone = zone.resolve();
+    let half_angle = zone.angle / 2.0;
+    let mut parameters = ZoneParameters {
+        style: None,
+        r1: zone.finite_radius(),
+        a1: half_angle,
+        inner: None,
+        a12: None,
+        line: false,
+    };
+
+    match zone.shape {
+        ZoneShape::Cylinder | ZoneShape::Line => {
+            parameters.line = zone.shape == ZoneShape::Line;
+        }
+        ZoneShape::Sector => {
+            parameters.inner = (zone.inner_radius > 0.0).then_some((zone.inner_radius, half_angle));
+        }
+        ZoneShape::Keyhole => parameters.inner = Some((zone.inner_radius, 180.0)),
+    }
+
+    // Fixed orientation, with `A12` pointing along the bisector
+    if let Orientation::Fixed(bisector) = zone.orientation {
+        parameters.style = Some(0);
+        parameters.a12 = Some(bisector);
+    }
+    parameters
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn cup_aat_task() {
+        let xml = include_str!("../fixtures/aat-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_snapshot!(to_string(&task));
+    }
+
+    #[test]
+    fn cup_optional_start_task() {
+        let xml = include_str!("../fixtures/optional-start-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_snapshot!(to_string(&task));
+    }
+
+    #[test]
+    fn cup_all_oz_types() {
+        let xml = include_str!("../fixtures/all-oz-types.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_snapshot!(to_string(&task));
+    }
+}
@@ -0,0 +1,650 @@
+//! Structural comparison of two tasks.
+//!
+//! Task points are matched by waypoint name and location rather than by
+//! index, so inserting a turnpoint shows up as a single addition instead of
+//! every following point being reported as changed.
+
+use crate::format::{self, UnitSystem};
+use crate::{
+    Altitude, EarthModel, Location, ObservationZone, Point, PointType, Speed, Task, TaskType,
+    TimeOfDay,
+};
+use std::fmt;
+use std::time::Duration;
+
+/// Waypoints closer than this are considered to be at the same location
+/// when pairing up the points of both tasks. Any move of a paired point is
+/// still reported.
+pub const LOCATION_TOLERANCE: f64 = 100.0;
+
+/// The differences between two tasks, in the order they should be presented.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TaskDiff {
+    pub changes: Vec<Change>,
+}
+
+impl TaskDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// A single difference. Indices are zero-based positions in `Task::points`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    TaskType {
+        old: TaskType,
+        new: TaskType,
+    },
+
+    /// A start/finish rule or time changed, with values rendered for display.
+    Setting {
+        name: &'static str,
+        old: Option<String>,
+        new: Option<String>,
+    },
+
+    PointAdded {
+        index: usize,
+        name: String,
+    },
+
+    PointRemoved {
+        index: usize,
+        name: String,
+    },
+
+    /// The point was moved to a different position in the task.
+    PointReordered {
+        name: String,
+        old_index: usize,
+        new_index: usize,
+    },
+
+    /// A waypoint at the same location was renamed.
+    PointRenamed {
+        index: usize,
+        old: String,
+        new: String,
+    },
+
+    /// A waypoint with the same name was moved geographically.
+    PointRelocated {
+        index: usize,
+        name: String,
+        old: Location,
+        new: Location,
+        distance: f64,
+    },
+
+    PointTypeChanged {
+        index: usize,
+        name: String,
+        old: PointType,
+        new: PointType,
+    },
+
+    ZoneChanged {
+        index: usize,
+        name: String,
+        old: ObservationZone,
+        new: ObservationZone,
+    },
+
+    /// A setting of the point or a detail of its waypoint, such as the
+    /// altitude or comment, changed. Values are rendered for display.
+    PointSetting {
+        index: usize,
+        name: String,
+        setting: &'static str,
+        old: Option<String>,
+        new: Option<String>,
+    },
+}
+
+/// Compares two tasks, measuring distances on the FAI sphere.
+pub fn diff(old: &Task, new: &Task) -> TaskDiff {
+    diff_with_model(old, new, EarthModel::FaiSphere)
+}
+
+/// Compares two tasks, measuring the distances used to match points and
+/// the reported moves on `model`.
+pub fn diff_with_model(old: &Task, new: &Task, model: EarthModel) -> TaskDiff {
+    let mut changes = Vec::new();
+
+    if old.task_type != new.task_type {
+        changes.push(Change::TaskType {
+            old: old.task_type,
+            new: new.task_type,
+        });
+    }
+
+    for (name, old, new) in changed_settings(task_settings(old), task_settings(new)) {
+        changes.push(Change::Setting { name, old, new });
+    }
+
+    let matches = match_points(&old.points, &new.points, model);
+    let mut matched_old = vec![false; old.points.len()];
+    for &(old_index, _) in &matches {
+        matched_old[old_index] = true;
+    }
+
+    for (old_index, point) in old.points.iter().enumerate() {
+        if !matched_old[old_index] {
+            changes.push(Change::PointRemoved {
+                index: old_index,
+                name: point.waypoint.name.clone(),
+            });
+        }
+    }
+
+    let in_order = longest_increasing_run(&matches);
+    let mut matches_by_new = vec![None; new.points.len()];
+    for (position, &(old_index, new_index)) in matches.iter().enumerate() {
+        matches_by_new[new_index] = Some((old_index, in_order[position]));
+    }
+
+    for (new_index, new_point) in new.points.iter().enumerate() {
+        let Some((old_index, in_order)) = matches_by_new[new_index] else {
+            changes.push(Change::PointAdded {
+                index: new_index,
+                name: new_point.waypoint.name.clone(),
+            });
+            continue;
+        };
+        let old_point = &old.points[old_index];
+        let name = new_point.waypoint.name.clone();
+
+        if !in_order {
+            changes.push(Change::PointReordered {
+                name: name.clone(),
+                old_index,
+                new_index,
+            });
+        }
+        if old_point.waypoint.name != new_point.waypoint.name {
+            changes.push(Change::PointRenamed {
+                index: new_index,
+                old: old_point.waypoint.name.clone(),
+                new: name.clone(),
+            });
+        }
+        let (old_location, new_location) =
+            (old_point.waypoint.location, new_point.waypoint.location);
+        if old_location != new_location {
+            changes.push(Change::PointRelocated {
+                index: new_index,
+                name: name.clone(),
+                old: old_location,
+                new: new_location,
+                distance: model.distance(&old_location, &new_location),
+            });
+        }
+        if old_point.point_type != new_point.point_type {
+            changes.push(Change::PointTypeChanged {
+                index: new_index,
+                name: name.clone(),
+                old: old_point.point_type,
+                new: new_point.point_type,
+            });
+        }
+        if old_point.observation_zone != new_point.observation_zone {
+            changes.push(Change::ZoneChanged {
+                index: new_index,
+                name: name.clone(),
+                old: old_point.observation_zone.clone(),
+                new: new_point.observation_zone.clone(),
+            });
+        }
+        for (setting, old, new) in
+            changed_settings(point_settings(old_point), point_settings(new_point))
+        {
+            changes.push(Change::PointSetting {
+                index: new_index,
+                name: name.clone(),
+                setting,
+                old,
+                new,
+            });
+        }
+    }
+
+    TaskDiff { changes }
+}
+
+/// Pairs up old and new points, returned as `(old_index, new_index)` sorted
+/// by `new_index`.
+///
+/// Points are first matched by name and location, then by name only and
+/// finally by location only. When a waypoint appears several times (e.g. the
+/// same airfield as start and finish) the closest position wins.
+fn match_points(old: &[Point], new: &[Point], model: EarthModel) -> Vec<(usize, usize)> {
+    let same_location = |a: &Point, b: &Point| {
+        model.distance(&a.waypoint.location, &b.waypoint.location) <= LOCATION_TOLERANCE
+    };
+    let criteria: [&Criterion<'_>; 3] = [
+        &|a, b| same_name(a, b) && same_location(a, b),
+        &same_name,
+        &same_location,
+    ];
+
+    let mut old_matched = vec![false; old.len()];
+    let mut new_matched = vec![None; new.len()];
+    for criterion in criteria {
+        for (new_index, new_point) in new.iter().enumerate() {
+            if new_matched[new_index].is_some() {
+                continue;
+            }
+            let candidate = old
+                .iter()
+                .enumerate()
+                .filter(|(old_index, old_point)| {
+                    !old_matched[*old_index] && criterion(old_point, new_point)
+                })
+                .min_by_key(|(old_index, _)| old_index.abs_diff(new_index));
+            if let Some((old_index, _)) = candidate {
+                old_matched[old_index] = true;
+                new_matched[new_index] = Some(old_index);
+            }
+        }
+    }
+
+    new_matched
+        .into_iter()
+        .enumerate()
+        .filter_map(|(new_index, old_index)| old_index.map(|old_index| (old_index, new_index)))
+        .collect()
+}
+
+type Criterion<'a> = dyn Fn(&Point, &Point) -> bool + 'a;
+
+fn same_name(a: &Point, b: &Point) -> bool {
+    a.waypoint.name == b.waypoint.name
+}
+
+/// Marks the matches that keep their relative order, using the longest
+/// increasing subsequence of old indices. The others have been reordered.
+///
+/// Among equally long subsequences the one whose points moved the least is
+/// kept, so swapping two points reports the one that travelled further.
+fn longest_increasing_run(matches: &[(usize, usize)]) -> Vec<bool> {
+    let displacement = |i: usize| matches[i].0.abs_diff(matches[i].1);
+    let n = matches.len();
+    // (length, total displacement) of the best run ending at each match
+    let mut best = (0..n).map(|i| (1, displacement(i))).collect::<Vec<_>>();
+    let mut previous = vec![None; n];
+    for i in 0..n {
+        for j in 0..i {
+            let candidate = (best[j].0 + 1, best[j].1 + displacement(i));
+            if matches[j].0 < matches[i].0 && better(candidate, best[i]) {
+                best[i] = candidate;
+                previous[i] = Some(j);
+            }
+        }
+    }
+
+    let mut in_order = vec![false; n];
+    let mut current = (0..n).reduce(|a, b| if better(best[b], best[a]) { b } else { a });
+    while let Some(i) = current {
+        in_order[i] = true;
+        current = previous[i];
+    }
+    in_order
+}
+
+fn better(
+    (length, displacement): (usize, usize),
+    (other_length, other_displacement): (usize, usize),
+) -> bool {
+    length > other_length || (length == other_length && displacement < other_displacement)
+}
+
+/// The value of a setting, compared as is and only rendered for display.
+#[derive(Debug, PartialEq)]
+enum Value {
+    Flag(bool),
+    Count(u32),
+    Duration(Duration),
+    Speed(Speed),
+    Altitude(Altitude),
+    Elevation(f64),
+    Time(TimeOfDay),
+    Text(String),
+}
+
+impl Value {
+    fn render(&self) -> String {
+        let units = UnitSystem::METRIC;
+        match self {
+            Value::Flag(value) => if *value { "yes" } else { "no" }.to_string(),
+            Value::Count(count) => count.to_string(),
+            Value::Duration(duration) => format::duration(*duration),
+            Value::Speed(speed) => format::speed(*speed, units),
+            Value::Altitude(altitude) => format::altitude(*altitude, units),
+            Value::Elevation(meters) => format!("{meters:.0} m"),
+            Value::Time(time) => time.to_string(),
+            Value::Text(text) => format!("{text:?}"),
+        }
+    }
+
+    /// Renders without the rounding of [`Value::render`], for values that
+    /// differ by less than it shows.
+    fn render_exact(&self) -> String {
+        match self {
+            Value::Duration(duration) => format!("{} s", duration.as_secs_f64()),
+            Value::Speed(speed) => format!("{} m/s", speed.meters_per_second()),
+            Value::Elevation(meters) => format!("{meters} m"),
+            _ => self.render(),
+        }
+    }
+}
+
+type Setting = (&'static str, Option<Value>);
+
+/// Pairs up the settings that differ between `old` and `new`, rendered for
+/// display.
+fn changed_settings<const N: usize>(
+    old: [Setting; N],
+    new: [Setting; N],
+) -> Vec<(&'static str, Option<String>, Option<String>)> {
+    old.into_iter()
+        .zip(new)
+        .filter(|((_, old), (_, new))| old != new)
+        .map(|((name, old), (_, new))| {
+            let mut rendered = (
+                old.as_ref().map(Value::render),
+                new.as_ref().map(Value::render),
+            );
+            if rendered.0 == rendered.1 {
+                rendered = (
+                    old.as_ref().map(Value::render_exact),
+                    new.as_ref().map(Value::render_exact),
+                );
+            }
+            (name, rendered.0, rendered.1)
+        })
+        .collect()
+}
+
+fn task_settings(task: &Task) -> [Setting; 16] {
+    [
+        ("AAT min time", task.aat_min_time.map(Value::Duration)),
+        (
+            "Start requires arm",
+            task.start_requires_arm.map(Value::Flag),
+        ),
+        ("Start score exit", task.start_score_exit.map(Value::Flag)),
+        ("Start max speed", task.start_max_speed.map(Value::Speed)),
+        (
+            "Start max height",
+            task.start_max_height.map(Value::Altitude),
+        ),
+        ("Start open time", task.start_open_time.map(Value::Time)),
+        ("Start close time", task.start_close_time.map(Value::Time)),
+        (
+            "Finish min height",
+            task.finish_min_height.map(Value::Altitude),
+        ),
+        ("FAI finish", task.fai_finish.map(Value::Flag)),
+        (
+            "PEV start wait time",
+            task.pev_start_wait_time.map(Value::Duration),
+        ),
+        (
+            "PEV start window",
+            task.pev_start_window.map(Value::Duration),
+        ),
+        ("Task scored", task.task_scored.map(Value::Flag)),
+        ("Min points", task.min_points.map(Value::Count)),
+        ("Max points", task.max_points.map(Value::Count)),
+        (
+            "Homogeneous turnpoints",
+            task.homogeneous_tps.map(Value::Flag),
+        ),
+        ("Closed", task.is_closed.map(Value::Flag)),
+    ]
+}
+
+fn point_settings(point: &Point) -> [Setting; 4] {
+    let waypoint = &point.waypoint;
+    [
+        ("score exit", point.score_exit.map(Value::Flag)),
+        ("altitude", waypoint.altitude.map(Value::Elevation)),
+        ("id", waypoint.id.clone().map(Value::Text)),
+        ("comment", waypoint.comment.clone().map(Value::Text)),
+    ]
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let units = UnitSystem::METRIC;
+        let value = |value: &Option<String>| value.clone().unwrap_or_else(|| "unset".into());
+        match self {
+            Change::TaskType { old, new } => write!(
+                f,
+                "Task type changed from {} to {}",
+                format::task_type(*old),
+                format::task_type(*new)
+            ),
+            Change::Setting { name, old, new } => {
+                write!(f, "{name} changed from {} to {}", value(old), value(new))
+            }
+            Change::PointAdded { index, name } => {
+                write!(f, "Added {name} at position {}", index + 1)
+            }
+            Change::PointRemoved { index, name } => {
+                write!(f, "Removed {name} from position {}", index + 1)
+            }
+            Change::PointReordered {
+                name,
+                old_index,
+                new_index,
+            } => write!(
+                f,
+                "Moved {name} from position {} to {}",
+                old_index + 1,
+                new_index + 1
+            ),
+            Change::PointRenamed { index, old, new } => {
+                write!(f, "Renamed {old} to {new} at position {}", index + 1)
+            }
+            Change::PointRelocated {
+                name,
+                old,
+                new,
+                distance,
+                ..
+            } => {
+                let distance = format::distance(*distance, units);
+                if distance == format::distance(0.0, units) {
+                    // Too small a move to show, give the coordinates instead
+                    write!(
+                        f,
+                        "{name}: location moved from {}, {} to {}, {}",
+                        old.latitude, old.longitude, new.latitude, new.longitude
+                    )
+                } else {
+                    write!(f, "{name}: location moved by {distance}")
+                }
+            }
+            Change::PointTypeChanged { name, old, new, .. } => {
+                write!(f, "{name}: type changed from {old:?} to {new:?}")
+            }
+            Change::ZoneChanged { name, old, new, .. } => {
+                let (old_zone, new_zone) = (format::zone(old, units), format::zone(new, units));
+                if old_zone == new_zone {
+                    // Differs by less than the rounding, show the exact values
+                    write!(f, "{name}: zone changed from {old:?} to {new:?}")
+                } else {
+                    write!(f, "{name}: zone changed from {old_zone} to {new_zone}")
+                }
+            }
+            Change::PointSetting {
+                name,
+                setting,
+                old,
+                new,
+                ..
+            } => write!(
+                f,
+                "{name}: {setting} changed from {} to {}",
+                value(old),
+                value(new)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for TaskDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimeOfDay;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn identical_tasks() {
+        let xml = include_str!("../fixtures/fai-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert!(diff(&task, &task).is_empty());
+    }
+
+    #[test]
+    fn republished_task() {
+        let xml = include_str!("../fixtures/racing-task.tsk");
+        let old = crate::from_str(xml).unwrap();
+        let mut new = old.clone();
+
+        new.start_open_time = TimeOfDay::from_hms(12, 30, 0);
+        // Swap the second and third turnpoint
+        new.points.swap(2, 3);
+        // Insert a new turnpoint before the finish
+        let mut inserted = new.points[1].clone();
+        inserted.waypoint.name = "Neuer Wendepunkt".to_string();
+        inserted.waypoint.location.latitude += 0.2;
+        new.points.insert(4, inserted);
+        // Enlarge the finish cylinder
+        new.points[5].observation_zone = ObservationZone::Cylinder { radius: 3000.0 };
+        // Remove the first turnpoint
+        new.points.remove(1);
+
+        let diff = diff(&old, &new);
+        assert_snapshot!(diff.to_string());
+    }
+
+    #[test]
+    fn repeated_waypoints_match_by_position() {
+        let xml = include_str!("../fixtures/fai-task.tsk");
+        let old = crate::from_str(xml).unwrap();
+        let mut new = old.clone();
+        new.points[4].observation_zone = ObservationZone::Cylinder { radius: 500.0 };
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.changes.len(), 1);
+        assert!(matches!(
+            diff.changes[0],
+            Change::ZoneChanged { index: 4, .. }
+        ));
+    }
+
+    #[test]
+    fn settings_are_compared_unrounded() {
+        let xml = include_str!("../fixtures/aat-task.tsk");
+        let old = crate::from_str(xml).unwrap();
+        let mut new = old.clone();
+        new.aat_min_time = Some(Duration::from_secs(12620));
+        new.start_max_speed = new
+            .start_max_speed
+            .map(|speed| Speed::from_meters_per_second(speed.meters_per_second() + 0.01));
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.changes.len(), 2, "{diff}");
+        assert_eq!(
+            diff.changes[0].to_string(),
+            "AAT min time changed from 12600 s to 12620 s"
+        );
+        assert_eq!(
+            diff.changes[1].to_string(),
+            "Start max speed changed from 60 m/s to 60.01 m/s"
+        );
+    }
+
+    #[test]
+    fn small_moves_are_reported() {
+        let xml = include_str!("../fixtures/racing-task.tsk");
+        let old = crate::from_str(xml).unwrap();
+        let mut new = old.clone();
+        // About 50 m north
+        new.points[1].waypoint.location.latitude += 0.00045;
+        new.points[2].waypoint.location.longitude = 6.1922177;
+
+        let diff = diff(&old, &new);
+        assert_eq!(diff.changes.len(), 2, "{diff}");
+        assert_eq!(
+            diff.changes[0].to_string(),
+            "084Linnich Kontrollpunkt: location moved by 50 m"
+        );
+        assert_eq!(
+            diff.changes[1].to_string(),
+            "113Ritzeroder Duenen: location moved from 51.1766667, 6.1922167 to 51.1766667, 6.1922177"
+        );
+    }
+
+    #[test]
+    fn moves_are_measured_on_earth_model() {
+        let xml = include_str!("../fixtures/racing-task.tsk");
+        let old = crate::from_str(xml).unwrap();
+        let mut new = old.clone();
+        new.points[1].waypoint.location.latitude += 0.1;
+
+        let distance = |diff: TaskDiff| match diff.changes.as_slice() {
+            [Change::PointRelocated { distance, .. }] => *distance,
+            changes => panic!("expected a relocation, found {changes:?}"),
+        };
+        let [from, to] = [&old, &new].map(|task| &task.points[1].waypoint.location);
+        assert_eq!(
+            distance(diff_with_model(&old, &new, EarthModel::Wgs84)),
+            EarthModel::Wgs84.distance(from, to)
+        );
+        assert_ne!(
+            distance(diff(&old, &new)),
+            EarthModel::Wgs84.distance(from, to)
+        );
+    }
+
+    #[test]
+    fn zone_changes_are_shown_unrounded() {
+        let xml = include_str!("../fixtures/racing-task.tsk");
+        let old = crate::from_str(xml).unwrap();
+        let mut new = old.clone();
+        new.points[4].observation_zone = ObservationZone::Cylinder { radius: 2010.0 };
+
+        let diff = diff(&old, &new);
+        assert_eq!(
+            diff.to_string(),
+            "002Zielkreis: zone changed from Cylinder { radius: 2000.0 } to Cylinder { radius: 2010.0 }\n"
+        );
+    }
+
+    #[test]
+    fn point_details() {
+        let xml = include_str!("../fixtures/racing-task.tsk");
+        let old = crate::from_str(xml).unwrap();
+        let mut new = old.clone();
+        new.points[1].score_exit = Some(true);
+        new.points[2].waypoint.altitude = Some(120.0);
+        new.points[3].waypoint.id = Some("21".to_string());
+        new.points[4].waypoint.comment = Some("Zielkreis".to_string());
+
+        let diff = diff(&old, &new);
+        assert_snapshot!(diff.to_string());
+    }
+}
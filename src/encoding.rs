@@ -0,0 +1,116 @@
+//! Character encoding detection for task files.
+//!
+//! Detection follows appendix F of the XML specification: a byte order mark
+//! wins, then the `encoding` attribute of the XML declaration, then UTF-8.
+
+use crate::ParseError;
+use encoding_rs::{Encoding, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use std::borrow::Cow;
+
+/// Decodes `bytes` into a string.
+///
+/// In lossy mode malformed sequences are replaced with U+FFFD, and input
+/// without BOM or known declared encoding that isn't valid UTF-8 is read as
+/// Windows-1252 (a superset of the ISO-8859-1 used by older XCSoar
+/// installs).
+pub(crate) fn decode(bytes: &[u8], lossy: bool) -> Result<Cow<'_, str>, ParseError> {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        return decode_with(encoding, &bytes[bom_length..], lossy);
+    }
+
+    if let Some(encoding) = sniff_utf16(bytes) {
+        return decode_with(encoding, bytes, lossy);
+    }
+
+    if let Some(label) = declared_encoding(bytes) {
+        match Encoding::for_label(label.as_bytes()) {
+            Some(encoding) => return decode_with(encoding, bytes, lossy),
+            // Lossy mode treats unknown labels like a missing declaration
+            None if lossy => {}
+            None => return Err(ParseError::UnknownEncoding(label.to_string())),
+        }
+    }
+
+    if lossy && std::str::from_utf8(bytes).is_err() {
+        decode_with(WINDOWS_1252, bytes, lossy)
+    } else {
+        decode_with(UTF_8, bytes, lossy)
+    }
+}
+
+fn decode_with<'a>(
+    encoding: &'static Encoding,
+    bytes: &'a [u8],
+    lossy: bool,
+) -> Result<Cow<'a, str>, ParseError> {
+    if lossy {
+        return Ok(encoding.decode_without_bom_handling(bytes).0);
+    }
+
+    encoding
+        .decode_without_bom_handling_and_without_replacement(bytes)
+        .ok_or(ParseError::Encoding(encoding.name()))
+}
+
+/// Recognizes BOM-less UTF-16 by the `<?` of the XML declaration.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    match bytes {
+        [0x3C, 0x00, 0x3F, 0x00, ..] => Some(UTF_16LE),
+        [0x00, 0x3C, 0x00, 0x3F, ..] => Some(UTF_16BE),
+        _ => None,
+    }
+}
+
+/// Extracts the `encoding` attribute of an ASCII-compatible XML declaration.
+fn declared_encoding(bytes: &[u8]) -> Option<&str> {
+    let rest = bytes.strip_prefix(b"<?xml")?;
+    let end = rest.windows(2).position(|window| window == b"?>")?;
+    let declaration = std::str::from_utf8(&rest[..end]).ok()?;
+
+    let (_, value) = declaration.split_once("encoding")?;
+    let value = value.trim_start().strip_prefix('=')?.trim_start();
+    let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let value = &value[1..];
+    value.find(quote).map(|end| &value[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn declaration() {
+        let xml = br#"<?xml version="1.0" encoding="ISO-8859-1"?><Task/>"#;
+        assert_eq!(declared_encoding(xml), Some("ISO-8859-1"));
+
+        let xml = br#"<?xml version='1.0' encoding = 'utf-8' ?><Task/>"#;
+        assert_eq!(declared_encoding(xml), Some("utf-8"));
+
+        let xml = br#"<?xml version="1.0"?><Task/>"#;
+        assert_eq!(declared_encoding(xml), None);
+    }
+
+    #[test]
+    fn lossy_fallback() {
+        let bytes = b"<Task name=\"Merzbr\xfcck\"/>";
+        assert!(matches!(decode(bytes, false), Err(ParseError::Encoding(_))));
+        assert_eq!(decode(bytes, true).unwrap(), "<Task name=\"Merzbrück\"/>");
+    }
+
+    #[test]
+    fn unknown_encoding() {
+        let xml = br#"<?xml version="1.0" encoding="EBCDIC-FOO"?><Task/>"#;
+        assert!(matches!(
+            decode(xml, false),
+            Err(ParseError::UnknownEncoding(label)) if label == "EBCDIC-FOO"
+        ));
+        assert_eq!(decode(xml, true).unwrap().as_bytes(), xml);
+
+        let xml = b"<?xml version=\"1.0\" encoding=\"EBCDIC-FOO\"?><Task name=\"Merzbr\xfcck\"/>";
+        assert!(
+            decode(xml, true)
+                .unwrap()
+                .ends_with("<Task name=\"Merzbrück\"/>")
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! FLARM/LXNav task declarations (`flarmcfg.txt`).
+//!
+//! FLARM devices (and LXNav units that embed them) read a task declaration
+//! from `$PFLAC,S,...` configuration sentences, either sent over the serial
+//! port or placed in a `flarmcfg.txt` file on the SD card/USB stick.
+
+use crate::coordinates::{Axis, igc};
+use crate::{Location, Task};
+use std::fmt::Write as FmtWrite;
+
+/// Maximum length of a waypoint or task description accepted by the devices.
+pub const MAX_NAME_LEN: usize = 50;
+
+/// Pilot and glider metadata written alongside the task.
+///
+/// Fields that are `None` are omitted from the declaration, leaving the
+/// value already configured on the device untouched.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Declaration {
+    pub pilot: Option<String>,
+    pub copilot: Option<String>,
+    pub glider_id: Option<String>,
+    pub glider_type: Option<String>,
+    pub competition_id: Option<String>,
+    pub competition_class: Option<String>,
+
+    /// Task description, `"Task"` if not set.
+    pub task_name: Option<String>,
+}
+
+/// Writes the declaration for `task` as `$PFLAC` sentences, one per line.
+///
+/// The task is wrapped in the takeoff and landing placeholders the devices
+/// expect before the first and after the last task point. Optional starts
+/// are left out, the devices only know a single start.
+pub fn to_writer(
+    mut writer: impl FmtWrite,
+    task: &Task,
+    declaration: &Declaration,
+) -> std::fmt::Result {
+    let settings = [
+        ("PILOT", &declaration.pilot),
+        ("COPIL", &declaration.copilot),
+        ("GLIDERID", &declaration.glider_id),
+        ("GLIDERTYPE", &declaration.glider_type),
+        ("COMPID", &declaration.competition_id),
+        ("COMPCLASS", &declaration.competition_class),
+    ];
+    for (key, value) in settings {
+        if let Some(value) = value {
+            writeln!(writer, "$PFLAC,S,{key},{}", clean_string(value))?;
+        }
+    }
+
+    let task_name = declaration.task_name.as_deref().unwrap_or("Task");
+    writeln!(writer, "$PFLAC,S,NEWTASK,{}", clean_string(task_name))?;
+    writeln!(writer, "$PFLAC,S,ADDWP,0000000N,00000000E,Takeoff")?;
+    for (_, point) in task.course() {
+        writeln!(
+            writer,
+            "$PFLAC,S,ADDWP,{},{}",
+            format_location(point.waypoint.location),
+            clean_string(&point.waypoint.name)
+        )?;
+    }
+    writeln!(writer, "$PFLAC,S,ADDWP,0000000N,00000000E,Landing")?;
+
+    Ok(())
+}
+
+/// Returns the declaration for `task` as a string.
+pub fn to_string(task: &Task, declaration: &Declaration) -> String {
+    let mut buffer = String::new();
+    to_writer(&mut buffer, task, declaration).expect("writing to a String cannot fail");
+    buffer
+}
+
+/// Formats a location as `DDMMmmmN,DDDMMmmmE`.
+fn format_location(location: Location) -> String {
+    format!(
+        "{},{}",
+        igc(location.latitude, Axis::Latitude),
+        igc(location.longitude, Axis::Longitude)
+    )
+}
+
+/// Strips characters the devices reject and truncates to [`MAX_NAME_LEN`].
+///
+/// FLARM only accepts printable ASCII, and `,`, `$` and `*` would break the
+/// sentence structure. Common Latin-1 letters are transliterated so that
+/// e.g. "Merzbrück" becomes "Merzbruck" rather than "Merzbrck".
+fn clean_string(value: &str) -> String {
+    value
+        .chars()
+        .filter_map(|c| match c {
+            ',' | '$' | '*' => None,
+            ' '..='~' => Some(c),
+            _ => transliterate(c),
+        })
+        .take(MAX_NAME_LEN)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn transliterate(c: char) -> Option<char> {
+    Some(match c {
+        'À'..='Å' => 'A',
+        'à'..='å' => 'a',
+        'Ç' => 'C',
+        'ç' => 'c',
+        'È'..='Ë' => 'E',
+        'è'..='ë' => 'e',
+        'Ì'..='Ï' => 'I',
+        'ì'..='ï' => 'i',
+        'Ñ' => 'N',
+        'ñ' => 'n',
+        'Ò'..='Ö' | 'Ø' => 'O',
+        'ò'..='ö' | 'ø' => 'o',
+        'Ù'..='Ü' => 'U',
+        'ù'..='ü' => 'u',
+        'Ý' => 'Y',
+        'ý' | 'ÿ' => 'y',
+        'ß' => 's',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn declaration_aat_task() {
+        let xml = include_str!("../fixtures/aat-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        let declaration = Declaration {
+            pilot: Some("Max Mustermann".to_string()),
+            glider_id: Some("D-1234".to_string()),
+            glider_type: Some("ASW 28".to_string()),
+            competition_id: Some("XY".to_string()),
+            competition_class: Some("Standard".to_string()),
+            task_name: Some("Day 3, AAT".to_string()),
+            ..Default::default()
+        };
+        assert_snapshot!(to_string(&task, &declaration));
+    }
+
+    #[test]
+    fn optional_starts_are_not_declared() {
+        let xml = include_str!("../fixtures/optional-start-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        let declaration = to_string(&task, &Declaration::default());
+        assert!(
+            !declaration.contains("Langenfeld-Wiescheid"),
+            "{declaration}"
+        );
+
+        let xml = include_str!("../fixtures/racing-task.tsk");
+        let without = crate::from_str(xml).unwrap();
+        assert_eq!(declaration, to_string(&without, &Declaration::default()));
+    }
+
+    #[test]
+    fn coordinates() {
+        let location = Location {
+            longitude: -6.9852833,
+            latitude: -51.1413833,
+        };
+        assert_eq!(format_location(location), "5108483S,00659117W");
+
+        // Rounding must carry into the degrees instead of producing 60'
+        let location = Location {
+            longitude: 6.9999999,
+            latitude: 0.0,
+        };
+        assert_eq!(format_location(location), "0000000N,00700000E");
+    }
+
+    #[test]
+    fn name_cleanup() {
+        assert_eq!(clean_string("Aachen Merzbrück"), "Aachen Merzbruck");
+        assert_eq!(clean_string("A,B$C*D"), "ABCD");
+        assert_eq!(clean_string(&"x".repeat(60)).len(), MAX_NAME_LEN);
+    }
+}
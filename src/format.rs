@@ -0,0 +1,440 @@
+//! Human-readable descriptions of tasks in a chosen unit system.
+//!
+//! The wording follows the terms XCSoar uses in its task editor, e.g.
+//! "FAI sector" or "Racing", so pilots see the same names they know from
+//! the flight computer.
+
+use crate::coordinates::{self, Notation};
+use crate::legs::Leg;
+use crate::{
+    Altitude, AltitudeReference, ObservationZone, Point, PointType, Speed, Task, TaskType, ZoneKind,
+};
+use std::fmt::Write as FmtWrite;
+use std::time::Duration;
+
+const METERS_PER_STATUTE_MILE: f64 = 1609.344;
+const METERS_PER_NAUTICAL_MILE: f64 = 1852.0;
+const METERS_PER_FOOT: f64 = 0.3048;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceUnit {
+    Kilometers,
+    StatuteMiles,
+    NauticalMiles,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AltitudeUnit {
+    Meters,
+    Feet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeedUnit {
+    KilometersPerHour,
+    MilesPerHour,
+    Knots,
+}
+
+/// The units used when rendering distances, altitudes and speeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnitSystem {
+    pub distance: DistanceUnit,
+    pub altitude: AltitudeUnit,
+    pub speed: SpeedUnit,
+}
+
+impl UnitSystem {
+    /// Kilometers, meters and km/h.
+    pub const METRIC: UnitSystem = UnitSystem {
+        distance: DistanceUnit::Kilometers,
+        altitude: AltitudeUnit::Meters,
+        speed: SpeedUnit::KilometersPerHour,
+    };
+
+    /// Statute miles, feet and mph.
+    pub const IMPERIAL: UnitSystem = UnitSystem {
+        distance: DistanceUnit::StatuteMiles,
+        altitude: AltitudeUnit::Feet,
+        speed: SpeedUnit::MilesPerHour,
+    };
+
+    /// Nautical miles, feet and knots.
+    pub const AVIATION: UnitSystem = UnitSystem {
+        distance: DistanceUnit::NauticalMiles,
+        altitude: AltitudeUnit::Feet,
+        speed: SpeedUnit::Knots,
+    };
+}
+
+impl Default for UnitSystem {
+    fn default() -> Self {
+        UnitSystem::METRIC
+    }
+}
+
+/// Formats a distance given in meters.
+///
+/// Distances below one unit switch to the small unit (meters or feet), so
+/// that a 500 m cylinder doesn't show up as "0.3 mi".
+pub fn distance(meters: f64, units: UnitSystem) -> String {
+    let (factor, unit, small_factor, small_unit) = match units.distance {
+        DistanceUnit::Kilometers => (1000.0, "km", 1.0, "m"),
+        DistanceUnit::StatuteMiles => (METERS_PER_STATUTE_MILE, "mi", METERS_PER_FOOT, "ft"),
+        DistanceUnit::NauticalMiles => (METERS_PER_NAUTICAL_MILE, "NM", METERS_PER_FOOT, "ft"),
+    };
+
+    // Compare the rounded value so that e.g. 999.96 m becomes "1 km"
+    let value = meters / factor;
+    if (value * 10.0).round() < 10.0 {
+        format!("{:.0} {small_unit}", meters / small_factor)
+    } else {
+        format!("{} {unit}", one_decimal(value))
+    }
+}
+
+/// Formats an altitude, including its reference.
+pub fn altitude(altitude: Altitude, units: UnitSystem) -> String {
+    let reference = match altitude.reference {
+        AltitudeReference::AGL => "AGL",
+        AltitudeReference::MSL => "MSL",
+    };
+    match units.altitude {
+        AltitudeUnit::Meters => format!("{} m {reference}", altitude.meters),
+        AltitudeUnit::Feet => format!("{:.0} ft {reference}", altitude.feet()),
+    }
+}
+
+pub fn speed(speed: Speed, units: UnitSystem) -> String {
+    match units.speed {
+        SpeedUnit::KilometersPerHour => format!("{:.0} km/h", speed.kilometers_per_hour()),
+        SpeedUnit::MilesPerHour => format!("{:.0} mph", speed.miles_per_hour()),
+        SpeedUnit::Knots => format!("{:.0} kt", speed.knots()),
+    }
+}
+
+/// Formats a task duration as hours and minutes, e.g. "3h30" or "45 min".
+pub fn duration(duration: Duration) -> String {
+    let minutes = (duration.as_secs() + 30) / 60;
+    if minutes < 60 {
+        format!("{minutes} min")
+    } else {
+        format!("{}h{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+/// The name XCSoar uses for a task type.
+pub fn task_type(task_type: TaskType) -> &'static str {
+    match task_type {
+        TaskType::AAT => "AAT",
+        TaskType::RT => "Racing",
+        TaskType::FAIGeneral => "FAI badges/records",
+        TaskType::FAITriangle => "FAI triangle",
+        TaskType::FAIOR => "FAI out and return",
+        TaskType::FAIGoal => "FAI goal",
+        TaskType::MAT => "MAT",
+        TaskType::Mixed => "Mixed",
+        TaskType::Touring => "Touring",
+    }
+}
+
+/// The name of a zone type without its parameters, matching [`zone`].
+pub fn zone_kind(kind: ZoneKind) -> &'static str {
+    match kind {
+        ZoneKind::Cylinder => "Cylinder",
+        ZoneKind::Line => "Line",
+        ZoneKind::Keyhole => "DAeC keyhole",
+        ZoneKind::FAISector => "FAI sector",
+        ZoneKind::Sector => "Sector",
+        ZoneKind::SymmetricQuadrant => "Symmetric quadrant",
+        ZoneKind::CustomKeyhole => "Keyhole",
+        ZoneKind::MatCylinder => "MAT cylinder",
+        ZoneKind::BGAStartSector => "BGA start sector",
+        ZoneKind::BGAFixedCourse => "BGA fixed course",
+        ZoneKind::BGAEnhancedOption => "BGA enhanced option",
+    }
+}
+
+/// Describes an observation zone, e.g. "Cylinder 500 m" or "FAI sector".
+pub fn zone(zone: &ObservationZone, units: UnitSystem) -> String {
+    let distance = |meters| distance(meters, units);
+    let resolved = zone.resolve();
+    match zone {
+        ObservationZone::Cylinder { radius } => format!("Cylinder {}", distance(*radius)),
+        ObservationZone::Line { length } => format!("Line {}", distance(*length)),
+        ObservationZone::Keyhole => "DAeC keyhole".to_string(),
+        ObservationZone::FAISector => "FAI sector".to_string(),
+        ObservationZone::Sector {
+            radius,
+            start_radial,
+            end_radial,
+            inner_radius: None,
+        } => format!(
+            "Sector {} {}–{}",
+            distance(*radius),
+            angle(*start_radial),
+            angle(*end_radial)
+        ),
+        ObservationZone::Sector {
+            radius,
+            start_radial,
+            end_radial,
+            inner_radius: Some(inner_radius),
+        } => format!(
+            "Annular sector {}/{} {}–{}",
+            distance(*radius),
+            distance(*inner_radius),
+            angle(*start_radial),
+            angle(*end_radial)
+        ),
+        ObservationZone::SymmetricQuadrant { .. } => format!(
+            "Symmetric quadrant {} {}",
+            distance(resolved.radius),
+            angle(resolved.angle)
+        ),
+        ObservationZone::CustomKeyhole { .. } => format!(
+            "Keyhole {}/{} {}",
+            distance(resolved.radius),
+            distance(resolved.inner_radius),
+            angle(resolved.angle)
+        ),
+        ObservationZone::MatCylinder => format!("MAT cylinder {}", distance(resolved.radius)),
+        ObservationZone::BGAStartSector => "BGA start sector".to_string(),
+        ObservationZone::BGAFixedCourse => "BGA fixed course".to_string(),
+        ObservationZone::BGAEnhancedOption => "BGA enhanced option".to_string(),
+    }
+}
+
+/// Describes a task point's zone, prefixed with its role for start and
+/// finish points, e.g. "Start line 10 km" or "Finish cylinder 2 km".
+pub fn point(point: &Point, units: UnitSystem) -> String {
+    let zone = zone(&point.observation_zone, units);
+    let role = match point.point_type {
+        PointType::Start => "Start",
+        PointType::OptionalStart => "Optional start",
+        PointType::Finish => "Finish",
+        PointType::Turn | PointType::Area => return zone,
+    };
+    // Keep acronyms like "FAI" and "BGA" in upper case
+    let mut chars = zone.chars();
+    match (chars.next(), chars.next()) {
+        (Some(_), Some(second)) if second.is_ascii_uppercase() => format!("{role} {zone}"),
+        (Some(first), _) => format!("{role} {}{}", first.to_ascii_lowercase(), &zone[1..]),
+        (None, _) => role.to_string(),
+    }
+}
+
+/// Renders a multi-line task summary with one line per point and the
+/// distance of the leg leading to it. Optional starts are labelled "Opt".
+pub fn task(task: &Task, units: UnitSystem) -> String {
+    let mut output = task_type(task.task_type).to_string();
+    if let Some(min_time) = task
+        .aat_min_time
+        .filter(|_| task.task_type == TaskType::AAT)
+    {
+        let _ = write!(output, " {}", duration(min_time));
+    }
+    let _ = writeln!(output, ", {}", distance(task.distance(), units));
+
+    let legs = task.leg_distances();
+    let mut turnpoint = 0;
+    let mut course_position = 0usize;
+    for task_point in &task.points {
+        let label = match task_point.point_type {
+            PointType::Start => "Start".to_string(),
+            PointType::OptionalStart => "Opt".to_string(),
+            PointType::Finish => "Finish".to_string(),
+            PointType::Turn | PointType::Area => {
+                turnpoint += 1;
+                format!("TP{turnpoint}")
+            }
+        };
+        let _ = write!(
+            output,
+            "{label:<6} {} ({})",
+            task_point.waypoint.name,
+            point(task_point, units)
+        );
+        // Optional starts are alternatives to the start, not part of a leg
+        if task_point.point_type != PointType::OptionalStart {
+            if let Some(leg) = course_position.checked_sub(1).map(|leg| legs[leg]) {
+                let _ = write!(output, ", leg {}", distance(leg, units));
+            }
+            course_position += 1;
+        }
+        output.push('\n');
+    }
+    output
+}
+
+fn angle(degrees: f64) -> String {
+    format!("{}°", one_decimal(degrees))
+}
+
+/// Formats with at most one decimal, dropping a trailing `.0`.
+fn one_decimal(value: f64) -> String {
+    let rounded = (value * 10.0).round() / 10.0;
+    if rounded.fract() == 0.0 {
+        format!("{rounded:.0}")
+    } else {
+        format!("{rounded:.1}")
+    }
+}
+
+/// Formats a bearing as three whole degrees, e.g. "005°", the way it's
+/// read out on the radio.
+pub fn bearing(degrees: f64) -> String {
+    format!("{:03.0}°", degrees.round().rem_euclid(360.0))
+}
+
+/// Renders one line per leg of `task` with its bearing, distances and
+/// progress, e.g. for a task sheet. `legs` comes from [`Task::legs`] or
+/// [`Task::legs_with_options`].
+pub fn legs(task: &Task, legs: &[Leg], units: UnitSystem) -> String {
+    let name = |index: usize| &task.points[index].waypoint.name;
+    let mut output = String::new();
+    for (number, leg) in (1..).zip(legs) {
+        let _ = write!(
+            output,
+            "{number}. {} - {}: {}",
+            name(leg.from),
+            name(leg.to),
+            bearing(leg.true_bearing)
+        );
+        if let Some(magnetic) = leg.magnetic_bearing {
+            let _ = write!(output, " ({} M)", bearing(magnetic));
+        }
+        let _ = writeln!(
+            output,
+            ", {} (adjusted {}), total {} ({:.0}%)",
+            distance(leg.distance, units),
+            distance(leg.adjusted_distance, units),
+            distance(leg.cumulative_distance, units),
+            leg.percentage
+        );
+    }
+    output
+}
+
+/// Renders one line per task point with its name, coordinates in
+/// `notation` and elevation, e.g. "Bergheim  5057.900N 00636.467E  70 m".
+pub fn waypoints(task: &Task, notation: Notation, units: UnitSystem) -> String {
+    let width = task
+        .points
+        .iter()
+        .map(|point| point.waypoint.name.chars().count())
+        .max()
+        .unwrap_or_default();
+    let mut output = String::new();
+    for point in &task.points {
+        let waypoint = &point.waypoint;
+        let _ = write!(
+            output,
+            "{:<width$}  {}",
+            waypoint.name,
+            coordinates::format(&waypoint.location, notation)
+        );
+        if let Some(meters) = waypoint.altitude {
+            let _ = match units.altitude {
+                AltitudeUnit::Meters => write!(output, "  {meters:.0} m"),
+                AltitudeUnit::Feet => write!(output, "  {:.0} ft", meters / METERS_PER_FOOT),
+            };
+        }
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn leg_table() {
+        let task = crate::from_str(include_str!("../fixtures/racing-task.tsk")).unwrap();
+        let options = crate::legs::LegOptions {
+            declination: Some(crate::legs::Declination::Fixed(2.5)),
+            ..Default::default()
+        };
+        assert_snapshot!(legs(
+            &task,
+            &task.legs_with_options(options),
+            UnitSystem::METRIC
+        ));
+    }
+
+    #[test]
+    fn distances() {
+        assert_eq!(distance(500.0, UnitSystem::METRIC), "500 m");
+        assert_eq!(distance(10000.0, UnitSystem::METRIC), "10 km");
+        assert_eq!(distance(12_345.0, UnitSystem::METRIC), "12.3 km");
+        assert_eq!(distance(999.99, UnitSystem::METRIC), "1 km");
+        assert_eq!(distance(500.0, UnitSystem::IMPERIAL), "1640 ft");
+        assert_eq!(distance(10000.0, UnitSystem::IMPERIAL), "6.2 mi");
+        assert_eq!(distance(10000.0, UnitSystem::AVIATION), "5.4 NM");
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(duration(Duration::from_secs(12600)), "3h30");
+        assert_eq!(duration(Duration::from_secs(2700)), "45 min");
+        assert_eq!(duration(Duration::from_secs(7200)), "2h00");
+    }
+
+    #[test]
+    fn altitudes() {
+        assert_eq!(
+            altitude(Altitude::msl(1500), UnitSystem::METRIC),
+            "1500 m MSL"
+        );
+        assert_eq!(
+            altitude(Altitude::agl(1500), UnitSystem::AVIATION),
+            "4921 ft AGL"
+        );
+    }
+
+    #[test]
+    fn zones() {
+        let xml = include_str!("../fixtures/all-oz-types.tsk");
+        let task = crate::from_str(xml).unwrap();
+        let zones = task
+            .points
+            .iter()
+            .map(|p| point(p, UnitSystem::METRIC))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_snapshot!(zones);
+    }
+
+    #[test]
+    fn task_summary_metric() {
+        let xml = include_str!("../fixtures/aat-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_snapshot!(super::task(&task, UnitSystem::METRIC));
+    }
+
+    #[test]
+    fn task_summary_imperial() {
+        let xml = include_str!("../fixtures/racing-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_snapshot!(super::task(&task, UnitSystem::IMPERIAL));
+    }
+
+    #[test]
+    fn waypoint_table() {
+        let task = crate::from_str(include_str!("../fixtures/racing-task.tsk")).unwrap();
+        assert_snapshot!(waypoints(
+            &task,
+            Notation::DegreesDecimalMinutes,
+            UnitSystem::METRIC
+        ));
+    }
+
+    #[test]
+    fn task_summary_optional_start() {
+        let xml = include_str!("../fixtures/optional-start-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_snapshot!(super::task(&task, UnitSystem::METRIC));
+    }
+}
@@ -0,0 +1,90 @@
+//! GeoJSON feature collections.
+//!
+//! The task is written as a `LineString` course line followed by one `Point`
+//! feature per task point. Each point carries its name, type and a
+//! description of its observation zone in the feature properties. Optional
+//! starts get a `Point` feature but are not part of the course line.
+
+use crate::Task;
+use crate::format::{self, UnitSystem};
+use serde_json::{Value, json};
+
+pub fn to_value(task: &Task) -> Value {
+    let coordinates = task
+        .course()
+        .iter()
+        .map(|(_, point)| {
+            let location = point.waypoint.location;
+            [location.longitude, location.latitude]
+        })
+        .collect::<Vec<_>>();
+
+    let mut features = vec![json!({
+        "type": "Feature",
+        "properties": {
+            "feature_type": "course_line",
+            "task_type": task.task_type,
+            "distance": task.distance(),
+        },
+        "geometry": {
+            "type": "LineString",
+            "coordinates": coordinates,
+        },
+    })];
+
+    for point in &task.points {
+        let location = point.waypoint.location;
+        features.push(json!({
+            "type": "Feature",
+            "properties": {
+                "feature_type": "waypoint",
+                "name": point.waypoint.name,
+                "point_type": point.point_type,
+                "zone": format::point(point, UnitSystem::METRIC),
+            },
+            "geometry": {
+                "type": "Point",
+                "coordinates": [location.longitude, location.latitude],
+            },
+        }));
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+pub fn to_string(task: &Task) -> String {
+    serde_json::to_string_pretty(&to_value(task)).expect("GeoJSON values always serialize")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn geojson_racing_task() {
+        let xml = include_str!("../fixtures/racing-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_json_snapshot!(to_value(&task), {
+            ".features[0].properties.distance" => insta::rounded_redaction(3),
+        });
+    }
+
+    #[test]
+    fn optional_starts_are_not_on_the_course_line() {
+        let xml = include_str!("../fixtures/optional-start-task.tsk");
+        let value = to_value(&crate::from_str(xml).unwrap());
+        let coordinates = value["features"][0]["geometry"]["coordinates"]
+            .as_array()
+            .unwrap();
+        assert_eq!(coordinates.len(), 5);
+        assert_eq!(coordinates[4], json!([7.03695, 51.0977833]));
+
+        let optional_start = &value["features"][6]["properties"];
+        assert_eq!(optional_start["name"], "Langenfeld-Wiescheid");
+        assert_eq!(optional_start["point_type"], "OptionalStart");
+    }
+}
@@ -0,0 +1,216 @@
+//! Distances and bearings on the FAI sphere or the WGS84 ellipsoid.
+
+use crate::{Location, Point, PointType, Task};
+use geographiclib_rs::{DirectGeodesic, Geodesic, InverseGeodesic};
+
+/// Radius of the FAI sphere used by XCSoar, in meters.
+pub const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// The shape of the earth that distances and bearings are measured on.
+///
+/// XCSoar and most scoring software use the FAI sphere, while badges and
+/// records are measured on the WGS84 ellipsoid. The two differ by up to
+/// about 0.5% on the same leg.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum EarthModel {
+    /// Great circles on a sphere with a radius of [`EARTH_RADIUS`].
+    #[default]
+    FaiSphere,
+
+    /// Geodesics on the WGS84 ellipsoid.
+    Wgs84,
+}
+
+impl EarthModel {
+    /// Distance from `from` to `to` in meters.
+    pub fn distance(self, from: &Location, to: &Location) -> f64 {
+        match self {
+            EarthModel::FaiSphere => {
+                let lat1 = from.latitude.to_radians();
+                let lat2 = to.latitude.to_radians();
+                let delta_lat = lat2 - lat1;
+                let delta_lon = (to.longitude - from.longitude).to_radians();
+
+                let a = (delta_lat / 2.0).sin().powi(2)
+                    + lat1.cos() * lat2.cos() * (delta_lon / 2.0).sin().powi(2);
+                2.0 * EARTH_RADIUS * a.sqrt().asin()
+            }
+            EarthModel::Wgs84 => {
+                Geodesic::wgs84().inverse(from.latitude, from.longitude, to.latitude, to.longitude)
+            }
+        }
+    }
+
+    /// Initial true bearing from `from` towards `to` in degrees, `0..360`.
+    pub fn bearing(self, from: &Location, to: &Location) -> f64 {
+        let bearing = match self {
+            EarthModel::FaiSphere => {
+                let lat1 = from.latitude.to_radians();
+                let lat2 = to.latitude.to_radians();
+                let delta_lon = (to.longitude - from.longitude).to_radians();
+
+                let y = delta_lon.sin() * lat2.cos();
+                let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * delta_lon.cos();
+                y.atan2(x).to_degrees()
+            }
+            EarthModel::Wgs84 => {
+                let (azimuth, _, _): (f64, f64, f64) = Geodesic::wgs84().inverse(
+                    from.latitude,
+                    from.longitude,
+                    to.latitude,
+                    to.longitude,
+                );
+                azimuth
+            }
+        };
+        bearing.rem_euclid(360.0)
+    }
+
+    /// The location reached by starting from `from` with the true bearing
+    /// `bearing` in degrees and going straight for `distance` meters.
+    pub fn destination(self, from: &Location, bearing: f64, distance: f64) -> Location {
+        let (latitude, longitude) = match self {
+            EarthModel::FaiSphere => {
+                let lat1 = from.latitude.to_radians();
+                let bearing = bearing.to_radians();
+                let delta = distance / EARTH_RADIUS;
+
+                let lat2 =
+                    (lat1.sin() * delta.cos() + lat1.cos() * delta.sin() * bearing.cos()).asin();
+                let delta_lon = (bearing.sin() * delta.sin() * lat1.cos())
+                    .atan2(delta.cos() - lat1.sin() * lat2.sin());
+                (lat2.to_degrees(), from.longitude + delta_lon.to_degrees())
+            }
+            EarthModel::Wgs84 => {
+                Geodesic::wgs84().direct(from.latitude, from.longitude, bearing, distance)
+            }
+        };
+        Location {
+            longitude: (longitude + 180.0).rem_euclid(360.0) - 180.0,
+            latitude,
+        }
+    }
+}
+
+impl Location {
+    /// Great circle distance to `other` on the FAI sphere in meters, see
+    /// [`EarthModel::distance`].
+    pub fn distance_to(&self, other: &Location) -> f64 {
+        EarthModel::FaiSphere.distance(self, other)
+    }
+
+    /// Initial true bearing towards `other` on the FAI sphere in degrees,
+    /// `0..360`, see [`EarthModel::bearing`].
+    pub fn bearing_to(&self, other: &Location) -> f64 {
+        EarthModel::FaiSphere.bearing(self, other)
+    }
+
+    /// The location reached by following the great circle with initial true
+    /// bearing `bearing` in degrees for `distance` meters on the FAI sphere,
+    /// see [`EarthModel::destination`].
+    pub fn destination(&self, bearing: f64, distance: f64) -> Location {
+        EarthModel::FaiSphere.destination(self, bearing, distance)
+    }
+}
+
+impl Task {
+    /// The points flown in order with their index in `points`, leaving out
+    /// optional starts, which XCSoar stores after the finish.
+    pub(crate) fn course(&self) -> Vec<(usize, &Point)> {
+        self.points
+            .iter()
+            .enumerate()
+            .filter(|(_, point)| point.point_type != PointType::OptionalStart)
+            .collect()
+    }
+
+    /// Center to center distance of each leg on the FAI sphere in meters.
+    pub fn leg_distances(&self) -> Vec<f64> {
+        self.leg_distances_with_model(EarthModel::FaiSphere)
+    }
+
+    /// Center to center distance of each leg on `model` in meters. Legs
+    /// connect the points of the course, without optional starts.
+    pub fn leg_distances_with_model(&self, model: EarthModel) -> Vec<f64> {
+        self.course()
+            .windows(2)
+            .map(|pair| model.distance(&pair[0].1.waypoint.location, &pair[1].1.waypoint.location))
+            .collect()
+    }
+
+    /// Nominal task distance on the FAI sphere in meters, measured between
+    /// the waypoints without taking observation zones into account.
+    pub fn distance(&self) -> f64 {
+        self.distance_with_model(EarthModel::FaiSphere)
+    }
+
+    /// Nominal task distance on `model` in meters, see [`Task::distance`].
+    pub fn distance_with_model(&self, model: EarthModel) -> f64 {
+        self.leg_distances_with_model(model).iter().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance_and_bearing() {
+        let from = Location {
+            longitude: 0.0,
+            latitude: 0.0,
+        };
+        let to = Location {
+            longitude: 1.0,
+            latitude: 0.0,
+        };
+        assert!((from.distance_to(&to) - 111_194.93).abs() < 0.01);
+        assert!((from.bearing_to(&to) - 90.0).abs() < 1e-9);
+        assert!((to.bearing_to(&from) - 270.0).abs() < 1e-9);
+
+        let destination = from.destination(90.0, 111_194.93);
+        assert!((destination.longitude - 1.0).abs() < 1e-6);
+        assert!(destination.latitude.abs() < 1e-9);
+    }
+
+    #[test]
+    fn wgs84() {
+        let at = |longitude, latitude| Location {
+            longitude,
+            latitude,
+        };
+        let model = EarthModel::Wgs84;
+        // One degree along the equator and along the meridian
+        assert!((model.distance(&at(0.0, 0.0), &at(1.0, 0.0)) - 111_319.491).abs() < 0.001);
+        assert!((model.distance(&at(0.0, 0.0), &at(0.0, 1.0)) - 110_574.389).abs() < 0.001);
+        assert!((model.bearing(&at(0.0, 0.0), &at(1.0, 0.0)) - 90.0).abs() < 1e-9);
+        assert!((model.bearing(&at(1.0, 0.0), &at(0.0, 0.0)) - 270.0).abs() < 1e-9);
+
+        let destination = model.destination(&at(0.0, 0.0), 0.0, 110_574.389);
+        assert!(destination.longitude.abs() < 1e-9);
+        assert!((destination.latitude - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn task_distance() {
+        let xml = include_str!("../fixtures/fai-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_eq!(task.leg_distances().len(), 4);
+        assert!(
+            (task.distance() - 132_939.0).abs() < 1.0,
+            "{}",
+            task.distance()
+        );
+
+        let wgs84 = task.distance_with_model(EarthModel::Wgs84);
+        assert!((wgs84 - task.distance()).abs() / wgs84 < 0.005, "{wgs84}");
+    }
+
+    #[test]
+    fn optional_starts_are_not_legs() {
+        let task = crate::from_str(include_str!("../fixtures/optional-start-task.tsk")).unwrap();
+        let expected = crate::from_str(include_str!("../fixtures/racing-task.tsk")).unwrap();
+        assert_eq!(task.leg_distances(), expected.leg_distances());
+        assert_eq!(task.distance(), expected.distance());
+    }
+}
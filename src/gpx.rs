@@ -0,0 +1,79 @@
+//! GPX 1.1 routes.
+//!
+//! The task is written as a single `<rte>` with one `<rtept>` per task
+//! point. Observation zones have no GPX equivalent and are only described in
+//! each point's `<desc>`. Optional starts are not part of the route and are
+//! written as separate `<wpt>` waypoints.
+
+use crate::format::{self, UnitSystem};
+use crate::{Point, PointType, Task};
+use quick_xml::escape::escape;
+use std::fmt::Write as FmtWrite;
+
+pub fn to_writer(mut writer: impl FmtWrite, task: &Task) -> std::fmt::Result {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<gpx version="1.1" creator="xcsoar-tasks" xmlns="http://www.topografix.com/GPX/1/1">"#
+    )?;
+    for point in &task.points {
+        if point.point_type == PointType::OptionalStart {
+            write_point(&mut writer, "wpt", point)?;
+        }
+    }
+    writeln!(writer, "  <rte>")?;
+    writeln!(
+        writer,
+        "    <name>{}</name>",
+        escape(format::task_type(task.task_type))
+    )?;
+    for (_, point) in task.course() {
+        write_point(&mut writer, "rtept", point)?;
+    }
+    writeln!(writer, "  </rte>")?;
+    writeln!(writer, "</gpx>")
+}
+
+fn write_point(writer: &mut impl FmtWrite, element: &str, point: &Point) -> std::fmt::Result {
+    // Waypoints sit directly in `<gpx>`, route points one level deeper
+    let indent = if element == "wpt" { "  " } else { "    " };
+    let waypoint = &point.waypoint;
+    writeln!(
+        writer,
+        r#"{indent}<{element} lat="{}" lon="{}">"#,
+        waypoint.location.latitude, waypoint.location.longitude
+    )?;
+    if let Some(altitude) = waypoint.altitude {
+        writeln!(writer, "{indent}  <ele>{altitude}</ele>")?;
+    }
+    writeln!(writer, "{indent}  <name>{}</name>", escape(&waypoint.name))?;
+    let description = format::point(point, UnitSystem::METRIC);
+    writeln!(writer, "{indent}  <desc>{}</desc>", escape(&description))?;
+    writeln!(writer, "{indent}</{element}>")
+}
+
+pub fn to_string(task: &Task) -> String {
+    let mut buffer = String::new();
+    to_writer(&mut buffer, task).expect("writing to a String cannot fail");
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn gpx_racing_task() {
+        let xml = include_str!("../fixtures/racing-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_snapshot!(to_string(&task));
+    }
+
+    #[test]
+    fn gpx_optional_start_task() {
+        let xml = include_str!("../fixtures/optional-start-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_snapshot!(to_string(&task));
+    }
+}
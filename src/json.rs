@@ -0,0 +1,543 @@
+//! JSON representation of tasks.
+//!
+//! The XML (de)serialization of [`Task`] is tied to the `.tsk` format, with
+//! `@`-prefixed attribute names and numbers written as strings, so this
+//! module uses its own model that is meant to be exchanged with other
+//! services. Field names are stable; new optional fields may be added.
+//!
+//! ```json
+//! {
+//!   "type": "AAT",
+//!   "aat_min_time": 12600,
+//!   "start_max_speed": 41.67,
+//!   "start_max_height": { "meters": 1000, "reference": "MSL" },
+//!   "start_open_time": 43200,
+//!   "points": [
+//!     {
+//!       "type": "Start",
+//!       "waypoint": {
+//!         "name": "Langenfeld-Wiescheid",
+//!         "altitude": 83.0,
+//!         "location": { "latitude": 51.1408, "longitude": 6.9834 }
+//!       },
+//!       "observation_zone": { "type": "Line", "length": 2000.0 }
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! - Durations (`aat_min_time`, `pev_start_wait_time`, `pev_start_window`)
+//!   are whole seconds.
+//! - Times of day (`start_open_time`, `start_close_time`) are seconds since
+//!   midnight UTC.
+//! - `start_max_speed` is in meters per second.
+//! - Heights are whole meters with an `AGL` or `MSL` reference.
+//! - Distances and radii are meters, angles are degrees.
+//! - Optional fields are omitted when unset.
+//! - Observation zones are tagged by `type` using the XCSoar zone names.
+
+use crate::checks;
+use crate::{
+    Altitude, AltitudeReference, Location, ObservationZone, Point, PointType, RangeError, Speed,
+    Task, TaskType, TimeOfDay, Waypoint, duration_to_seconds, seconds_to_duration,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+pub fn to_value(task: &Task) -> Value {
+    serde_json::to_value(TaskJson::from(task)).expect("tasks always serialize to JSON")
+}
+
+pub fn to_string(task: &Task) -> String {
+    serde_json::to_string(&TaskJson::from(task)).expect("tasks always serialize to JSON")
+}
+
+pub fn to_string_pretty(task: &Task) -> String {
+    serde_json::to_string_pretty(&TaskJson::from(task)).expect("tasks always serialize to JSON")
+}
+
+/// JSON Schema describing the representation used by this module, including
+/// the coordinate and zone ranges enforced by [`Task::check`].
+#[cfg(feature = "schemars")]
+pub fn schema() -> schemars::Schema {
+    schemars::schema_for!(TaskJson)
+}
+
+/// Reads a task, rejecting out of range values like [`crate::from_str`].
+pub fn from_value(value: Value) -> Result<Task, serde_json::Error> {
+    serde_json::from_value::<TaskJson>(value).and_then(checked)
+}
+
+pub fn from_str(json: &str) -> Result<Task, serde_json::Error> {
+    serde_json::from_str::<TaskJson>(json).and_then(checked)
+}
+
+pub fn from_slice(json: &[u8]) -> Result<Task, serde_json::Error> {
+    serde_json::from_slice::<TaskJson>(json).and_then(checked)
+}
+
+fn checked(json: TaskJson) -> Result<Task, serde_json::Error> {
+    let task = Task::try_from(json).map_err(serde::de::Error::custom)?;
+    task.check().map_err(serde::de::Error::custom)?;
+    Ok(task)
+}
+
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(
+    feature = "schemars",
+    derive(schemars::JsonSchema),
+    schemars(rename = "Task")
+)]
+struct TaskJson {
+    #[serde(rename = "type")]
+    task_type: TaskType,
+
+    /// Minimum task time of AAT tasks, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    aat_min_time: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start_requires_arm: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start_score_exit: Option<bool>,
+
+    /// Maximum ground speed when crossing the start, in m/s. `0` means no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "schemars", schemars(range(min = 0)))]
+    start_max_speed: Option<f64>,
+
+    /// Maximum height when crossing the start. `0` means no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start_max_height: Option<AltitudeJson>,
+
+    /// Opening of the start, in seconds since midnight UTC.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "schemars", schemars(range(max = 86399)))]
+    start_open_time: Option<u32>,
+
+    /// Closing of the start, in seconds since midnight UTC.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "schemars", schemars(range(max = 86399)))]
+    start_close_time: Option<u32>,
+
+    /// Minimum height when crossing the finish.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    finish_min_height: Option<AltitudeJson>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fai_finish: Option<bool>,
+
+    /// Time between the pilot event (PEV) and the opening of the start, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pev_start_wait_time: Option<u32>,
+
+    /// How long the start stays open after a pilot event, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pev_start_window: Option<u32>,
+
+    /// Written by XCSoar 6 and kept for round trips, like the following
+    /// fields.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    task_scored: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_points: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_points: Option<u32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    homogeneous_tps: Option<bool>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    is_closed: Option<bool>,
+
+    #[serde(default)]
+    points: Vec<PointJson>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(
+    feature = "schemars",
+    derive(schemars::JsonSchema),
+    schemars(rename = "Altitude")
+)]
+struct AltitudeJson {
+    meters: u32,
+    reference: AltitudeReference,
+}
+
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(
+    feature = "schemars",
+    derive(schemars::JsonSchema),
+    schemars(rename = "Point")
+)]
+struct PointJson {
+    #[serde(rename = "type")]
+    point_type: PointType,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    score_exit: Option<bool>,
+
+    waypoint: WaypointJson,
+
+    observation_zone: ObservationZoneJson,
+}
+
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(
+    feature = "schemars",
+    derive(schemars::JsonSchema),
+    schemars(rename = "Waypoint")
+)]
+struct WaypointJson {
+    name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+
+    /// Elevation of the waypoint in meters MSL.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    altitude: Option<f64>,
+
+    location: LocationJson,
+}
+
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(
+    feature = "schemars",
+    derive(schemars::JsonSchema),
+    schemars(rename = "Location")
+)]
+struct LocationJson {
+    #[cfg_attr(feature = "schemars", schemars(range(min = -90, max = 90)))]
+    latitude: f64,
+    #[cfg_attr(feature = "schemars", schemars(range(min = -180, max = 180)))]
+    longitude: f64,
+}
+
+#[derive(Deserialize, Serialize)]
+#[cfg_attr(
+    feature = "schemars",
+    derive(schemars::JsonSchema),
+    schemars(rename = "ObservationZone")
+)]
+#[serde(tag = "type")]
+enum ObservationZoneJson {
+    /// Cylinder with the given radius in meters.
+    Cylinder {
+        #[cfg_attr(feature = "schemars", schemars(extend("exclusiveMinimum" = 0)))]
+        radius: f64,
+    },
+
+    /// Line gate with the given length in meters.
+    Line {
+        #[cfg_attr(feature = "schemars", schemars(extend("exclusiveMinimum" = 0)))]
+        length: f64,
+    },
+
+    /// DAeC keyhole: 500 m cylinder or 10 km 90° sector.
+    Keyhole,
+
+    /// FAI 90° sector with infinite length sides.
+    FAISector,
+
+    /// Sector between two radials in degrees, annular if `inner_radius` is set.
+    Sector {
+        #[cfg_attr(feature = "schemars", schemars(extend("exclusiveMinimum" = 0)))]
+        radius: f64,
+        start_radial: f64,
+        end_radial: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "schemars", schemars(range(min = 0)))]
+        inner_radius: Option<f64>,
+    },
+
+    /// Symmetric quadrant, defaulting to a 10 km radius and 90° angle.
+    SymmetricQuadrant {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "schemars", schemars(extend("exclusiveMinimum" = 0)))]
+        radius: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(
+            feature = "schemars",
+            schemars(range(max = 360), extend("exclusiveMinimum" = 0))
+        )]
+        angle: Option<f64>,
+    },
+
+    /// Keyhole defaulting to a 10 km radius, 500 m inner radius and 90° angle.
+    CustomKeyhole {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "schemars", schemars(extend("exclusiveMinimum" = 0)))]
+        radius: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(
+            feature = "schemars",
+            schemars(range(max = 360), extend("exclusiveMinimum" = 0))
+        )]
+        angle: Option<f64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[cfg_attr(feature = "schemars", schemars(range(min = 0)))]
+        inner_radius: Option<f64>,
+    },
+
+    /// Fixed 1-mile radius cylinder for Modified Area Tasks.
+    MatCylinder,
+
+    /// BGA start sector: 5 km 180° sector.
+    BGAStartSector,
+
+    /// BGA fixed course: 500 m cylinder or 20 km 90° sector.
+    BGAFixedCourse,
+
+    /// BGA enhanced option: 500 m cylinder or 10 km 180° sector.
+    BGAEnhancedOption,
+}
+
+impl From<&Task> for TaskJson {
+    fn from(task: &Task) -> Self {
+        TaskJson {
+            task_type: task.task_type,
+            aat_min_time: task.aat_min_time.map(duration_to_seconds),
+            start_requires_arm: task.start_requires_arm,
+            start_score_exit: task.start_score_exit,
+            start_max_speed: task.start_max_speed.map(Speed::meters_per_second),
+            start_max_height: task.start_max_height.map(AltitudeJson::from),
+            start_open_time: task.start_open_time.map(TimeOfDay::as_seconds),
+            start_close_time: task.start_close_time.map(TimeOfDay::as_seconds),
+            finish_min_height: task.finish_min_height.map(AltitudeJson::from),
+            fai_finish: task.fai_finish,
+            pev_start_wait_time: task.pev_start_wait_time.map(duration_to_seconds),
+            pev_start_window: task.pev_start_window.map(duration_to_seconds),
+            task_scored: task.task_scored,
+            min_points: task.min_points,
+            max_points: task.max_points,
+            homogeneous_tps: task.homogeneous_tps,
+            is_closed: task.is_closed,
+            points: task.points.iter().map(PointJson::from).collect(),
+        }
+    }
+}
+
+impl TryFrom<TaskJson> for Task {
+    type Error = RangeError;
+
+    fn try_from(json: TaskJson) -> Result<Self, RangeError> {
+        Ok(Task {
+            task_type: json.task_type,
+            aat_min_time: json.aat_min_time.map(seconds_to_duration),
+            start_requires_arm: json.start_requires_arm,
+            start_score_exit: json.start_score_exit,
+            start_max_speed: json.start_max_speed.map(Speed::from_meters_per_second),
+            start_max_height: json.start_max_height.map(Altitude::from),
+            start_open_time: checks::time_of_day("start_open_time", json.start_open_time)?,
+            start_close_time: checks::time_of_day("start_close_time", json.start_close_time)?,
+            finish_min_height: json.finish_min_height.map(Altitude::from),
+            fai_finish: json.fai_finish,
+            pev_start_wait_time: json.pev_start_wait_time.map(seconds_to_duration),
+            pev_start_window: json.pev_start_window.map(seconds_to_duration),
+            task_scored: json.task_scored,
+            min_points: json.min_points,
+            max_points: json.max_points,
+            homogeneous_tps: json.homogeneous_tps,
+            is_closed: json.is_closed,
+            points: json.points.into_iter().map(Point::from).collect(),
+        })
+    }
+}
+
+impl From<Altitude> for AltitudeJson {
+    fn from(altitude: Altitude) -> Self {
+        AltitudeJson {
+            meters: altitude.meters,
+            reference: altitude.reference,
+        }
+    }
+}
+
+impl From<AltitudeJson> for Altitude {
+    fn from(json: AltitudeJson) -> Self {
+        Altitude {
+            meters: json.meters,
+            reference: json.reference,
+        }
+    }
+}
+
+impl From<&Point> for PointJson {
+    fn from(point: &Point) -> Self {
+        let waypoint = &point.waypoint;
+        PointJson {
+            point_type: point.point_type,
+            score_exit: point.score_exit,
+            waypoint: WaypointJson {
+                name: waypoint.name.clone(),
+                id: waypoint.id.clone(),
+                comment: waypoint.comment.clone(),
+                altitude: waypoint.altitude,
+                location: LocationJson {
+                    latitude: waypoint.location.latitude,
+                    longitude: waypoint.location.longitude,
+                },
+            },
+            observation_zone: ObservationZoneJson::from(&point.observation_zone),
+        }
+    }
+}
+
+impl From<PointJson> for Point {
+    fn from(json: PointJson) -> Self {
+        let waypoint = json.waypoint;
+        Point {
+            point_type: json.point_type,
+            score_exit: json.score_exit,
+            waypoint: Waypoint {
+                name: waypoint.name,
+                altitude: waypoint.altitude,
+                id: waypoint.id,
+                comment: waypoint.comment,
+                location: Location {
+                    longitude: waypoint.location.longitude,
+                    latitude: waypoint.location.latitude,
+                },
+            },
+            observation_zone: json.observation_zone.into(),
+        }
+    }
+}
+
+impl From<&ObservationZone> for ObservationZoneJson {
+    fn from(zone: &ObservationZone) -> Self {
+        match *zone {
+            ObservationZone::Cylinder { radius } => ObservationZoneJson::Cylinder { radius },
+            ObservationZone::Line { length } => ObservationZoneJson::Line { length },
+            ObservationZone::Keyhole => ObservationZoneJson::Keyhole,
+            ObservationZone::FAISector => ObservationZoneJson::FAISector,
+            ObservationZone::Sector {
+                radius,
+                start_radial,
+                end_radial,
+                inner_radius,
+            } => ObservationZoneJson::Sector {
+                radius,
+                start_radial,
+                end_radial,
+                inner_radius,
+            },
+            ObservationZone::SymmetricQuadrant { radius, angle } => {
+                ObservationZoneJson::SymmetricQuadrant { radius, angle }
+            }
+            ObservationZone::CustomKeyhole {
+                radius,
+                angle,
+                inner_radius,
+            } => ObservationZoneJson::CustomKeyhole {
+                radius,
+                angle,
+                inner_radius,
+            },
+            ObservationZone::MatCylinder => ObservationZoneJson::MatCylinder,
+            ObservationZone::BGAStartSector => ObservationZoneJson::BGAStartSector,
+            ObservationZone::BGAFixedCourse => ObservationZoneJson::BGAFixedCourse,
+            ObservationZone::BGAEnhancedOption => ObservationZoneJson::BGAEnhancedOption,
+        }
+    }
+}
+
+impl From<ObservationZoneJson> for ObservationZone {
+    fn from(json: ObservationZoneJson) -> Self {
+        match json {
+            ObservationZoneJson::Cylinder { radius } => ObservationZone::Cylinder { radius },
+            ObservationZoneJson::Line { length } => ObservationZone::Line { length },
+            ObservationZoneJson::Keyhole => ObservationZone::Keyhole,
+            ObservationZoneJson::FAISector => ObservationZone::FAISector,
+            ObservationZoneJson::Sector {
+                radius,
+                start_radial,
+                end_radial,
+                inner_radius,
+            } => ObservationZone::Sector {
+                radius,
+                start_radial,
+                end_radial,
+                inner_radius,
+            },
+            ObservationZoneJson::SymmetricQuadrant { radius, angle } => {
+                ObservationZone::SymmetricQuadrant { radius, angle }
+            }
+            ObservationZoneJson::CustomKeyhole {
+                radius,
+                angle,
+                inner_radius,
+            } => ObservationZone::CustomKeyhole {
+                radius,
+                angle,
+                inner_radius,
+            },
+            ObservationZoneJson::MatCylinder => ObservationZone::MatCylinder,
+            ObservationZoneJson::BGAStartSector => ObservationZone::BGAStartSector,
+            ObservationZoneJson::BGAFixedCourse => ObservationZone::BGAFixedCourse,
+            ObservationZoneJson::BGAEnhancedOption => ObservationZone::BGAEnhancedOption,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn json_aat_task() {
+        let xml = include_str!("../fixtures/aat-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_snapshot!(to_string_pretty(&task));
+    }
+
+    #[test]
+    fn json_all_oz_types() {
+        let xml = include_str!("../fixtures/all-oz-types.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_snapshot!(to_string_pretty(&task));
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        for xml in [
+            include_str!("../fixtures/aat-task.tsk"),
+            include_str!("../fixtures/all-oz-types.tsk"),
+            include_str!("../fixtures/fai-task.tsk"),
+            include_str!("../fixtures/racing-task.tsk"),
+        ] {
+            let task = crate::from_str(xml).unwrap();
+            assert_eq!(from_str(&to_string(&task)).unwrap(), task);
+            assert_eq!(from_value(to_value(&task)).unwrap(), task);
+        }
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn json_schema() {
+        let schema = serde_json::to_string_pretty(&schema()).unwrap();
+        assert_snapshot!(schema);
+    }
+
+    #[test]
+    fn json_rejects_unknown_zone_type() {
+        let json = r#"{
+            "type": "RT",
+            "points": [{
+                "type": "Turn",
+                "waypoint": { "name": "A", "location": { "latitude": 50.0, "longitude": 7.0 } },
+                "observation_zone": { "type": "Donut", "radius": 500.0 }
+            }]
+        }"#;
+        assert!(from_str(json).is_err());
+    }
+}
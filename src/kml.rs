@@ -0,0 +1,91 @@
+//! KML documents for Google Earth.
+//!
+//! The course line is written as a `LineString` placemark, followed by one
+//! point placemark per task point. Optional starts get a placemark but are
+//! not part of the course line.
+
+use crate::Task;
+use crate::format::{self, UnitSystem};
+use quick_xml::escape::escape;
+use std::fmt::Write as FmtWrite;
+
+pub fn to_writer(mut writer: impl FmtWrite, task: &Task) -> std::fmt::Result {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#)?;
+    writeln!(writer, "  <Document>")?;
+    writeln!(
+        writer,
+        "    <name>{}</name>",
+        escape(format::task_type(task.task_type))
+    )?;
+
+    writeln!(writer, "    <Placemark>")?;
+    writeln!(writer, "      <name>Course</name>")?;
+    writeln!(writer, "      <LineString>")?;
+    writeln!(writer, "        <tessellate>1</tessellate>")?;
+    write!(writer, "        <coordinates>")?;
+    for (index, (_, point)) in task.course().iter().enumerate() {
+        if index > 0 {
+            write!(writer, " ")?;
+        }
+        let location = point.waypoint.location;
+        write!(writer, "{},{}", location.longitude, location.latitude)?;
+    }
+    writeln!(writer, "</coordinates>")?;
+    writeln!(writer, "      </LineString>")?;
+    writeln!(writer, "    </Placemark>")?;
+
+    for point in &task.points {
+        let waypoint = &point.waypoint;
+        let description = format::point(point, UnitSystem::METRIC);
+        writeln!(writer, "    <Placemark>")?;
+        writeln!(writer, "      <name>{}</name>", escape(&waypoint.name))?;
+        writeln!(
+            writer,
+            "      <description>{}</description>",
+            escape(&description)
+        )?;
+        writeln!(writer, "      <Point>")?;
+        writeln!(
+            writer,
+            "        <coordinates>{},{}</coordinates>",
+            waypoint.location.longitude, waypoint.location.latitude
+        )?;
+        writeln!(writer, "      </Point>")?;
+        writeln!(writer, "    </Placemark>")?;
+    }
+
+    writeln!(writer, "  </Document>")?;
+    writeln!(writer, "</kml>")
+}
+
+pub fn to_string(task: &Task) -> String {
+    let mut buffer = String::new();
+    to_writer(&mut buffer, task).expect("writing to a String cannot fail");
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn kml_racing_task() {
+        let xml = include_str!("../fixtures/racing-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_snapshot!(to_string(&task));
+    }
+
+    #[test]
+    fn optional_starts_are_not_on_the_course_line() {
+        let xml = include_str!("../fixtures/optional-start-task.tsk");
+        let kml = to_string(&crate::from_str(xml).unwrap());
+        assert!(
+            kml.contains("<coordinates>6.9322167,51.08195 6.2772167,50.9991667 6.1922167,51.1766667 6.6077833,50.965 7.03695,51.0977833</coordinates>"),
+            "{kml}"
+        );
+        assert!(kml.contains("<name>Langenfeld-Wiescheid</name>"), "{kml}");
+        assert!(kml.contains("<description>Optional start line"), "{kml}");
+    }
+}
@@ -0,0 +1,340 @@
+//! Per-leg distances and bearings for task sheets.
+//!
+//! ```
+//! let xml = std::fs::read_to_string("fixtures/racing-task.tsk").unwrap();
+//! let task = xcsoar_tasks::from_str(&xml).unwrap();
+//! for leg in task.legs() {
+//!     println!(
+//!         "{:03.0}° {:.1} km ({:.0}%)",
+//!         leg.true_bearing,
+//!         leg.distance / 1000.0,
+//!         leg.percentage
+//!     );
+//! }
+//! ```
+
+use crate::magnetic::{MagneticModel, decimal_year};
+use crate::{EarthModel, Location, Point, Task, ZoneShape};
+use std::time::SystemTime;
+
+/// Rounds of improving the touch points of all cylinders in turn.
+const OPTIMIZATION_ROUNDS: usize = 5;
+
+/// Options for [`Task::legs_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LegOptions<'a> {
+    pub earth_model: EarthModel,
+
+    /// Source of the magnetic declination, by default the bundled
+    /// [`MagneticModel::wmm`] at the current date. Without it,
+    /// [`Leg::magnetic_bearing`] is `None`.
+    pub declination: Option<Declination<'a>>,
+}
+
+impl Default for LegOptions<'_> {
+    fn default() -> Self {
+        LegOptions {
+            earth_model: EarthModel::default(),
+            declination: Some(Declination::Model(
+                MagneticModel::wmm(),
+                decimal_year(SystemTime::now()),
+            )),
+        }
+    }
+}
+
+/// How magnetic bearings are derived from true ones.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Declination<'a> {
+    /// The same declination in degrees for every leg, positive to the east.
+    Fixed(f64),
+
+    /// The declination at the start of each leg, from a magnetic model at a
+    /// decimal year, see [`crate::magnetic`].
+    Model(&'a MagneticModel, f64),
+}
+
+impl Declination<'_> {
+    fn at(&self, location: &Location) -> f64 {
+        match *self {
+            Declination::Fixed(declination) => declination,
+            Declination::Model(model, year) => model.declination(location, 0.0, year),
+        }
+    }
+}
+
+/// The leg between two consecutive points of the course.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Leg {
+    /// Zero-based index in `Task::points` of the point the leg starts at.
+    pub from: usize,
+
+    /// Zero-based index in `Task::points` of the point the leg ends at.
+    pub to: usize,
+
+    /// Initial true bearing in degrees, `0..360`.
+    pub true_bearing: f64,
+
+    /// Initial magnetic bearing in degrees, `0..360`, if a declination is
+    /// known.
+    pub magnetic_bearing: Option<f64>,
+
+    /// Center to center distance in meters.
+    pub distance: f64,
+
+    /// Distance in meters on the shortest path touching the cylinders, see
+    /// [`Task::legs_with_options`].
+    pub adjusted_distance: f64,
+
+    /// Center to center distance from the start to the end of this leg in
+    /// meters.
+    pub cumulative_distance: f64,
+
+    /// Share of the nominal task distance covered at the end of this leg,
+    /// `0..=100`.
+    pub percentage: f64,
+}
+
+impl Task {
+    /// Returns the legs of the task on the FAI sphere, with magnetic
+    /// bearings from the bundled WMM at the current date.
+    pub fn legs(&self) -> Vec<Leg> {
+        self.legs_with_options(LegOptions::default())
+    }
+
+    /// Returns one [`Leg`] for each pair of consecutive points, leaving out
+    /// optional starts, which XCSoar stores after the finish.
+    ///
+    /// The adjusted distance follows the shortest path that touches the
+    /// cylinders of start, turn and finish points, as XCSoar shows it for
+    /// racing tasks. Other zones count from the waypoint itself.
+    pub fn legs_with_options(&self, options: LegOptions) -> Vec<Leg> {
+        let model = options.earth_model;
+        let course = self.course();
+        let points = course.iter().map(|(_, point)| *point).collect::<Vec<_>>();
+        let touch_points = touch_points(model, &points);
+
+        let total = self.distance_with_model(model);
+        let mut cumulative_distance = 0.0;
+        course
+            .windows(2)
+            .enumerate()
+            .map(|(position, pair)| {
+                let ((from_index, from), (to_index, to)) = (pair[0], pair[1]);
+                let from = &from.waypoint.location;
+                let to = &to.waypoint.location;
+                let true_bearing = model.bearing(from, to);
+                let distance = model.distance(from, to);
+                cumulative_distance += distance;
+                Leg {
+                    from: from_index,
+                    to: to_index,
+                    true_bearing,
+                    magnetic_bearing: options
+                        .declination
+                        .map(|declination| (true_bearing - declination.at(from)).rem_euclid(360.0)),
+                    distance,
+                    adjusted_distance: model
+                        .distance(&touch_points[position], &touch_points[position + 1]),
+                    cumulative_distance,
+                    percentage: if total > 0.0 {
+                        cumulative_distance / total * 100.0
+                    } else {
+                        100.0
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+/// Improves the touch points one cylinder at a time, with the neighbours
+/// fixed, which converges after a few rounds for realistic tasks.
+fn touch_points(model: EarthModel, points: &[&Point]) -> Vec<Location> {
+    let mut touch_points = points
+        .iter()
+        .map(|point| point.waypoint.location)
+        .collect::<Vec<_>>();
+    for _ in 0..OPTIMIZATION_ROUNDS {
+        for (index, point) in points.iter().enumerate() {
+            let zone = point.observation_zone.resolve();
+            if zone.shape != ZoneShape::Cylinder {
+                continue;
+            }
+            let center = point.waypoint.location;
+            let previous = index.checked_sub(1).map(|index| touch_points[index]);
+            let next = touch_points.get(index + 1).copied();
+            let path_length = |bearing: f64| {
+                let touch_point = model.destination(&center, bearing, zone.radius);
+                previous.map_or(0.0, |previous| model.distance(&previous, &touch_point))
+                    + next.map_or(0.0, |next| model.distance(&touch_point, &next))
+            };
+            touch_points[index] = model.destination(&center, minimize(path_length), zone.radius);
+        }
+    }
+    touch_points
+}
+
+/// Finds the bearing that minimizes `f`, by sampling every 5° and refining
+/// around the best sample with a golden section search.
+fn minimize(f: impl Fn(f64) -> f64) -> f64 {
+    const STEP: f64 = 5.0;
+    let best = (0..72)
+        .map(|i| i as f64 * STEP)
+        .min_by(|a, b| f(*a).total_cmp(&f(*b)))
+        .unwrap_or_default();
+
+    let ratio = (5f64.sqrt() - 1.0) / 2.0;
+    let (mut low, mut high) = (best - STEP, best + STEP);
+    while high - low > 1e-6 {
+        let a = high - ratio * (high - low);
+        let b = low + ratio * (high - low);
+        if f(a) < f(b) {
+            high = b;
+        } else {
+            low = a;
+        }
+    }
+    (low + high) / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn racing_task_legs() {
+        let task = crate::from_str(include_str!("../fixtures/racing-task.tsk")).unwrap();
+        let options = LegOptions {
+            declination: Some(Declination::Fixed(2.5)),
+            ..Default::default()
+        };
+        let rendered = task
+            .legs_with_options(options)
+            .iter()
+            .map(|leg| {
+                format!(
+                    "{} {:.1}° true {:.1}° magnetic, {:.0} m, adjusted {:.0} m, total {:.0} m ({:.1}%)",
+                    leg.from,
+                    leg.true_bearing,
+                    leg.magnetic_bearing.unwrap(),
+                    leg.distance,
+                    leg.adjusted_distance,
+                    leg.cumulative_distance,
+                    leg.percentage
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_snapshot!(rendered);
+    }
+
+    #[test]
+    fn declination_from_model() {
+        let task = crate::from_str(include_str!("../fixtures/racing-task.tsk")).unwrap();
+        // An axial dipole points to true north everywhere
+        let model = MagneticModel::from_cof("2025.0 AXIAL\n 1 0 -29000 0 0 0\n").unwrap();
+        let options = LegOptions {
+            declination: Some(Declination::Model(&model, 2026.0)),
+            ..Default::default()
+        };
+        for leg in task.legs_with_options(options) {
+            assert_eq!(leg.magnetic_bearing, Some(leg.true_bearing));
+        }
+    }
+
+    #[test]
+    fn wmm_by_default() {
+        let task = crate::from_str(include_str!("../fixtures/racing-task.tsk")).unwrap();
+        let year = decimal_year(SystemTime::now());
+        for leg in task.legs() {
+            let location = &task.points[leg.from].waypoint.location;
+            let expected = MagneticModel::wmm().magnetic_bearing(leg.true_bearing, location, year);
+            // The clock moves on a little between the two evaluations
+            let difference = (leg.magnetic_bearing.unwrap() - expected + 180.0).rem_euclid(360.0);
+            assert!((difference - 180.0).abs() < 1e-3, "{leg:?}");
+        }
+
+        let options = LegOptions {
+            declination: None,
+            ..Default::default()
+        };
+        assert!(
+            task.legs_with_options(options)
+                .iter()
+                .all(|leg| leg.magnetic_bearing.is_none())
+        );
+    }
+
+    #[test]
+    fn legs_add_up() {
+        let task = crate::from_str(include_str!("../fixtures/aat-task.tsk")).unwrap();
+        for earth_model in [EarthModel::FaiSphere, EarthModel::Wgs84] {
+            let options = LegOptions {
+                earth_model,
+                ..Default::default()
+            };
+            let legs = task.legs_with_options(options);
+            let last = legs.last().unwrap();
+            assert!(
+                (last.cumulative_distance - task.distance_with_model(earth_model)).abs() < 1e-6
+            );
+            assert!((last.percentage - 100.0).abs() < 1e-9);
+            assert!(legs.iter().all(|leg| leg.magnetic_bearing.is_some()));
+            assert!(legs.iter().all(|leg| leg.adjusted_distance < leg.distance));
+        }
+    }
+
+    #[test]
+    fn optional_starts_are_left_out() {
+        let xml = include_str!("../fixtures/optional-start-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        let without = crate::from_str(include_str!("../fixtures/racing-task.tsk")).unwrap();
+        assert_eq!(task.points.len(), without.points.len() + 1);
+        let options = LegOptions {
+            declination: Some(Declination::Model(MagneticModel::wmm(), 2026.0)),
+            ..Default::default()
+        };
+        assert_eq!(
+            task.legs_with_options(options),
+            without.legs_with_options(options)
+        );
+        assert_eq!(task.legs().last().unwrap().to, 4);
+    }
+
+    #[test]
+    fn cylinders_on_a_straight_course() {
+        let mut task = crate::from_str(include_str!("../fixtures/racing-task.tsk")).unwrap();
+        task.points.truncate(3);
+        for (index, point) in task.points.iter_mut().enumerate() {
+            point.waypoint.location = Location {
+                longitude: index as f64 * 0.1,
+                latitude: 0.0,
+            };
+            point.observation_zone = crate::ObservationZone::Cylinder { radius: 1000.0 };
+        }
+        let adjusted = task
+            .legs()
+            .iter()
+            .map(|leg| leg.adjusted_distance)
+            .sum::<f64>();
+        // Only the start and finish cylinders shorten a straight course
+        assert!(
+            (task.distance() - adjusted - 2000.0).abs() < 0.01,
+            "{adjusted}"
+        );
+
+        // Going back to the start shortens both legs at the turnpoint
+        task.points[2].waypoint.location = task.points[0].waypoint.location;
+        let adjusted = task
+            .legs()
+            .iter()
+            .map(|leg| leg.adjusted_distance)
+            .sum::<f64>();
+        assert!(
+            (task.distance() - adjusted - 4000.0).abs() < 0.01,
+            "{adjusted}"
+        );
+    }
+}
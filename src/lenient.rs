@@ -0,0 +1,768 @@
+//! Lenient parsing that recovers from invalid attribute values.
+//!
+//! The strict parser in [`crate::from_str`] rejects a task as soon as a
+//! single attribute is malformed. For bulk imports of archived tasks it is
+//! often preferable to keep going, replace the offending value with a
+//! sensible default and report what was changed. The functions in this module
+//! only fail if the input is not well-formed XML.
+
+use crate::{
+    AltitudeReference, Location, ObservationZone, ParseError, Point, PointType, RangeError, Speed,
+    Task, TaskType, TimeOfDay, Waypoint, checks, seconds_to_duration, to_altitude,
+};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Task type used when the `type` attribute is missing or unknown.
+pub const FALLBACK_TASK_TYPE: TaskType = TaskType::RT;
+
+/// Point type used when the `type` attribute is missing or unknown.
+pub const FALLBACK_POINT_TYPE: PointType = PointType::Turn;
+
+/// Default `Cylinder` radius, also used for unknown zone types.
+pub const DEFAULT_RADIUS: f64 = 10000.0;
+
+/// Default `Line` length.
+pub const DEFAULT_LINE_LENGTH: f64 = 1000.0;
+
+/// A recoverable problem found while parsing leniently.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// XPath of the element containing the problem, e.g.
+    /// `Task/Point[2]/ObservationZone` for the zone of the second point.
+    pub element: String,
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiagnosticKind {
+    /// An enumerated attribute had a value that isn't recognized.
+    UnknownValue {
+        attribute: &'static str,
+        value: String,
+        replacement: String,
+    },
+
+    /// A numeric or boolean attribute couldn't be parsed or is out of range.
+    /// Optional attributes are dropped (`replacement` is `None`), required
+    /// ones get a default.
+    InvalidValue {
+        attribute: &'static str,
+        value: String,
+        replacement: Option<String>,
+    },
+
+    /// A required attribute was missing and a default was used.
+    MissingAttribute {
+        attribute: &'static str,
+        replacement: String,
+    },
+
+    /// A coordinate was missing, couldn't be parsed or is out of range, so
+    /// the point was skipped.
+    InvalidCoordinate {
+        attribute: &'static str,
+        value: Option<String>,
+    },
+
+    /// The observation zone type is unknown and was replaced with a cylinder.
+    UnknownZoneType { value: String, replacement: String },
+
+    /// A required child element was missing, so the point was skipped.
+    MissingElement { element: &'static str },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.element)?;
+        match &self.kind {
+            DiagnosticKind::UnknownValue {
+                attribute,
+                value,
+                replacement,
+            } => write!(
+                f,
+                "unknown {attribute} {value:?}, using {replacement:?} instead"
+            ),
+            DiagnosticKind::InvalidValue {
+                attribute,
+                value,
+                replacement: Some(replacement),
+            } => write!(
+                f,
+                "invalid {attribute} {value:?}, using {replacement:?} instead"
+            ),
+            DiagnosticKind::InvalidValue {
+                attribute,
+                value,
+                replacement: None,
+            } => write!(f, "invalid {attribute} {value:?}, ignoring it"),
+            DiagnosticKind::MissingAttribute {
+                attribute,
+                replacement,
+            } => write!(f, "missing {attribute}, using {replacement:?}"),
+            DiagnosticKind::InvalidCoordinate {
+                attribute,
+                value: Some(value),
+            } => write!(f, "invalid {attribute} {value:?}, skipping point"),
+            DiagnosticKind::InvalidCoordinate {
+                attribute,
+                value: None,
+            } => write!(f, "missing {attribute}, skipping point"),
+            DiagnosticKind::UnknownZoneType { value, replacement } => {
+                write!(
+                    f,
+                    "unknown zone type {value:?}, using {replacement} instead"
+                )
+            }
+            DiagnosticKind::MissingElement { element } => {
+                write!(f, "missing {element} element, skipping point")
+            }
+        }
+    }
+}
+
+/// Parses a task, recovering from invalid attribute values.
+///
+/// Returns the task together with a diagnostic for every value that was
+/// replaced or dropped.
+pub fn from_str(xml: &str) -> Result<(Task, Vec<Diagnostic>), ParseError> {
+    let raw: RawTask = quick_xml::de::from_str(xml)?;
+    let mut converter = Converter::default();
+    let task = converter.task(raw);
+    Ok((task, converter.diagnostics))
+}
+
+/// Parses a task from raw bytes, decoding them like
+/// [`from_slice_lossy`](crate::from_slice_lossy).
+pub fn from_slice(bytes: &[u8]) -> Result<(Task, Vec<Diagnostic>), ParseError> {
+    from_str(&crate::encoding::decode(bytes, true)?)
+}
+
+/// One of the range checks in [`checks`], taking the attribute name and the
+/// parsed value.
+type Check = fn(&'static str, f64) -> Result<f64, RangeError>;
+
+/// Parses a number, returning `None` if it is malformed or fails `check`.
+fn checked_number(value: &str, check: impl FnOnce(f64) -> Result<f64, RangeError>) -> Option<f64> {
+    value
+        .trim()
+        .parse()
+        .ok()
+        .and_then(|parsed| check(parsed).ok())
+}
+
+#[derive(Default)]
+struct Converter {
+    diagnostics: Vec<Diagnostic>,
+    element: String,
+}
+
+impl Converter {
+    fn task(&mut self, raw: RawTask) -> Task {
+        self.element = "Task".to_string();
+        let start_max_height = self.opt_number("start_max_height", raw.start_max_height);
+        let start_max_height_ref =
+            self.altitude_reference("start_max_height_ref", raw.start_max_height_ref);
+        let finish_min_height = self.opt_number("finish_min_height", raw.finish_min_height);
+        let finish_min_height_ref =
+            self.altitude_reference("finish_min_height_ref", raw.finish_min_height_ref);
+
+        let mut task = Task {
+            task_type: self.task_type(raw.task_type),
+            aat_min_time: self
+                .opt_number("aat_min_time", raw.aat_min_time)
+                .map(seconds_to_duration),
+            start_requires_arm: self.opt_bool("start_requires_arm", raw.start_requires_arm),
+            start_score_exit: self.opt_bool("start_score_exit", raw.start_score_exit),
+            start_max_speed: self
+                .opt_checked("start_max_speed", raw.start_max_speed, checks::non_negative)
+                .map(Speed::from_meters_per_second),
+            start_max_height: to_altitude(start_max_height, start_max_height_ref),
+            start_open_time: self.opt_time("start_open_time", raw.start_open_time),
+            start_close_time: self.opt_time("start_close_time", raw.start_close_time),
+            finish_min_height: to_altitude(finish_min_height, finish_min_height_ref),
+            fai_finish: self.opt_bool("fai_finish", raw.fai_finish),
+            pev_start_wait_time: self
+                .opt_number("pev_start_wait_time", raw.pev_start_wait_time)
+                .map(seconds_to_duration),
+            pev_start_window: self
+                .opt_number("pev_start_window", raw.pev_start_window)
+                .map(seconds_to_duration),
+            task_scored: self.opt_bool("task_scored", raw.task_scored),
+            min_points: self.opt_number("min_points", raw.min_points),
+            max_points: self.opt_number("max_points", raw.max_points),
+            homogeneous_tps: self.opt_bool("homogeneous_tps", raw.homogeneous_tps),
+            is_closed: self.opt_bool("is_closed", raw.is_closed),
+            points: Vec::with_capacity(raw.points.len()),
+        };
+
+        for (index, point) in raw.points.into_iter().enumerate() {
+            self.element = format!("Task/Point[{}]", index + 1);
+            if let Some(point) = self.point(point) {
+                task.points.push(point);
+            }
+        }
+
+        task
+    }
+
+    fn point(&mut self, raw: RawPoint) -> Option<Point> {
+        let point_element = self.element.clone();
+        let point_type = self.point_type(raw.point_type);
+        let score_exit = self.opt_bool("score_exit", raw.score_exit);
+
+        let Some(waypoint) = raw.waypoint else {
+            self.report(DiagnosticKind::MissingElement {
+                element: "Waypoint",
+            });
+            return None;
+        };
+        self.element = format!("{point_element}/Waypoint");
+        let waypoint = self.waypoint(waypoint)?;
+
+        self.element = format!("{point_element}/ObservationZone");
+        let observation_zone = match raw.observation_zone {
+            Some(zone) => self.observation_zone(zone),
+            None => {
+                self.element = point_element;
+                self.report(DiagnosticKind::MissingElement {
+                    element: "ObservationZone",
+                });
+                return None;
+            }
+        };
+
+        Some(Point {
+            point_type,
+            score_exit,
+            waypoint,
+            observation_zone,
+        })
+    }
+
+    fn waypoint(&mut self, raw: RawWaypoint) -> Option<Waypoint> {
+        let name = match raw.name {
+            Some(name) => name,
+            None => {
+                self.report(DiagnosticKind::MissingAttribute {
+                    attribute: "name",
+                    replacement: String::new(),
+                });
+                String::new()
+            }
+        };
+        let altitude = self.opt_checked("altitude", raw.altitude, checks::finite);
+
+        let Some(location) = raw.location else {
+            self.report(DiagnosticKind::MissingElement {
+                element: "Location",
+            });
+            return None;
+        };
+        let waypoint_element = self.element.clone();
+        self.element = format!("{waypoint_element}/Location");
+        let location = self.location(location);
+        self.element = waypoint_element;
+
+        Some(Waypoint {
+            name,
+            altitude,
+            id: raw.id,
+            comment: raw.comment,
+            location: location?,
+        })
+    }
+
+    /// Coordinates have no meaningful default, so an invalid location causes
+    /// the point to be skipped.
+    fn location(&mut self, raw: RawLocation) -> Option<Location> {
+        let longitude = self.required_coordinate("longitude", raw.longitude, checks::longitude);
+        let latitude = self.required_coordinate("latitude", raw.latitude, checks::latitude);
+        Some(Location {
+            longitude: longitude?,
+            latitude: latitude?,
+        })
+    }
+
+    fn required_coordinate(
+        &mut self,
+        attribute: &'static str,
+        value: Option<String>,
+        check: Check,
+    ) -> Option<f64> {
+        let parsed = value
+            .as_deref()
+            .and_then(|value| checked_number(value, |parsed| check(attribute, parsed)));
+        if parsed.is_none() {
+            self.report(DiagnosticKind::InvalidCoordinate { attribute, value });
+        }
+        parsed
+    }
+
+    fn observation_zone(&mut self, raw: RawObservationZone) -> ObservationZone {
+        match raw.zone_type.as_deref() {
+            Some("Cylinder") => ObservationZone::Cylinder {
+                radius: self.number("radius", raw.radius, DEFAULT_RADIUS, checks::positive),
+            },
+            Some("Line") => ObservationZone::Line {
+                length: self.number("length", raw.length, DEFAULT_LINE_LENGTH, checks::positive),
+            },
+            Some("Keyhole") => ObservationZone::Keyhole,
+            Some("FAISector") => ObservationZone::FAISector,
+            Some("Sector") => {
+                let radius = self.number("radius", raw.radius, DEFAULT_RADIUS, checks::positive);
+                ObservationZone::Sector {
+                    radius,
+                    start_radial: self.number(
+                        "start_radial",
+                        raw.start_radial,
+                        0.0,
+                        checks::finite,
+                    ),
+                    end_radial: self.number("end_radial", raw.end_radial, 360.0, checks::finite),
+                    inner_radius: self.opt_checked("inner_radius", raw.inner_radius, |_, value| {
+                        checks::inner_radius(value, Some(radius))
+                    }),
+                }
+            }
+            Some("SymmetricQuadrant") => ObservationZone::SymmetricQuadrant {
+                radius: self.opt_checked("radius", raw.radius, checks::positive),
+                angle: self.opt_checked("angle", raw.angle, checks::angle),
+            },
+            Some("CustomKeyhole") => {
+                let radius = self.opt_checked("radius", raw.radius, checks::positive);
+                ObservationZone::CustomKeyhole {
+                    radius,
+                    angle: self.opt_checked("angle", raw.angle, checks::angle),
+                    inner_radius: self.opt_checked("inner_radius", raw.inner_radius, |_, value| {
+                        checks::inner_radius(value, radius)
+                    }),
+                }
+            }
+            Some("MatCylinder") => ObservationZone::MatCylinder,
+            Some("BGAStartSector") => ObservationZone::BGAStartSector,
+            Some("BGAFixedCourse") => ObservationZone::BGAFixedCourse,
+            Some("BGAEnhancedOption") => ObservationZone::BGAEnhancedOption,
+            other => {
+                // Keep the radius of unknown zones if it's usable, so the
+                // replacement covers roughly the same area.
+                let radius = raw
+                    .radius
+                    .and_then(|radius| {
+                        checked_number(&radius, |radius| checks::positive("radius", radius))
+                    })
+                    .unwrap_or(DEFAULT_RADIUS);
+                let replacement = ObservationZone::Cylinder { radius };
+                self.report(DiagnosticKind::UnknownZoneType {
+                    value: other.unwrap_or_default().to_string(),
+                    replacement: format!("Cylinder {radius} m"),
+                });
+                replacement
+            }
+        }
+    }
+
+    fn task_type(&mut self, value: Option<String>) -> TaskType {
+        let parsed = value.as_deref().and_then(|value| match value {
+            "AAT" => Some(TaskType::AAT),
+            "RT" => Some(TaskType::RT),
+            "FAIGeneral" => Some(TaskType::FAIGeneral),
+            "FAITriangle" => Some(TaskType::FAITriangle),
+            "FAIOR" => Some(TaskType::FAIOR),
+            "FAIGoal" => Some(TaskType::FAIGoal),
+            "MAT" => Some(TaskType::MAT),
+            "Mixed" => Some(TaskType::Mixed),
+            "Touring" => Some(TaskType::Touring),
+            _ => None,
+        });
+        self.enum_value("type", value, parsed, FALLBACK_TASK_TYPE)
+    }
+
+    fn point_type(&mut self, value: Option<String>) -> PointType {
+        let parsed = value.as_deref().and_then(|value| match value {
+            "Start" => Some(PointType::Start),
+            "Turn" => Some(PointType::Turn),
+            "Area" => Some(PointType::Area),
+            "Finish" => Some(PointType::Finish),
+            "OptionalStart" => Some(PointType::OptionalStart),
+            _ => None,
+        });
+        self.enum_value("type", value, parsed, FALLBACK_POINT_TYPE)
+    }
+
+    /// XCSoar treats anything other than "MSL" as AGL, but values other than
+    /// "AGL" are reported so they can be cleaned up.
+    fn altitude_reference(
+        &mut self,
+        attribute: &'static str,
+        value: Option<String>,
+    ) -> Option<AltitudeReference> {
+        let value = value?;
+        // XCSoar 6 wrote the references as numbers
+        let parsed = match value.as_str() {
+            "MSL" | "1" => Some(AltitudeReference::MSL),
+            "AGL" | "0" => Some(AltitudeReference::AGL),
+            _ => None,
+        };
+        Some(self.enum_value(attribute, Some(value), parsed, AltitudeReference::AGL))
+    }
+
+    fn enum_value<T: fmt::Debug>(
+        &mut self,
+        attribute: &'static str,
+        value: Option<String>,
+        parsed: Option<T>,
+        fallback: T,
+    ) -> T {
+        if let Some(parsed) = parsed {
+            return parsed;
+        }
+        let replacement = format!("{fallback:?}");
+        match value {
+            Some(value) => self.report(DiagnosticKind::UnknownValue {
+                attribute,
+                value,
+                replacement,
+            }),
+            None => self.report(DiagnosticKind::MissingAttribute {
+                attribute,
+                replacement,
+            }),
+        }
+        fallback
+    }
+
+    fn number(
+        &mut self,
+        attribute: &'static str,
+        value: Option<String>,
+        default: f64,
+        check: Check,
+    ) -> f64 {
+        let Some(value) = value else {
+            self.report(DiagnosticKind::MissingAttribute {
+                attribute,
+                replacement: default.to_string(),
+            });
+            return default;
+        };
+        match checked_number(&value, |parsed| check(attribute, parsed)) {
+            Some(parsed) => parsed,
+            None => {
+                self.report(DiagnosticKind::InvalidValue {
+                    attribute,
+                    value,
+                    replacement: Some(default.to_string()),
+                });
+                default
+            }
+        }
+    }
+
+    /// Like [`Self::opt_number`], but also drops values that are out of
+    /// range.
+    fn opt_checked(
+        &mut self,
+        attribute: &'static str,
+        value: Option<String>,
+        check: impl FnOnce(&'static str, f64) -> Result<f64, RangeError>,
+    ) -> Option<f64> {
+        let value = value?;
+        let parsed = checked_number(&value, |parsed| check(attribute, parsed));
+        if parsed.is_none() {
+            self.report(DiagnosticKind::InvalidValue {
+                attribute,
+                value,
+                replacement: None,
+            });
+        }
+        parsed
+    }
+
+    fn opt_number<T: FromStr>(
+        &mut self,
+        attribute: &'static str,
+        value: Option<String>,
+    ) -> Option<T> {
+        let value = value?;
+        let parsed = value.trim().parse().ok();
+        if parsed.is_none() {
+            self.report(DiagnosticKind::InvalidValue {
+                attribute,
+                value,
+                replacement: None,
+            });
+        }
+        parsed
+    }
+
+    fn opt_time(&mut self, attribute: &'static str, value: Option<String>) -> Option<TimeOfDay> {
+        let value = value?;
+        let parsed = value.trim().parse().ok().and_then(TimeOfDay::from_seconds);
+        if parsed.is_none() {
+            self.report(DiagnosticKind::InvalidValue {
+                attribute,
+                value,
+                replacement: None,
+            });
+        }
+        parsed
+    }
+
+    fn opt_bool(&mut self, attribute: &'static str, value: Option<String>) -> Option<bool> {
+        let value = value?;
+        match value.as_str() {
+            "1" | "true" => Some(true),
+            "0" | "false" => Some(false),
+            _ => {
+                self.report(DiagnosticKind::InvalidValue {
+                    attribute,
+                    value,
+                    replacement: None,
+                });
+                None
+            }
+        }
+    }
+
+    fn report(&mut self, kind: DiagnosticKind) {
+        self.diagnostics.push(Diagnostic {
+            element: self.element.clone(),
+            kind,
+        });
+    }
+}
+
+/// Mirror of the task model with every attribute kept as raw text.
+#[derive(Deserialize)]
+struct RawTask {
+    #[serde(rename = "@type")]
+    task_type: Option<String>,
+    #[serde(rename = "@aat_min_time")]
+    aat_min_time: Option<String>,
+    #[serde(rename = "@start_requires_arm")]
+    start_requires_arm: Option<String>,
+    #[serde(rename = "@start_score_exit")]
+    start_score_exit: Option<String>,
+    #[serde(rename = "@start_max_speed")]
+    start_max_speed: Option<String>,
+    #[serde(rename = "@start_max_height")]
+    start_max_height: Option<String>,
+    #[serde(rename = "@start_max_height_ref")]
+    start_max_height_ref: Option<String>,
+    #[serde(rename = "@start_open_time")]
+    start_open_time: Option<String>,
+    #[serde(rename = "@start_close_time")]
+    start_close_time: Option<String>,
+    #[serde(rename = "@finish_min_height")]
+    finish_min_height: Option<String>,
+    #[serde(rename = "@finish_min_height_ref")]
+    finish_min_height_ref: Option<String>,
+    #[serde(rename = "@fai_finish")]
+    fai_finish: Option<String>,
+    #[serde(rename = "@pev_start_wait_time")]
+    pev_start_wait_time: Option<String>,
+    #[serde(rename = "@pev_start_window")]
+    pev_start_window: Option<String>,
+    #[serde(rename = "@task_scored")]
+    task_scored: Option<String>,
+    #[serde(rename = "@min_points")]
+    min_points: Option<String>,
+    #[serde(rename = "@max_points")]
+    max_points: Option<String>,
+    #[serde(rename = "@homogeneous_tps")]
+    homogeneous_tps: Option<String>,
+    #[serde(rename = "@is_closed")]
+    is_closed: Option<String>,
+    #[serde(rename = "Point", default)]
+    points: Vec<RawPoint>,
+}
+
+#[derive(Deserialize)]
+struct RawPoint {
+    #[serde(rename = "@type")]
+    point_type: Option<String>,
+    #[serde(rename = "@score_exit")]
+    score_exit: Option<String>,
+    #[serde(rename = "Waypoint")]
+    waypoint: Option<RawWaypoint>,
+    #[serde(rename = "ObservationZone")]
+    observation_zone: Option<RawObservationZone>,
+}
+
+#[derive(Deserialize)]
+struct RawWaypoint {
+    #[serde(rename = "@name")]
+    name: Option<String>,
+    #[serde(rename = "@altitude")]
+    altitude: Option<String>,
+    #[serde(rename = "@id")]
+    id: Option<String>,
+    #[serde(rename = "@comment")]
+    comment: Option<String>,
+    #[serde(rename = "Location")]
+    location: Option<RawLocation>,
+}
+
+#[derive(Deserialize)]
+struct RawLocation {
+    #[serde(rename = "@longitude")]
+    longitude: Option<String>,
+    #[serde(rename = "@latitude")]
+    latitude: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RawObservationZone {
+    #[serde(rename = "@type")]
+    zone_type: Option<String>,
+    #[serde(rename = "@radius")]
+    radius: Option<String>,
+    #[serde(rename = "@length")]
+    length: Option<String>,
+    #[serde(rename = "@start_radial")]
+    start_radial: Option<String>,
+    #[serde(rename = "@end_radial")]
+    end_radial: Option<String>,
+    #[serde(rename = "@inner_radius")]
+    inner_radius: Option<String>,
+    #[serde(rename = "@angle")]
+    angle: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn fixtures_match_strict_parser() {
+        for xml in [
+            include_str!("../fixtures/aat-task.tsk"),
+            include_str!("../fixtures/racing-task.tsk"),
+            include_str!("../fixtures/fai-task.tsk"),
+            include_str!("../fixtures/all-oz-types.tsk"),
+        ] {
+            let (task, _) = from_str(xml).unwrap();
+            assert_eq!(task, crate::from_str(xml).unwrap());
+        }
+    }
+
+    #[test]
+    fn fai_task_reads_numeric_height_reference() {
+        let xml = include_str!("../fixtures/fai-task.tsk");
+        let (task, diagnostics) = from_str(xml).unwrap();
+        let reference = task.start_max_height.map(|height| height.reference);
+        assert_eq!(reference, Some(AltitudeReference::AGL));
+        assert_eq!(diagnostics, vec![]);
+    }
+
+    #[test]
+    fn reports_unknown_altitude_reference() {
+        let xml = include_str!("../fixtures/fai-task.tsk")
+            .replace(r#"start_max_height_ref="0""#, r#"start_max_height_ref="2""#);
+        let (_, diagnostics) = from_str(&xml).unwrap();
+        assert_eq!(
+            diagnostics,
+            vec![Diagnostic {
+                element: "Task".to_string(),
+                kind: DiagnosticKind::UnknownValue {
+                    attribute: "start_max_height_ref",
+                    value: "2".to_string(),
+                    replacement: "AGL".to_string(),
+                },
+            }]
+        );
+    }
+
+    #[test]
+    fn recovers_from_invalid_values() {
+        let xml = r#"
+            <Task type="Speed" aat_min_time="3h" fai_finish="yes">
+                <Point type="Start">
+                    <Waypoint name="Start" altitude="n/a">
+                        <Location longitude="7.0" latitude="51.0"/>
+                    </Waypoint>
+                    <ObservationZone type="Line" length="ten km"/>
+                </Point>
+                <Point type="Waypoint">
+                    <Waypoint name="TP1">
+                        <Location longitude="7.1" latitude="51.1"/>
+                    </Waypoint>
+                    <ObservationZone type="Circle" radius="3000"/>
+                </Point>
+                <Point type="Turn">
+                    <Waypoint name="Nowhere">
+                        <Location longitude="east" latitude="51.1"/>
+                    </Waypoint>
+                    <ObservationZone type="Cylinder"/>
+                </Point>
+                <Point type="Finish">
+                    <Waypoint name="Finish">
+                        <Location longitude="7.0" latitude="51.0"/>
+                    </Waypoint>
+                    <ObservationZone type="Cylinder"/>
+                </Point>
+            </Task>
+        "#;
+        let (task, diagnostics) = from_str(xml).unwrap();
+        assert_eq!(task.task_type, FALLBACK_TASK_TYPE);
+        assert_eq!(task.points.len(), 3);
+        assert_eq!(
+            task.points[1].observation_zone,
+            ObservationZone::Cylinder { radius: 3000.0 }
+        );
+
+        let rendered = diagnostics
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_snapshot!(rendered);
+    }
+
+    #[test]
+    fn replaces_out_of_range_values() {
+        let xml = r#"
+            <Task type="RT" start_max_speed="-10">
+                <Point type="Start">
+                    <Waypoint name="Start" altitude="inf">
+                        <Location longitude="7.0" latitude="51.0"/>
+                    </Waypoint>
+                    <ObservationZone type="Line" length="NaN"/>
+                </Point>
+                <Point type="Turn">
+                    <Waypoint name="Pole">
+                        <Location longitude="7.1" latitude="91.0"/>
+                    </Waypoint>
+                    <ObservationZone type="Cylinder"/>
+                </Point>
+                <Point type="Turn">
+                    <Waypoint name="TP1">
+                        <Location longitude="7.1" latitude="51.1"/>
+                    </Waypoint>
+                    <ObservationZone type="Sector" radius="-3000" start_radial="0"
+                        end_radial="inf" inner_radius="20000"/>
+                </Point>
+                <Point type="Finish">
+                    <Waypoint name="Finish">
+                        <Location longitude="7.0" latitude="51.0"/>
+                    </Waypoint>
+                    <ObservationZone type="SymmetricQuadrant" angle="720"/>
+                </Point>
+            </Task>
+        "#;
+        let (task, diagnostics) = from_str(xml).unwrap();
+        assert_eq!(task.points.len(), 3);
+        task.check().unwrap();
+
+        let rendered = diagnostics
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_snapshot!(rendered);
+    }
+}
@@ -1,21 +1,205 @@
 #![doc = include_str!("../README.md")]
 
+use serde::de::Error as _;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
 use std::fmt::Write as FmtWrite;
-use std::io::BufRead;
-
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-#[serde(rename = "Task")]
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write as IoWrite};
+use std::path::Path;
+use std::time::Duration;
+
+pub mod borrowed;
+mod checks;
+pub mod constraints;
+pub mod coordinates;
+pub mod cup;
+pub mod diff;
+mod encoding;
+pub mod flarm;
+pub mod format;
+pub mod geojson;
+mod geometry;
+pub mod gpx;
+pub mod json;
+pub mod kml;
+pub mod legs;
+pub mod lenient;
+pub mod lint;
+pub mod magnetic;
+#[cfg(feature = "geo-types")]
+pub mod polygon;
+pub mod repair;
+mod units;
+mod xcsoar;
+pub mod xctsk;
+mod zone;
+
+pub use checks::{RangeError, TaskRangeError};
+pub use geometry::{EARTH_RADIUS, EarthModel};
+pub use units::{Altitude, Speed, TimeOfDay};
+pub use zone::{Orientation, ResolvedZone, Scoring, ZoneShape};
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Task {
-    #[serde(rename = "@type")]
     pub task_type: TaskType,
 
+    /// Minimum task time of AAT tasks.
+    pub aat_min_time: Option<Duration>,
+
+    pub start_requires_arm: Option<bool>,
+    pub start_score_exit: Option<bool>,
+
+    /// Maximum ground speed when crossing the start, `0` meaning no limit.
+    pub start_max_speed: Option<Speed>,
+
+    /// Maximum height when crossing the start, `0` meaning no limit.
+    pub start_max_height: Option<Altitude>,
+
+    pub start_open_time: Option<TimeOfDay>,
+    pub start_close_time: Option<TimeOfDay>,
+
+    /// Minimum height when crossing the finish.
+    pub finish_min_height: Option<Altitude>,
+
+    pub fai_finish: Option<bool>,
+
+    /// Time between the pilot event (PEV) and the opening of the start.
+    pub pev_start_wait_time: Option<Duration>,
+
+    /// How long the start stays open after a pilot event.
+    pub pev_start_window: Option<Duration>,
+
+    /// Whether the task counts for scoring. This and the following fields
+    /// are written by XCSoar 6, while newer versions derive them from the
+    /// task type, see [`TaskType::constraints`]. They are kept so that
+    /// re-saved files don't lose them.
+    pub task_scored: Option<bool>,
+
+    pub min_points: Option<u32>,
+    pub max_points: Option<u32>,
+
+    /// Whether all turnpoints must have the same zone.
+    pub homogeneous_tps: Option<bool>,
+
+    /// Whether the task must finish at the start.
+    pub is_closed: Option<bool>,
+
+    pub points: Vec<Point>,
+}
+
+/// Deserializing rejects out of range values like [`from_str`], reporting
+/// them as custom errors of the deserializer.
+impl<'de> Deserialize<'de> for Task {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let task = Task::from_xml(TaskXml::deserialize(deserializer)?).map_err(D::Error::custom)?;
+        task.check().map_err(D::Error::custom)?;
+        Ok(task)
+    }
+}
+
+impl Task {
+    fn from_xml(xml: TaskXml<'_>) -> Result<Task, RangeError> {
+        Ok(Task {
+            task_type: xml.task_type,
+            aat_min_time: xml.aat_min_time.map(seconds_to_duration),
+            start_requires_arm: xml.start_requires_arm,
+            start_score_exit: xml.start_score_exit,
+            start_max_speed: xml.start_max_speed.map(Speed::from_meters_per_second),
+            start_max_height: to_altitude(xml.start_max_height, xml.start_max_height_ref),
+            start_open_time: checks::time_of_day("start_open_time", xml.start_open_time)?,
+            start_close_time: checks::time_of_day("start_close_time", xml.start_close_time)?,
+            finish_min_height: to_altitude(xml.finish_min_height, xml.finish_min_height_ref),
+            fai_finish: xml.fai_finish,
+            pev_start_wait_time: xml.pev_start_wait_time.map(seconds_to_duration),
+            pev_start_window: xml.pev_start_window.map(seconds_to_duration),
+            task_scored: xml.task_scored,
+            min_points: xml.min_points,
+            max_points: xml.max_points,
+            homogeneous_tps: xml.homogeneous_tps,
+            is_closed: xml.is_closed,
+            points: xml.points.into_owned(),
+        })
+    }
+}
+
+impl Serialize for Task {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let xml = TaskXml {
+            task_type: self.task_type,
+            aat_min_time: self.aat_min_time.map(duration_to_seconds),
+            start_requires_arm: self.start_requires_arm,
+            start_score_exit: self.start_score_exit,
+            start_max_speed: self.start_max_speed.map(Speed::meters_per_second),
+            start_max_height: self.start_max_height.map(|altitude| altitude.meters),
+            start_max_height_ref: self.start_max_height.map(|altitude| altitude.reference),
+            start_open_time: self.start_open_time.map(TimeOfDay::as_seconds),
+            start_close_time: self.start_close_time.map(TimeOfDay::as_seconds),
+            finish_min_height: self.finish_min_height.map(|altitude| altitude.meters),
+            finish_min_height_ref: self.finish_min_height.map(|altitude| altitude.reference),
+            fai_finish: self.fai_finish,
+            pev_start_wait_time: self.pev_start_wait_time.map(duration_to_seconds),
+            pev_start_window: self.pev_start_window.map(duration_to_seconds),
+            task_scored: self.task_scored,
+            min_points: self.min_points,
+            max_points: self.max_points,
+            homogeneous_tps: self.homogeneous_tps,
+            is_closed: self.is_closed,
+            points: Cow::Borrowed(&self.points),
+        };
+        xml.serialize(serializer)
+    }
+}
+
+/// Combines a height attribute with its reference, which XCSoar treats as
+/// AGL if missing.
+pub(crate) fn to_altitude(
+    meters: Option<u32>,
+    reference: Option<AltitudeReference>,
+) -> Option<Altitude> {
+    meters.map(|meters| Altitude {
+        meters,
+        reference: reference.unwrap_or(AltitudeReference::AGL),
+    })
+}
+
+pub(crate) fn seconds_to_duration(seconds: u32) -> Duration {
+    Duration::from_secs(seconds.into())
+}
+
+pub(crate) fn duration_to_seconds(duration: Duration) -> u32 {
+    u32::try_from(duration.as_secs()).unwrap_or(u32::MAX)
+}
+
+/// Mirror of [`Task`] matching the XML attributes one to one.
+///
+/// Typed fields like [`Altitude`] span two attributes, so `Task` is
+/// (de)serialized through this struct. The point type is generic so that
+/// [`borrowed::Task`] can share it.
+#[derive(Deserialize, Serialize)]
+#[serde(
+    rename = "Task",
+    bound(deserialize = "P: Deserialize<'de>", serialize = "P: Serialize")
+)]
+pub(crate) struct TaskXml<'a, P: Clone = Point> {
+    #[serde(rename = "@type")]
+    pub(crate) task_type: TaskType,
+
+    #[serde(
+        rename = "@task_scored",
+        default,
+        deserialize_with = "de_opt_bool",
+        serialize_with = "ser_opt_bool",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) task_scored: Option<bool>,
+
     #[serde(
         rename = "@aat_min_time",
         default,
         skip_serializing_if = "Option::is_none"
     )]
-    pub aat_min_time: Option<u32>,
+    pub(crate) aat_min_time: Option<u32>,
 
     #[serde(
         rename = "@start_requires_arm",
@@ -24,7 +208,7 @@ pub struct Task {
         serialize_with = "ser_opt_bool",
         skip_serializing_if = "Option::is_none"
     )]
-    pub start_requires_arm: Option<bool>,
+    pub(crate) start_requires_arm: Option<bool>,
 
     #[serde(
         rename = "@start_score_exit",
@@ -33,7 +217,7 @@ pub struct Task {
         serialize_with = "ser_opt_bool",
         skip_serializing_if = "Option::is_none"
     )]
-    pub start_score_exit: Option<bool>,
+    pub(crate) start_score_exit: Option<bool>,
 
     #[serde(
         rename = "@start_max_speed",
@@ -42,49 +226,49 @@ pub struct Task {
         serialize_with = "ser_opt_f64",
         skip_serializing_if = "Option::is_none"
     )]
-    pub start_max_speed: Option<f64>,
+    pub(crate) start_max_speed: Option<f64>,
 
     #[serde(
         rename = "@start_max_height",
         default,
         skip_serializing_if = "Option::is_none"
     )]
-    pub start_max_height: Option<u32>,
+    pub(crate) start_max_height: Option<u32>,
 
     #[serde(
         rename = "@start_max_height_ref",
         default,
         skip_serializing_if = "Option::is_none"
     )]
-    pub start_max_height_ref: Option<AltitudeReference>,
+    pub(crate) start_max_height_ref: Option<AltitudeReference>,
 
     #[serde(
         rename = "@start_open_time",
         default,
         skip_serializing_if = "Option::is_none"
     )]
-    pub start_open_time: Option<u32>,
+    pub(crate) start_open_time: Option<u32>,
 
     #[serde(
         rename = "@start_close_time",
         default,
         skip_serializing_if = "Option::is_none"
     )]
-    pub start_close_time: Option<u32>,
+    pub(crate) start_close_time: Option<u32>,
 
     #[serde(
         rename = "@finish_min_height",
         default,
         skip_serializing_if = "Option::is_none"
     )]
-    pub finish_min_height: Option<u32>,
+    pub(crate) finish_min_height: Option<u32>,
 
     #[serde(
         rename = "@finish_min_height_ref",
         default,
         skip_serializing_if = "Option::is_none"
     )]
-    pub finish_min_height_ref: Option<AltitudeReference>,
+    pub(crate) finish_min_height_ref: Option<AltitudeReference>,
 
     #[serde(
         rename = "@fai_finish",
@@ -93,27 +277,60 @@ pub struct Task {
         serialize_with = "ser_opt_bool",
         skip_serializing_if = "Option::is_none"
     )]
-    pub fai_finish: Option<bool>,
+    pub(crate) fai_finish: Option<bool>,
+
+    #[serde(
+        rename = "@min_points",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) min_points: Option<u32>,
+
+    #[serde(
+        rename = "@max_points",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) max_points: Option<u32>,
+
+    #[serde(
+        rename = "@homogeneous_tps",
+        default,
+        deserialize_with = "de_opt_bool",
+        serialize_with = "ser_opt_bool",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) homogeneous_tps: Option<bool>,
+
+    #[serde(
+        rename = "@is_closed",
+        default,
+        deserialize_with = "de_opt_bool",
+        serialize_with = "ser_opt_bool",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub(crate) is_closed: Option<bool>,
 
     #[serde(
         rename = "@pev_start_wait_time",
         default,
         skip_serializing_if = "Option::is_none"
     )]
-    pub pev_start_wait_time: Option<u32>,
+    pub(crate) pev_start_wait_time: Option<u32>,
 
     #[serde(
         rename = "@pev_start_window",
         default,
         skip_serializing_if = "Option::is_none"
     )]
-    pub pev_start_window: Option<u32>,
+    pub(crate) pev_start_window: Option<u32>,
 
     #[serde(rename = "Point", default)]
-    pub points: Vec<Point>,
+    pub(crate) points: Cow<'a, [P]>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum TaskType {
     AAT,
     RT,
@@ -127,6 +344,7 @@ pub enum TaskType {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum AltitudeReference {
     AGL,
     MSL,
@@ -134,9 +352,10 @@ pub enum AltitudeReference {
 
 impl<'de> Deserialize<'de> for AltitudeReference {
     fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
-        let s: String = Deserialize::deserialize(deserializer)?;
-        // XCSoar treats anything that's not "MSL" as AGL
-        if s == "MSL" {
+        let s = MaybeBorrowed::deserialize(deserializer)?;
+        // XCSoar treats anything that's not "MSL" as AGL. XCSoar 6 wrote
+        // `start_max_height_ref` as 0 (AGL) or 1 (MSL).
+        if s.0 == "MSL" || s.0 == "1" {
             Ok(AltitudeReference::MSL)
         } else {
             Ok(AltitudeReference::AGL)
@@ -165,7 +384,8 @@ pub struct Point {
     pub observation_zone: ObservationZone,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub enum PointType {
     Start,
     Turn,
@@ -341,23 +561,89 @@ pub enum ObservationZone {
     BGAEnhancedOption,
 }
 
+/// The variant of an [`ObservationZone`] without its parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ZoneKind {
+    Cylinder,
+    Line,
+    Keyhole,
+    FAISector,
+    Sector,
+    SymmetricQuadrant,
+    CustomKeyhole,
+    MatCylinder,
+    BGAStartSector,
+    BGAFixedCourse,
+    BGAEnhancedOption,
+}
+
+impl ObservationZone {
+    pub fn kind(&self) -> ZoneKind {
+        match self {
+            ObservationZone::Cylinder { .. } => ZoneKind::Cylinder,
+            ObservationZone::Line { .. } => ZoneKind::Line,
+            ObservationZone::Keyhole => ZoneKind::Keyhole,
+            ObservationZone::FAISector => ZoneKind::FAISector,
+            ObservationZone::Sector { .. } => ZoneKind::Sector,
+            ObservationZone::SymmetricQuadrant { .. } => ZoneKind::SymmetricQuadrant,
+            ObservationZone::CustomKeyhole { .. } => ZoneKind::CustomKeyhole,
+            ObservationZone::MatCylinder => ZoneKind::MatCylinder,
+            ObservationZone::BGAStartSector => ZoneKind::BGAStartSector,
+            ObservationZone::BGAFixedCourse => ZoneKind::BGAFixedCourse,
+            ObservationZone::BGAEnhancedOption => ZoneKind::BGAEnhancedOption,
+        }
+    }
+}
+
+/// An attribute value that borrows from the input unless it had to be
+/// unescaped, so numbers and flags can be parsed without allocating.
+struct MaybeBorrowed<'de>(Cow<'de, str>);
+
+impl<'de> Deserialize<'de> for MaybeBorrowed<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = MaybeBorrowed<'de>;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a string")
+            }
+
+            fn visit_borrowed_str<E>(self, value: &'de str) -> Result<Self::Value, E> {
+                Ok(MaybeBorrowed(Cow::Borrowed(value)))
+            }
+
+            fn visit_str<E>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(MaybeBorrowed(Cow::Owned(value.to_string())))
+            }
+
+            fn visit_string<E>(self, value: String) -> Result<Self::Value, E> {
+                Ok(MaybeBorrowed(Cow::Owned(value)))
+            }
+        }
+
+        deserializer.deserialize_str(Visitor)
+    }
+}
+
 fn de_f64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
-    let s: String = Deserialize::deserialize(deserializer)?;
-    s.parse().map_err(serde::de::Error::custom)
+    let s = MaybeBorrowed::deserialize(deserializer)?;
+    s.0.parse().map_err(serde::de::Error::custom)
 }
 
 fn de_opt_f64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
-    let s: Option<String> = Deserialize::deserialize(deserializer)?;
+    let s: Option<MaybeBorrowed> = Deserialize::deserialize(deserializer)?;
     match s {
-        Some(s) => s.parse().map(Some).map_err(serde::de::Error::custom),
+        Some(s) => s.0.parse().map(Some).map_err(serde::de::Error::custom),
         None => Ok(None),
     }
 }
 
 fn de_opt_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
-    let s: Option<String> = Deserialize::deserialize(deserializer)?;
-    match s {
-        Some(s) => match s.as_str() {
+    let s: Option<MaybeBorrowed> = Deserialize::deserialize(deserializer)?;
+    match s.map(|s| s.0) {
+        Some(s) => match s.as_ref() {
             "1" | "true" => Ok(Some(true)),
             "0" | "false" => Ok(Some(false)),
             _ => Err(serde::de::Error::custom(format!("invalid bool: {s}"))),
@@ -389,35 +675,218 @@ fn ser_opt_bool<S: Serializer>(value: &Option<bool>, serializer: S) -> Result<S:
 pub enum ParseError {
     #[error("XML parsing failed: {0}")]
     Xml(#[from] quick_xml::DeError),
+
+    #[error("input is not valid {0}")]
+    Encoding(&'static str),
+
+    #[error("unsupported encoding: {0}")]
+    UnknownEncoding(String),
+
+    /// The file is well-formed, but a coordinate or zone parameter is out of
+    /// range.
+    #[error("{0}")]
+    Range(#[from] TaskRangeError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum SerializeError {
     #[error("XML serialization failed: {0}")]
     Xml(#[from] quick_xml::SeError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Options controlling how a [`Task`] is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SerializeOptions {
+    /// Indent nested elements by four spaces.
+    pub pretty: bool,
+
+    /// Emit the `<?xml version="1.0" encoding="UTF-8"?>` declaration that
+    /// XCSoar writes at the top of its task files.
+    pub xml_declaration: bool,
+
+    /// Which writer produces the output.
+    pub profile: Profile,
+
+    /// End lines with `\r\n`, as XCSoar does on Windows.
+    pub crlf: bool,
 }
 
+/// Output style used when serializing a [`Task`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Profile {
+    /// Compact serde-based output with numbers in their shortest form.
+    #[default]
+    Default,
+
+    /// Mirrors the serializer of current XCSoar versions: its attribute
+    /// order, number formatting (`length="20000.0"`) and two space
+    /// indentation, so re-saved task files produce clean diffs against files
+    /// written by XCSoar.
+    ///
+    /// The output is always indented; [`SerializeOptions::pretty`] is ignored.
+    XCSoar,
+
+    /// Like [`Profile::XCSoar`], but as XCSoar 6 wrote task files: integral
+    /// values without a decimal part (`radius="1000"`), tab indentation,
+    /// `start_max_height_ref` as `0` (AGL) or `1` (MSL) and no
+    /// `finish_min_height_ref` for AGL.
+    XCSoar6,
+}
+
+const XML_DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+
+/// Parses a task file.
+///
+/// Besides malformed XML, this rejects coordinates and zone parameters that
+/// are out of range, see [`Task::check`].
 pub fn from_str(xml: &str) -> Result<Task, ParseError> {
-    Ok(quick_xml::de::from_str(xml)?)
+    let task = Task::from_xml(quick_xml::de::from_str(xml)?).map_err(TaskRangeError::Task)?;
+    task.check()?;
+    Ok(task)
 }
 
-pub fn from_reader(reader: impl BufRead) -> Result<Task, ParseError> {
-    Ok(quick_xml::de::from_reader(reader)?)
+/// Parses a task file from raw bytes.
+///
+/// The encoding is taken from the byte order mark or the XML declaration,
+/// falling back to UTF-8, so ISO-8859-1 and UTF-16 files written by older
+/// XCSoar installs and Windows editors load correctly.
+pub fn from_slice(bytes: &[u8]) -> Result<Task, ParseError> {
+    from_str(&encoding::decode(bytes, false)?)
 }
 
-pub fn to_writer(mut writer: impl FmtWrite, task: &Task) -> Result<(), SerializeError> {
-    let serializer = quick_xml::se::Serializer::new(&mut writer);
-    task.serialize(serializer)?;
-    Ok(())
+/// Decodes a task file to text, detecting its encoding like [`from_slice`].
+pub fn decode(bytes: &[u8]) -> Result<Cow<'_, str>, ParseError> {
+    encoding::decode(bytes, false)
+}
+
+/// Like [`from_slice`], but never fails on encoding errors.
+///
+/// Malformed sequences are replaced with U+FFFD, and files without BOM or
+/// known declared encoding that aren't valid UTF-8 are read as
+/// Windows-1252.
+pub fn from_slice_lossy(bytes: &[u8]) -> Result<Task, ParseError> {
+    from_str(&encoding::decode(bytes, true)?)
+}
+
+/// Parses a task file from a reader, detecting its encoding like
+/// [`from_slice`].
+pub fn from_reader(mut reader: impl BufRead) -> Result<Task, ParseError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    from_slice(&bytes)
+}
+
+/// Parses a task file from a reader, decoding it like [`from_slice_lossy`].
+pub fn from_reader_lossy(mut reader: impl BufRead) -> Result<Task, ParseError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    from_slice_lossy(&bytes)
+}
+
+pub fn to_writer(writer: impl FmtWrite, task: &Task) -> Result<(), SerializeError> {
+    to_writer_with_options(writer, task, SerializeOptions::default())
+}
+
+pub fn to_writer_pretty(writer: impl FmtWrite, task: &Task) -> Result<(), SerializeError> {
+    let options = SerializeOptions {
+        pretty: true,
+        ..Default::default()
+    };
+    to_writer_with_options(writer, task, options)
+}
+
+pub fn to_writer_with_options(
+    writer: impl FmtWrite,
+    task: &Task,
+    options: SerializeOptions,
+) -> Result<(), SerializeError> {
+    if options.crlf {
+        write_task(CrlfAdapter(writer), task, options)
+    } else {
+        write_task(writer, task, options)
+    }
 }
 
-pub fn to_writer_pretty(mut writer: impl FmtWrite, task: &Task) -> Result<(), SerializeError> {
+fn write_task(
+    mut writer: impl FmtWrite,
+    task: &Task,
+    options: SerializeOptions,
+) -> Result<(), SerializeError> {
+    if options.xml_declaration {
+        writer
+            .write_str(XML_DECLARATION)
+            .and_then(|_| writer.write_char('\n'))
+            .map_err(quick_xml::SeError::from)?;
+    }
+
+    let flavor = match options.profile {
+        Profile::Default => None,
+        Profile::XCSoar => Some(xcsoar::Flavor::Current),
+        Profile::XCSoar6 => Some(xcsoar::Flavor::XCSoar6),
+    };
+    if let Some(flavor) = flavor {
+        xcsoar::write_task(&mut writer, task, flavor).map_err(quick_xml::SeError::from)?;
+        return Ok(());
+    }
+
     let mut serializer = quick_xml::se::Serializer::new(&mut writer);
-    serializer.indent(' ', 4);
+    if options.pretty {
+        serializer.indent(' ', 4);
+    }
     task.serialize(serializer)?;
     Ok(())
 }
 
+pub fn to_io_writer(writer: impl IoWrite, task: &Task) -> Result<(), SerializeError> {
+    to_io_writer_with_options(writer, task, SerializeOptions::default())
+}
+
+pub fn to_io_writer_pretty(writer: impl IoWrite, task: &Task) -> Result<(), SerializeError> {
+    let options = SerializeOptions {
+        pretty: true,
+        ..Default::default()
+    };
+    to_io_writer_with_options(writer, task, options)
+}
+
+/// Streams the serialized task into `writer` without buffering it in a
+/// `String` first.
+pub fn to_io_writer_with_options(
+    writer: impl IoWrite,
+    task: &Task,
+    options: SerializeOptions,
+) -> Result<(), SerializeError> {
+    let mut adapter = IoAdapter {
+        inner: writer,
+        error: None,
+    };
+    let result = to_writer_with_options(&mut adapter, task, options);
+    if let Some(error) = adapter.error {
+        return Err(error.into());
+    }
+    result?;
+    adapter.inner.flush()?;
+    Ok(())
+}
+
+/// Writes the task to a file at `path`, formatted like XCSoar's own task
+/// files: pretty-printed and with an XML declaration.
+pub fn to_path(path: impl AsRef<Path>, task: &Task) -> Result<(), SerializeError> {
+    let file = BufWriter::new(File::create(path)?);
+    let options = SerializeOptions {
+        pretty: true,
+        xml_declaration: true,
+        ..Default::default()
+    };
+    to_io_writer_with_options(file, task, options)
+}
+
 pub fn to_string(task: &Task) -> Result<String, SerializeError> {
     let mut buffer = String::new();
     to_writer(&mut buffer, task)?;
@@ -430,6 +899,39 @@ pub fn to_string_pretty(task: &Task) -> Result<String, SerializeError> {
     Ok(buffer)
 }
 
+/// Bridges `fmt::Write` to `io::Write`, keeping the underlying I/O error that
+/// `fmt::Error` can't carry.
+struct IoAdapter<W> {
+    inner: W,
+    error: Option<std::io::Error>,
+}
+
+impl<W: IoWrite> FmtWrite for IoAdapter<W> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            std::fmt::Error
+        })
+    }
+}
+
+/// Turns the `\n` line endings of the writers into `\r\n`.
+struct CrlfAdapter<W>(W);
+
+impl<W: FmtWrite> FmtWrite for CrlfAdapter<W> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        let mut lines = s.split('\n');
+        if let Some(first) = lines.next() {
+            self.0.write_str(first)?;
+        }
+        for line in lines {
+            self.0.write_str("\r\n")?;
+            self.0.write_str(line)?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -463,6 +965,48 @@ mod tests {
         assert_debug_snapshot!(task);
     }
 
+    #[test]
+    fn parse_latin1_task() {
+        let bytes = include_bytes!("../fixtures/aat-task-latin1.tsk");
+        let task = from_slice(bytes).unwrap();
+        let expected = from_str(include_str!("../fixtures/aat-task.tsk")).unwrap();
+        assert_eq!(task, expected);
+        assert_eq!(task.points[1].waypoint.name, "Aachen Merzbrück");
+    }
+
+    #[test]
+    fn parse_utf16_task() {
+        let bytes = include_bytes!("../fixtures/aat-task-utf16.tsk");
+        let task = from_reader(&bytes[..]).unwrap();
+        let expected = from_str(include_str!("../fixtures/aat-task.tsk")).unwrap();
+        assert_eq!(task, expected);
+    }
+
+    #[test]
+    fn parse_undeclared_latin1_task() {
+        let bytes = include_bytes!("../fixtures/aat-task-latin1.tsk");
+        let body = bytes.splitn(2, |b| *b == b'\n').nth(1).unwrap();
+        assert!(matches!(from_slice(body), Err(ParseError::Encoding(_))));
+
+        let task = from_slice_lossy(body).unwrap();
+        assert_eq!(task.points[1].waypoint.name, "Aachen Merzbrück");
+    }
+
+    #[test]
+    fn parse_unknown_encoding_lossy() {
+        let bytes = include_bytes!("../fixtures/aat-task-latin1.tsk");
+        let body = bytes.splitn(2, |b| *b == b'\n').nth(1).unwrap();
+        let mut bytes = br#"<?xml version="1.0" encoding="x-unknown"?>"#.to_vec();
+        bytes.extend_from_slice(body);
+        assert!(matches!(
+            from_slice(&bytes),
+            Err(ParseError::UnknownEncoding(_))
+        ));
+
+        let task = from_slice_lossy(&bytes).unwrap();
+        assert_eq!(task.points[1].waypoint.name, "Aachen Merzbrück");
+    }
+
     #[test]
     fn roundtrip_aat_task() {
         let xml = include_str!("../fixtures/aat-task.tsk");
@@ -519,4 +1063,98 @@ mod tests {
         let serialized = to_string(&task).unwrap();
         assert_snapshot!(serialized);
     }
+
+    #[test]
+    fn io_writer_matches_fmt_writer() {
+        let xml = include_str!("../fixtures/fai-task.tsk");
+        let task = from_str(xml).unwrap();
+
+        let mut bytes = Vec::new();
+        to_io_writer_pretty(&mut bytes, &task).unwrap();
+        assert_eq!(
+            String::from_utf8(bytes).unwrap(),
+            to_string_pretty(&task).unwrap()
+        );
+    }
+
+    #[test]
+    fn xml_declaration() {
+        let xml = include_str!("../fixtures/fai-task.tsk");
+        let task = from_str(xml).unwrap();
+
+        let options = SerializeOptions {
+            xml_declaration: true,
+            ..Default::default()
+        };
+        let mut serialized = String::new();
+        to_writer_with_options(&mut serialized, &task, options).unwrap();
+        assert!(serialized.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Task "));
+        assert_eq!(from_str(&serialized).unwrap(), task);
+    }
+
+    #[test]
+    fn serialize_xcsoar_profile() {
+        let xml = include_str!("../fixtures/aat-task.tsk");
+        let task = from_str(xml).unwrap();
+
+        let options = SerializeOptions {
+            xml_declaration: true,
+            profile: Profile::XCSoar,
+            ..Default::default()
+        };
+        let mut serialized = String::new();
+        to_writer_with_options(&mut serialized, &task, options).unwrap();
+        assert_snapshot!(serialized);
+
+        let roundtripped = from_str(&serialized).unwrap();
+        assert_eq!(task, roundtripped);
+    }
+
+    #[test]
+    fn xcsoar_profile_reproduces_xcsoar_output() {
+        for xml in [
+            include_str!("../fixtures/aat-task.tsk"),
+            include_str!("../fixtures/racing-task.tsk"),
+        ] {
+            let task = from_str(xml).unwrap();
+
+            let options = SerializeOptions {
+                profile: Profile::XCSoar,
+                ..Default::default()
+            };
+            let mut serialized = String::new();
+            to_writer_with_options(&mut serialized, &task, options).unwrap();
+            // aat-task.tsk was saved with a blank line after the task
+            assert_eq!(serialized.trim_end(), xml.trim_end());
+        }
+    }
+
+    #[test]
+    fn xcsoar6_profile_reproduces_xcsoar6_output() {
+        // Saved by XCSoar 6 on Windows
+        let xml = include_str!("../fixtures/fai-task.tsk");
+        let task = from_str(xml).unwrap();
+
+        let options = SerializeOptions {
+            profile: Profile::XCSoar6,
+            crlf: true,
+            ..Default::default()
+        };
+        let mut serialized = String::new();
+        to_writer_with_options(&mut serialized, &task, options).unwrap();
+        assert_eq!(serialized, xml);
+    }
+
+    #[test]
+    fn to_path_roundtrip() {
+        let xml = include_str!("../fixtures/aat-task.tsk");
+        let task = from_str(xml).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("task.tsk");
+        to_path(&path, &task).unwrap();
+
+        let file = std::io::BufReader::new(File::open(&path).unwrap());
+        assert_eq!(from_reader(file).unwrap(), task);
+    }
 }
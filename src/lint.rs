@@ -0,0 +1,424 @@
+//! Geometric checks for zones that get in each other's way.
+//!
+//! [`Task::validate`] checks a task against the rules of its type, but a
+//! valid task can still be badly set, e.g. with two 10 km cylinders 12 km
+//! apart that a pilot can tag at once. [`Task::lint`] looks for such
+//! problems:
+//!
+//! ```
+//! let xml = std::fs::read_to_string("fixtures/racing-task.tsk").unwrap();
+//! let task = xcsoar_tasks::from_str(&xml).unwrap();
+//! for warning in task.lint() {
+//!     println!("{warning}");
+//! }
+//! ```
+
+use crate::format::{self, UnitSystem};
+use crate::{EarthModel, Location, PointType, ResolvedZone, Task, ZoneShape};
+use std::fmt;
+
+/// Start and finish points closer than this, in meters, are taken to be the
+/// same waypoint of a closed task, whose zones overlap on purpose.
+const SAME_WAYPOINT_DISTANCE: f64 = 1.0;
+
+/// A problem with the placement of zones. Indices are zero-based positions
+/// in `Task::points`, and distances are in meters.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// The zones of consecutive points overlap, so both can be achieved at
+    /// once.
+    ZonesOverlap {
+        first: usize,
+        first_name: String,
+        second: usize,
+        second_name: String,
+        /// Distance between the points.
+        distance: f64,
+        /// How far the zones reach into each other along the leg.
+        overlap: f64,
+    },
+
+    /// A leg is shorter than the zone at one of its ends, so the other point
+    /// lies inside that zone.
+    ShortLeg {
+        from: usize,
+        from_name: String,
+        to: usize,
+        to_name: String,
+        distance: f64,
+        /// Radius of the larger zone.
+        radius: f64,
+    },
+
+    /// The finish zone overlaps a start zone around a different waypoint.
+    StartFinishOverlap {
+        start: usize,
+        start_name: String,
+        finish: usize,
+        finish_name: String,
+        distance: f64,
+        overlap: f64,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let distance = |meters| format::distance(meters, UnitSystem::METRIC);
+        match self {
+            Warning::ZonesOverlap {
+                first,
+                first_name,
+                second,
+                second_name,
+                distance: apart,
+                overlap,
+            } => write!(
+                f,
+                "points {} ({first_name}) and {} ({second_name}): zones overlap by {}, \
+                 the points are {} apart",
+                first + 1,
+                second + 1,
+                distance(*overlap),
+                distance(*apart)
+            ),
+            Warning::ShortLeg {
+                from,
+                from_name,
+                to,
+                to_name,
+                distance: leg,
+                radius,
+            } => write!(
+                f,
+                "points {} ({from_name}) and {} ({to_name}): the leg is only {}, \
+                 inside the {} zone",
+                from + 1,
+                to + 1,
+                distance(*leg),
+                distance(*radius)
+            ),
+            Warning::StartFinishOverlap {
+                start,
+                start_name,
+                finish,
+                finish_name,
+                distance: apart,
+                overlap,
+            } => write!(
+                f,
+                "start {} ({start_name}) and finish {} ({finish_name}): zones overlap by {}, \
+                 the points are {} apart",
+                start + 1,
+                finish + 1,
+                distance(*overlap),
+                distance(*apart)
+            ),
+        }
+    }
+}
+
+/// A zone around its point, facing the way it does within the task.
+struct PlacedZone {
+    index: usize,
+    center: Location,
+    zone: ResolvedZone,
+    direction: f64,
+}
+
+impl PlacedZone {
+    /// How far the zone extends from its center towards `bearing`.
+    fn reach(&self, bearing: f64) -> f64 {
+        let offset = (bearing - self.direction + 180.0).rem_euclid(360.0) - 180.0;
+        match self.zone.shape {
+            ZoneShape::Cylinder => self.zone.radius,
+            // Lines are perpendicular to their direction
+            ZoneShape::Line => self.zone.radius * offset.to_radians().sin().abs(),
+            ZoneShape::Sector | ZoneShape::Keyhole if offset.abs() <= self.zone.angle / 2.0 => {
+                self.zone.finite_radius()
+            }
+            ZoneShape::Sector => 0.0,
+            ZoneShape::Keyhole => self.zone.inner_radius,
+        }
+    }
+
+    /// Distance between the closest parts of the zones, negative when they
+    /// overlap. Measured along the line between the points, except from
+    /// lines, which are measured from their closest end or side.
+    fn gap(&self, other: &PlacedZone, model: EarthModel) -> f64 {
+        if other.zone.shape == ZoneShape::Line && self.zone.shape != ZoneShape::Line {
+            return other.gap(self, model);
+        }
+        let distance = model.distance(&self.center, &other.center);
+        let bearing = model.bearing(&self.center, &other.center);
+        let back = model.bearing(&other.center, &self.center);
+        if self.zone.shape == ZoneShape::Line {
+            let angle = (bearing - self.direction).to_radians();
+            let along = (distance * angle.sin()).abs() - self.zone.radius;
+            let across = (distance * angle.cos()).abs();
+            return along.max(0.0).hypot(across) - other.reach(back);
+        }
+        distance - self.reach(bearing) - other.reach(back)
+    }
+}
+
+impl Task {
+    /// Checks the placement of the zones along the course.
+    ///
+    /// Reports overlapping zones of consecutive points, legs that end inside
+    /// the zone they start from (or start inside the one they lead to), and
+    /// finish zones that overlap a start zone. Start and finish at the same
+    /// waypoint are fine. Returns the warnings in the order of the points.
+    /// Distances and bearings are measured on the FAI sphere.
+    pub fn lint(&self) -> Vec<Warning> {
+        self.lint_with_model(EarthModel::FaiSphere)
+    }
+
+    /// Like [`Task::lint`], with every distance and bearing measured on
+    /// `model`, so the reported distances match
+    /// [`Task::legs_with_options`](crate::Task::legs_with_options) for the
+    /// same model.
+    pub fn lint_with_model(&self, model: EarthModel) -> Vec<Warning> {
+        let course = self.course();
+        let location = |position: Option<usize>| {
+            position
+                .and_then(|position| course.get(position))
+                .map(|(_, point)| &point.waypoint.location)
+        };
+        let zones = course
+            .iter()
+            .enumerate()
+            .map(|(position, (index, point))| {
+                let center = point.waypoint.location;
+                let zone = point.observation_zone.resolve();
+                let previous = location(position.checked_sub(1));
+                let next = location(Some(position + 1));
+                PlacedZone {
+                    index: *index,
+                    center,
+                    zone,
+                    direction: zone.direction_with_model(model, &center, previous, next),
+                }
+            })
+            .collect::<Vec<_>>();
+        let name = |index: usize| self.points[index].waypoint.name.clone();
+
+        let mut warnings = Vec::new();
+        for pair in zones.windows(2) {
+            let (from, to) = (&pair[0], &pair[1]);
+            let distance = model.distance(&from.center, &to.center);
+            let radius = from
+                .reach(model.bearing(&from.center, &to.center))
+                .max(to.reach(model.bearing(&to.center, &from.center)));
+            if radius >= distance {
+                warnings.push(Warning::ShortLeg {
+                    from: from.index,
+                    from_name: name(from.index),
+                    to: to.index,
+                    to_name: name(to.index),
+                    distance,
+                    radius,
+                });
+                continue;
+            }
+            let gap = from.gap(to, model);
+            if gap < 0.0 {
+                warnings.push(Warning::ZonesOverlap {
+                    first: from.index,
+                    first_name: name(from.index),
+                    second: to.index,
+                    second_name: name(to.index),
+                    distance,
+                    overlap: -gap,
+                });
+            }
+        }
+
+        // The start and finish of a two point task were checked as a leg
+        let Some(finish) = zones.last().filter(|_| zones.len() > 2) else {
+            return warnings;
+        };
+        let optional_starts = self
+            .points
+            .iter()
+            .enumerate()
+            .filter(|(_, point)| point.point_type == PointType::OptionalStart)
+            .map(|(index, point)| {
+                let center = point.waypoint.location;
+                let zone = point.observation_zone.resolve();
+                PlacedZone {
+                    index,
+                    center,
+                    zone,
+                    direction: zone.direction_with_model(model, &center, None, location(Some(1))),
+                }
+            })
+            .collect::<Vec<_>>();
+        for start in std::iter::once(&zones[0]).chain(&optional_starts) {
+            let distance = model.distance(&start.center, &finish.center);
+            let gap = start.gap(finish, model);
+            if distance >= SAME_WAYPOINT_DISTANCE && gap < 0.0 {
+                warnings.push(Warning::StartFinishOverlap {
+                    start: start.index,
+                    start_name: name(start.index),
+                    finish: finish.index,
+                    finish_name: name(finish.index),
+                    distance,
+                    overlap: -gap,
+                });
+            }
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ObservationZone;
+    use insta::assert_snapshot;
+
+    /// A racing task with a 2 km start line, two turnpoints and a 1 km
+    /// finish cylinder along the equator, `spacing` degrees apart.
+    fn task(spacing: f64) -> Task {
+        let mut task = crate::from_str(include_str!("../fixtures/racing-task.tsk")).unwrap();
+        task.points.truncate(4);
+        for (index, point) in task.points.iter_mut().enumerate() {
+            point.waypoint.location = Location {
+                longitude: index as f64 * spacing,
+                latitude: 0.0,
+            };
+        }
+        task.points[0].observation_zone = ObservationZone::Line { length: 2000.0 };
+        task.points[3].point_type = PointType::Finish;
+        task.points[3].observation_zone = ObservationZone::Cylinder { radius: 1000.0 };
+        task
+    }
+
+    fn render(warnings: &[Warning]) -> String {
+        warnings
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn fixtures() {
+        for xml in [
+            include_str!("../fixtures/fai-task.tsk"),
+            include_str!("../fixtures/racing-task.tsk"),
+        ] {
+            let task = crate::from_str(xml).unwrap();
+            assert_eq!(task.lint(), vec![]);
+        }
+
+        // The 20 km start line passes 500 m from the finish cylinder
+        let task = crate::from_str(include_str!("../fixtures/aat-task.tsk")).unwrap();
+        assert_eq!(
+            render(&task.lint()),
+            "start 1 (Langenfeld-Wiescheid) and finish 6 (002Zielkreis): zones overlap by \
+             1.5 km, the points are 6 km apart"
+        );
+    }
+
+    #[test]
+    fn overlapping_cylinders() {
+        // 0.108° on the equator is about 12 km
+        let mut task = task(0.108);
+        task.points[1].observation_zone = ObservationZone::Cylinder { radius: 10_000.0 };
+        task.points[2].observation_zone = ObservationZone::Cylinder { radius: 10_000.0 };
+        let warnings = task.lint();
+        let [
+            Warning::ZonesOverlap {
+                first: 1,
+                second: 2,
+                distance,
+                overlap,
+                ..
+            },
+        ] = warnings.as_slice()
+        else {
+            panic!("{warnings:?}");
+        };
+        assert!((distance + overlap - 20_000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn measures_on_earth_model() {
+        let mut task = task(0.2);
+        for point in &mut task.points {
+            point.waypoint.location.latitude = 60.0;
+        }
+        task.points[1].observation_zone = ObservationZone::Cylinder { radius: 10_000.0 };
+        task.points[2].observation_zone = ObservationZone::Cylinder { radius: 10_000.0 };
+        let (first, second) = (
+            task.points[1].waypoint.location,
+            task.points[2].waypoint.location,
+        );
+        for model in [EarthModel::FaiSphere, EarthModel::Wgs84] {
+            let warnings = task.lint_with_model(model);
+            let [Warning::ZonesOverlap { distance, .. }] = warnings.as_slice() else {
+                panic!("{warnings:?}");
+            };
+            assert!(
+                (distance - model.distance(&first, &second)).abs() < 1e-6,
+                "{model:?}: {distance}"
+            );
+        }
+        assert_ne!(
+            task.lint(),
+            task.lint_with_model(EarthModel::Wgs84),
+            "WGS84 distances differ from the sphere's"
+        );
+    }
+
+    #[test]
+    fn sectors_facing_away_dont_overlap() {
+        let mut task = task(0.108);
+        task.points[1].point_type = PointType::Turn;
+        task.points[2].point_type = PointType::Turn;
+        task.points[1].observation_zone = ObservationZone::FAISector;
+        task.points[2].observation_zone = ObservationZone::FAISector;
+        task.points[2].waypoint.location.latitude = 0.1;
+        assert_eq!(task.lint(), vec![]);
+    }
+
+    #[test]
+    fn finish_near_start_line() {
+        // Closed tasks finish where they start
+        let mut task = task(0.1);
+        task.points[3].waypoint.location = task.points[0].waypoint.location;
+        task.points[3].observation_zone = ObservationZone::Cylinder { radius: 3000.0 };
+        assert_eq!(task.lint(), vec![]);
+
+        // The start line runs north-south through the start
+        task.points[3].waypoint.location.latitude = 0.027;
+        let warnings = task.lint();
+        let [
+            Warning::StartFinishOverlap {
+                start: 0,
+                finish: 3,
+                overlap,
+                ..
+            },
+        ] = warnings.as_slice()
+        else {
+            panic!("{warnings:?}");
+        };
+        // 3002 m from the start, 2002 m from the northern end of the line
+        assert!((overlap - 997.7).abs() < 0.1, "{overlap}");
+    }
+
+    #[test]
+    fn messages() {
+        let mut task = task(0.1);
+        task.points[1].observation_zone = ObservationZone::Cylinder { radius: 10_000.0 };
+        task.points[2].observation_zone = ObservationZone::Cylinder { radius: 5000.0 };
+        let mut close = task.points[2].clone();
+        close.waypoint.name = "Close".to_string();
+        close.waypoint.location.longitude = 0.22;
+        task.points.insert(3, close);
+        task.points[4].waypoint.location.longitude = 0.005;
+        assert_snapshot!(render(&task.lint()));
+    }
+}
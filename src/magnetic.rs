@@ -0,0 +1,454 @@
+//! Magnetic declination from the World Magnetic Model, computed offline.
+//!
+//! [`MagneticModel`] evaluates the spherical harmonic model published by
+//! NOAA/NCEI and the BGS. The crate bundles WMM2025, valid for the years 2025
+//! to 2030, and reads newer releases from their `WMM.COF` coefficient files:
+//!
+//! ```
+//! use xcsoar_tasks::Location;
+//! use xcsoar_tasks::magnetic::MagneticModel;
+//!
+//! let location = Location::new(51.0, 7.0).unwrap();
+//! let declination = MagneticModel::wmm().declination(&location, 0.0, 2026.5);
+//! assert!((2.0..5.0).contains(&declination));
+//! ```
+
+use crate::{Location, ObservationZone, Point};
+use std::ops::Range;
+use std::sync::LazyLock;
+use std::time::SystemTime;
+
+/// Reference radius of the model in kilometers.
+const REFERENCE_RADIUS: f64 = 6371.2;
+
+/// Semi-major axis of the WGS84 ellipsoid in kilometers.
+const WGS84_A: f64 = 6378.137;
+
+/// Flattening of the WGS84 ellipsoid.
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+/// Years for which a model is valid after its epoch.
+const VALIDITY_YEARS: f64 = 5.0;
+
+const SECONDS_PER_YEAR: f64 = 365.2425 * 86_400.0;
+
+/// A line of a coefficient file that couldn't be read.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("line {line}: {message}")]
+pub struct CofError {
+    /// One-based line number.
+    pub line: usize,
+    pub message: String,
+}
+
+/// Gauss coefficients of a main field model and their secular variation, in
+/// nanotesla and nanotesla per year.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MagneticModel {
+    /// Name from the file header, e.g. `WMM-2025`.
+    pub name: String,
+
+    /// Decimal year the coefficients refer to.
+    pub epoch: f64,
+
+    degree: usize,
+    /// `[g, h, g_dot, h_dot]` indexed by `n * (n + 1) / 2 + m`.
+    coefficients: Vec<[f64; 4]>,
+}
+
+static WMM: LazyLock<MagneticModel> = LazyLock::new(|| {
+    MagneticModel::from_cof(include_str!("magnetic/WMM.COF")).expect("bundled WMM.COF is valid")
+});
+
+impl MagneticModel {
+    /// The World Magnetic Model WMM2025 as published by NOAA/NCEI, valid
+    /// from 2025 to 2030 and bundled with the crate.
+    pub fn wmm() -> &'static MagneticModel {
+        &WMM
+    }
+
+    /// Reads a coefficient file in the format NOAA publishes the WMM in: a
+    /// header with epoch and name, one line `n m g h g_dot h_dot` per
+    /// coefficient, and a line of `9`s at the end.
+    pub fn from_cof(text: &str) -> Result<MagneticModel, CofError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty());
+
+        let (line, header) = lines.next().ok_or_else(|| CofError {
+            line: 1,
+            message: "missing header".to_string(),
+        })?;
+        let mut header = header.split_whitespace();
+        let epoch = header
+            .next()
+            .and_then(|epoch| epoch.parse::<f64>().ok())
+            .filter(|epoch| epoch.is_finite())
+            .ok_or_else(|| CofError {
+                line,
+                message: "expected the epoch as a decimal year".to_string(),
+            })?;
+        let name = header.next().unwrap_or_default().to_string();
+
+        let mut terms = Vec::new();
+        for (line, text) in lines {
+            if text.starts_with("9999") {
+                break;
+            }
+            let error = |message: &str| CofError {
+                line,
+                message: message.to_string(),
+            };
+            let fields = text.split_whitespace().collect::<Vec<_>>();
+            let [n, m, values @ ..] = fields.as_slice() else {
+                return Err(error("expected n, m, g, h, g_dot and h_dot"));
+            };
+            let (Ok(n), Ok(m)) = (n.parse::<usize>(), m.parse::<usize>()) else {
+                return Err(error("expected integer degree and order"));
+            };
+            if n == 0 || m > n {
+                return Err(error("expected 0 <= m <= n and n >= 1"));
+            }
+            let values = values
+                .iter()
+                .map(|value| value.parse::<f64>().ok().filter(|value| value.is_finite()))
+                .collect::<Option<Vec<_>>>()
+                .and_then(|values| <[f64; 4]>::try_from(values).ok())
+                .ok_or_else(|| error("expected four finite coefficients"))?;
+            terms.push((n, m, values));
+        }
+
+        let degree = terms.iter().map(|(n, _, _)| *n).max().unwrap_or(0);
+        let mut coefficients = vec![[0.0; 4]; index(degree, degree) + 1];
+        for (n, m, values) in terms {
+            coefficients[index(n, m)] = values;
+        }
+        Ok(MagneticModel {
+            name,
+            epoch,
+            degree,
+            coefficients,
+        })
+    }
+
+    /// Decimal years the model is meant for, five years from its epoch.
+    pub fn valid_years(&self) -> Range<f64> {
+        self.epoch..self.epoch + VALIDITY_YEARS
+    }
+
+    /// Magnetic declination in degrees at `altitude` meters above the WGS84
+    /// ellipsoid, positive when magnetic north is east of true north.
+    ///
+    /// `year` is a decimal year such as `2026.5`, see [`decimal_year`].
+    /// Outside of [`MagneticModel::valid_years`] the secular variation is
+    /// extrapolated, which quickly loses accuracy.
+    pub fn declination(&self, location: &Location, altitude: f64, year: f64) -> f64 {
+        let [north, east, _] = self.field(location, altitude, year);
+        east.atan2(north).to_degrees()
+    }
+
+    /// Converts a true bearing at `location` to a magnetic one, `0..360`.
+    pub fn magnetic_bearing(&self, bearing: f64, location: &Location, year: f64) -> f64 {
+        (bearing - self.declination(location, 0.0, year)).rem_euclid(360.0)
+    }
+
+    /// Magnetic start and end radials of a [`ObservationZone::Sector`], or
+    /// `None` for other zones.
+    pub fn magnetic_radials(&self, point: &Point, year: f64) -> Option<(f64, f64)> {
+        let ObservationZone::Sector {
+            start_radial,
+            end_radial,
+            ..
+        } = point.observation_zone
+        else {
+            return None;
+        };
+        let location = &point.waypoint.location;
+        let declination = self.declination(location, point.waypoint.altitude.unwrap_or(0.0), year);
+        Some((
+            (start_radial - declination).rem_euclid(360.0),
+            (end_radial - declination).rem_euclid(360.0),
+        ))
+    }
+
+    /// North, east and down components of the field in nanotesla.
+    fn field(&self, location: &Location, altitude: f64, year: f64) -> [f64; 3] {
+        let latitude = location.latitude.to_radians();
+        let longitude = location.longitude.to_radians();
+        let altitude = altitude / 1000.0;
+
+        // Geodetic to geocentric spherical coordinates
+        let e2 = WGS84_F * (2.0 - WGS84_F);
+        let prime_vertical = WGS84_A / (1.0 - e2 * latitude.sin().powi(2)).sqrt();
+        let p = (prime_vertical + altitude) * latitude.cos();
+        let z = (prime_vertical * (1.0 - e2) + altitude) * latitude.sin();
+        let radius = p.hypot(z);
+        let geocentric = (z / radius).asin();
+
+        let legendre = Legendre::new(self.degree, geocentric);
+        let cos_latitude = geocentric.cos().max(1e-10);
+        let dt = year - self.epoch;
+
+        let (mut x, mut y, mut z) = (0.0, 0.0, 0.0);
+        for n in 1..=self.degree {
+            let ratio = (REFERENCE_RADIUS / radius).powi(n as i32 + 2);
+            for m in 0..=n {
+                let [g, h, g_dot, h_dot] = self.coefficients[index(n, m)];
+                let g = g + g_dot * dt;
+                let h = h + h_dot * dt;
+                let (sin, cos) = (m as f64 * longitude).sin_cos();
+                let p = legendre.p[index(n, m)];
+                let dp = legendre.dp[index(n, m)];
+
+                x -= ratio * (g * cos + h * sin) * dp;
+                y += ratio * m as f64 * (g * sin - h * cos) * p / cos_latitude;
+                z -= ratio * (n as f64 + 1.0) * (g * cos + h * sin) * p;
+            }
+        }
+
+        // Rotate from geocentric to geodetic north and down
+        let (sin, cos) = (geocentric - latitude).sin_cos();
+        [x * cos - z * sin, y, x * sin + z * cos]
+    }
+}
+
+/// Converts a point in time to a decimal year, e.g. `2026.5` in early July
+/// 2026, accurate to within a day.
+pub fn decimal_year(time: SystemTime) -> f64 {
+    let seconds = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(duration) => duration.as_secs_f64(),
+        Err(error) => -error.duration().as_secs_f64(),
+    };
+    1970.0 + seconds / SECONDS_PER_YEAR
+}
+
+fn index(n: usize, m: usize) -> usize {
+    n * (n + 1) / 2 + m
+}
+
+/// Schmidt semi-normalized associated Legendre functions of the sine of the
+/// latitude, and their derivatives with respect to the latitude.
+struct Legendre {
+    p: Vec<f64>,
+    dp: Vec<f64>,
+}
+
+impl Legendre {
+    fn new(degree: usize, latitude: f64) -> Legendre {
+        let (x, z) = latitude.sin_cos();
+        let len = index(degree, degree) + 1;
+        let mut p = vec![0.0; len];
+        let mut dp = vec![0.0; len];
+        p[0] = 1.0;
+
+        // Unnormalized functions first, from the recurrences in n
+        for n in 1..=degree {
+            let nf = n as f64;
+            let diagonal = index(n, n);
+            let previous = index(n - 1, n - 1);
+            p[diagonal] = (2.0 * nf - 1.0) * z * p[previous];
+            dp[diagonal] = (2.0 * nf - 1.0) * (z * dp[previous] - x * p[previous]);
+            for m in 0..n {
+                let mf = m as f64;
+                let (p1, dp1) = (p[index(n - 1, m)], dp[index(n - 1, m)]);
+                let (p2, dp2) = if n >= m + 2 {
+                    (p[index(n - 2, m)], dp[index(n - 2, m)])
+                } else {
+                    (0.0, 0.0)
+                };
+                p[index(n, m)] = ((2.0 * nf - 1.0) * x * p1 - (nf + mf - 1.0) * p2) / (nf - mf);
+                dp[index(n, m)] =
+                    ((2.0 * nf - 1.0) * (x * dp1 + z * p1) - (nf + mf - 1.0) * dp2) / (nf - mf);
+            }
+        }
+
+        // Schmidt factor sqrt(2 (n - m)! / (n + m)!) for m > 0
+        for n in 1..=degree {
+            let mut factor = 1.0;
+            for m in 1..=n {
+                factor /= ((n + m) as f64 * (n - m + 1) as f64).sqrt();
+                let scale = factor * 2f64.sqrt();
+                p[index(n, m)] *= scale;
+                dp[index(n, m)] *= scale;
+            }
+        }
+        Legendre { p, dp }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A tilted dipole with secular variation.
+    const DIPOLE: &str = "
+    2025.0            TEST-2025     01/01/2025
+  1  0  -29000.0       0.0        0.0        0.0
+  1  1   -1500.0    5000.0        0.0     -100.0
+999999999999999999999999999999999999999999999999
+999999999999999999999999999999999999999999999999
+";
+
+    fn at(latitude: f64, longitude: f64) -> Location {
+        Location::new(latitude, longitude).unwrap()
+    }
+
+    #[test]
+    fn parses_cof() {
+        let model = MagneticModel::from_cof(DIPOLE).unwrap();
+        assert_eq!(model.name, "TEST-2025");
+        assert_eq!(model.epoch, 2025.0);
+        assert_eq!(model.valid_years(), 2025.0..2030.0);
+
+        let error = MagneticModel::from_cof("2025.0 TEST\n 1 0 -29000.0 0.0 x 0.0\n").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: expected four finite coefficients"
+        );
+        let error = MagneticModel::from_cof("2025.0 TEST\n 1 2 0 0 0 0\n").unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+
+    #[test]
+    fn dipole_secular_variation() {
+        let model = MagneticModel::from_cof(DIPOLE).unwrap();
+        // On the equator at the prime meridian the horizontal field is
+        // (-g10, -h11), north and east
+        let expected = (-5000.0f64).atan2(29000.0).to_degrees();
+        assert!((model.declination(&at(0.0, 0.0), 0.0, 2025.0) - expected).abs() < 1e-9);
+
+        // h11 drops by 100 nT per year
+        let expected = (-4800.0f64).atan2(29000.0).to_degrees();
+        assert!((model.declination(&at(0.0, 0.0), 0.0, 2027.0) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn wmm_test_values() {
+        let model = MagneticModel::wmm();
+        assert_eq!(model.name, "WMM-2025");
+        assert_eq!(model.valid_years(), 2025.0..2030.0);
+
+        // The test points of the WMM2025 report: year, height above the
+        // ellipsoid in km, latitude, longitude, then X, Y, Z in nT and the
+        // declination in degrees
+        let values = [
+            (2025.0, 0.0, 80.0, 0.0, [6521.6, 145.9, 54791.5], 1.28),
+            (2025.0, 0.0, 0.0, 120.0, [39677.8, -109.6, -10580.2], -0.16),
+            (
+                2025.0,
+                0.0,
+                -80.0,
+                240.0,
+                [6117.5, 15751.9, -52022.5],
+                68.78,
+            ),
+            (2025.0, 100.0, 80.0, 0.0, [6216.0, 92.4, 52598.8], 0.85),
+            (2025.0, 100.0, 0.0, 120.0, [37688.6, -96.2, -10152.1], -0.15),
+            (
+                2025.0,
+                100.0,
+                -80.0,
+                240.0,
+                [5907.6, 14780.3, -49540.7],
+                68.21,
+            ),
+            (2027.5, 0.0, 80.0, 0.0, [6500.8, 294.5, 54869.4], 2.59),
+            (2027.5, 0.0, 0.0, 120.0, [39701.6, -167.4, -10381.8], -0.24),
+            (
+                2027.5,
+                0.0,
+                -80.0,
+                240.0,
+                [6200.7, 15730.3, -51783.7],
+                68.49,
+            ),
+            (2027.5, 100.0, 80.0, 0.0, [6196.7, 233.8, 52670.5], 2.16),
+            (2027.5, 100.0, 0.0, 120.0, [37711.5, -148.7, -9969.8], -0.23),
+            (
+                2027.5,
+                100.0,
+                -80.0,
+                240.0,
+                [5984.0, 14760.1, -49317.7],
+                67.93,
+            ),
+        ];
+        for (year, height, latitude, longitude, expected, declination) in values {
+            let location = at(latitude, (longitude + 180.0) % 360.0 - 180.0);
+            let field = model.field(&location, height * 1000.0, year);
+            for (component, expected) in field.into_iter().zip(expected) {
+                assert!(
+                    (component - expected).abs() < 0.06,
+                    "{year} {height} {latitude} {longitude}: {field:?}"
+                );
+            }
+            let actual = model.declination(&location, height * 1000.0, year);
+            assert!(
+                (actual - declination).abs() < 0.006,
+                "{year} {height} {latitude} {longitude}: {actual}"
+            );
+        }
+    }
+
+    #[test]
+    fn legendre_functions() {
+        let latitude = 0.7f64;
+        let (x, z) = latitude.sin_cos();
+        let legendre = Legendre::new(6, latitude);
+        let closed_forms = [
+            (index(2, 0), (3.0 * x * x - 1.0) / 2.0),
+            (index(2, 1), 3f64.sqrt() * x * z),
+            (index(2, 2), 3f64.sqrt() / 2.0 * z * z),
+            (index(3, 3), 10f64.sqrt() / 4.0 * z.powi(3)),
+        ];
+        for (index, expected) in closed_forms {
+            assert!((legendre.p[index] - expected).abs() < 1e-12);
+        }
+
+        let h = 1e-6;
+        let above = Legendre::new(6, latitude + h);
+        let below = Legendre::new(6, latitude - h);
+        for i in 0..legendre.p.len() {
+            let numeric = (above.p[i] - below.p[i]) / (2.0 * h);
+            assert!((legendre.dp[i] - numeric).abs() < 1e-6, "{i}");
+        }
+    }
+
+    #[test]
+    fn zonal_terms_have_no_declination() {
+        // A pure g20 term has no east component anywhere
+        let model =
+            MagneticModel::from_cof("2025.0 G20\n 1 0 -29000 0 0 0\n 2 0 -2500 0 0 0\n").unwrap();
+        for latitude in [-60.0, -10.0, 30.0, 75.0] {
+            assert!(model.declination(&at(latitude, 40.0), 0.0, 2025.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn converts_bearings_and_radials() {
+        let model = MagneticModel::wmm();
+        let declination = model.declination(&at(0.0, 0.0), 0.0, 2025.0);
+        let magnetic = model.magnetic_bearing(350.0, &at(0.0, 0.0), 2025.0);
+        assert!((magnetic - (350.0 - declination).rem_euclid(360.0)).abs() < 1e-9);
+
+        let task = crate::from_str(include_str!("../fixtures/all-oz-types.tsk")).unwrap();
+        let sectors = task
+            .points
+            .iter()
+            .filter_map(|point| model.magnetic_radials(point, 2025.0))
+            .count();
+        assert_eq!(sectors, 1);
+    }
+
+    #[test]
+    fn decimal_years() {
+        let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_782_864_000);
+        assert!(
+            (decimal_year(time) - 2026.5).abs() < 0.01,
+            "{}",
+            decimal_year(time)
+        );
+    }
+}
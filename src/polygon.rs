@@ -0,0 +1,327 @@
+//! Observation zones as [`geo_types`] geometries.
+//!
+//! Arcs are approximated by points along great circles on the FAI sphere,
+//! or geodesics on the WGS84 ellipsoid with the `_with_model` variants, with
+//! coordinates as `x` = longitude and `y` = latitude:
+//!
+//! ```
+//! use xcsoar_tasks::polygon::ZoneGeometry;
+//!
+//! let xml = std::fs::read_to_string("fixtures/racing-task.tsk").unwrap();
+//! let task = xcsoar_tasks::from_str(&xml).unwrap();
+//! for geometry in task.zone_polygons() {
+//!     match geometry {
+//!         ZoneGeometry::Polygon(polygon) => println!("{:?}", polygon.exterior()),
+//!         ZoneGeometry::Line(line) => println!("{line:?}"),
+//!     }
+//! }
+//! ```
+
+use crate::{EarthModel, Location, Point, Task, ZoneShape};
+use geo_types::{Coord, Geometry, LineString, Polygon};
+
+/// Number of segments approximating a full circle.
+const CIRCLE_SEGMENTS: usize = 64;
+
+/// The area of a zone, or the line for start and finish lines.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ZoneGeometry {
+    Polygon(Polygon),
+    Line(LineString),
+}
+
+impl From<ZoneGeometry> for Geometry {
+    fn from(geometry: ZoneGeometry) -> Self {
+        match geometry {
+            ZoneGeometry::Polygon(polygon) => Geometry::Polygon(polygon),
+            ZoneGeometry::Line(line) => Geometry::LineString(line),
+        }
+    }
+}
+
+impl From<Location> for Coord {
+    fn from(location: Location) -> Self {
+        Coord {
+            x: location.longitude,
+            y: location.latitude,
+        }
+    }
+}
+
+impl From<Coord> for Location {
+    fn from(coord: Coord) -> Self {
+        Location {
+            longitude: coord.x,
+            latitude: coord.y,
+        }
+    }
+}
+
+impl From<Location> for geo_types::Point {
+    fn from(location: Location) -> Self {
+        geo_types::Point(location.into())
+    }
+}
+
+impl From<geo_types::Point> for Location {
+    fn from(point: geo_types::Point) -> Self {
+        point.0.into()
+    }
+}
+
+impl Point {
+    /// Returns the observation zone around this point, oriented by the
+    /// previous and next point of the task, see
+    /// [`ResolvedZone::direction`](crate::ResolvedZone::direction).
+    ///
+    /// FAI sectors are cut off at 20 km.
+    pub fn zone_polygon(&self, previous: Option<&Point>, next: Option<&Point>) -> ZoneGeometry {
+        self.zone_polygon_with_model(EarthModel::FaiSphere, previous, next)
+    }
+
+    /// Like [`Point::zone_polygon`], with the zone oriented and its arcs
+    /// drawn on `model`.
+    pub fn zone_polygon_with_model(
+        &self,
+        model: EarthModel,
+        previous: Option<&Point>,
+        next: Option<&Point>,
+    ) -> ZoneGeometry {
+        let center = self.waypoint.location;
+        let zone = self.observation_zone.resolve();
+        let direction = zone.direction_with_model(
+            model,
+            &center,
+            previous.map(|point| &point.waypoint.location),
+            next.map(|point| &point.waypoint.location),
+        );
+        let radius = zone.finite_radius();
+        let half_angle = zone.angle / 2.0;
+
+        if zone.shape == ZoneShape::Line {
+            return ZoneGeometry::Line(LineString::from(vec![
+                Coord::from(model.destination(&center, direction - 90.0, radius)),
+                Coord::from(model.destination(&center, direction + 90.0, radius)),
+            ]));
+        }
+        if zone.shape == ZoneShape::Cylinder || zone.angle >= 360.0 {
+            let interiors = match zone.shape {
+                ZoneShape::Sector if zone.inner_radius > 0.0 => {
+                    vec![circle(model, center, zone.inner_radius)]
+                }
+                _ => vec![],
+            };
+            return ZoneGeometry::Polygon(Polygon::new(circle(model, center, radius), interiors));
+        }
+
+        let start = direction - half_angle;
+        let mut coords = arc(model, center, radius, start, zone.angle);
+        match zone.shape {
+            ZoneShape::Sector if zone.inner_radius > 0.0 => {
+                let mut inner = arc(model, center, zone.inner_radius, start, zone.angle);
+                inner.reverse();
+                coords.extend(inner);
+            }
+            ZoneShape::Keyhole => {
+                // The rest of the cylinder, continuing from the end of the sector
+                coords.extend(arc(
+                    model,
+                    center,
+                    zone.inner_radius,
+                    direction + half_angle,
+                    360.0 - zone.angle,
+                ));
+            }
+            _ => coords.push(center.into()),
+        }
+        ZoneGeometry::Polygon(Polygon::new(LineString::from(coords), vec![]))
+    }
+}
+
+impl Task {
+    /// Returns the observation zone of every point, see
+    /// [`Point::zone_polygon`].
+    ///
+    /// Zones face their neighbours along the course. Optional starts, which
+    /// XCSoar stores after the finish, face the first turnpoint like the
+    /// start does.
+    pub fn zone_polygons(&self) -> Vec<ZoneGeometry> {
+        self.zone_polygons_with_model(EarthModel::FaiSphere)
+    }
+
+    /// Like [`Task::zone_polygons`], with the zones oriented and drawn on
+    /// `model`.
+    pub fn zone_polygons_with_model(&self, model: EarthModel) -> Vec<ZoneGeometry> {
+        let course = self.course();
+        let mut positions = vec![None; self.points.len()];
+        for (position, (index, _)) in course.iter().enumerate() {
+            positions[*index] = Some(position);
+        }
+        let neighbour = |position: usize| course.get(position).map(|(_, point)| *point);
+
+        self.points
+            .iter()
+            .zip(positions)
+            .map(|(point, position)| match position {
+                Some(position) => point.zone_polygon_with_model(
+                    model,
+                    position.checked_sub(1).and_then(neighbour),
+                    neighbour(position + 1),
+                ),
+                None => point.zone_polygon_with_model(model, None, neighbour(1)),
+            })
+            .collect()
+    }
+}
+
+fn circle(model: EarthModel, center: Location, radius: f64) -> LineString {
+    LineString::from(arc(model, center, radius, 0.0, 360.0))
+}
+
+/// Points along a clockwise arc of `sweep` degrees, including both ends.
+fn arc(model: EarthModel, center: Location, radius: f64, start: f64, sweep: f64) -> Vec<Coord> {
+    let segments = ((sweep / 360.0 * CIRCLE_SEGMENTS as f64).ceil() as usize).max(1);
+    (0..=segments)
+        .map(|i| {
+            let bearing = start + sweep * i as f64 / segments as f64;
+            model.destination(&center, bearing, radius).into()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ObservationZone, PointType, Waypoint};
+    use insta::assert_snapshot;
+
+    fn point(longitude: f64, latitude: f64, observation_zone: ObservationZone) -> Point {
+        Point {
+            point_type: PointType::Turn,
+            score_exit: None,
+            waypoint: Waypoint {
+                name: "Test".to_string(),
+                altitude: None,
+                id: None,
+                comment: None,
+                location: Location {
+                    longitude,
+                    latitude,
+                },
+            },
+            observation_zone,
+        }
+    }
+
+    /// Summarizes a geometry by its rings and their first coordinate,
+    /// rounded to about 10 cm.
+    fn render(geometry: &ZoneGeometry) -> String {
+        let render_line = |line: &LineString| {
+            let first = line.0[0];
+            format!(
+                "{} points from ({:.6} {:.6})",
+                line.0.len(),
+                first.x,
+                first.y
+            )
+        };
+        match geometry {
+            ZoneGeometry::Polygon(polygon) => {
+                let mut rendered = format!("polygon, {}", render_line(polygon.exterior()));
+                for interior in polygon.interiors() {
+                    rendered += &format!(", hole of {}", render_line(interior));
+                }
+                rendered
+            }
+            ZoneGeometry::Line(line) => format!("line, {}", render_line(line)),
+        }
+    }
+
+    #[test]
+    fn location_conversions() {
+        let location = Location {
+            longitude: 7.5,
+            latitude: 51.25,
+        };
+        let point = geo_types::Point::from(location);
+        assert_eq!((point.x(), point.y()), (7.5, 51.25));
+        assert_eq!(Location::from(point), location);
+    }
+
+    #[test]
+    fn sector_faces_away_from_course() {
+        let previous = point(-0.5, 0.0, ObservationZone::Cylinder { radius: 500.0 });
+        let next = point(0.0, 0.5, ObservationZone::Cylinder { radius: 500.0 });
+        let turnpoint = point(0.0, 0.0, ObservationZone::FAISector);
+
+        let geometry = turnpoint.zone_polygon(Some(&previous), Some(&next));
+        let ZoneGeometry::Polygon(polygon) = geometry else {
+            panic!("expected a polygon");
+        };
+        // The sector lies south-east of the point, outside the left turn
+        for coord in polygon.exterior().coords() {
+            assert!(coord.x >= -1e-9 && coord.y <= 1e-9, "{coord:?}");
+        }
+    }
+
+    #[test]
+    fn line_is_perpendicular_to_first_leg() {
+        let start = point(0.0, 0.0, ObservationZone::Line { length: 2000.0 });
+        let next = point(0.5, 0.0, ObservationZone::Cylinder { radius: 500.0 });
+
+        let ZoneGeometry::Line(line) = start.zone_polygon(None, Some(&next)) else {
+            panic!("expected a line");
+        };
+        let [a, b] = [line.0[0], line.0[1]];
+        assert!(a.x.abs() < 1e-9 && b.x.abs() < 1e-9);
+        assert!((Location::from(a).distance_to(&b.into()) - 2000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn wgs84_line_has_the_geodesic_length() {
+        let start = point(7.0, 51.0, ObservationZone::Line { length: 20_000.0 });
+        let next = point(7.5, 51.0, ObservationZone::Cylinder { radius: 500.0 });
+
+        let geometry = start.zone_polygon_with_model(EarthModel::Wgs84, None, Some(&next));
+        let ZoneGeometry::Line(line) = geometry else {
+            panic!("expected a line");
+        };
+        let [a, b] = [Location::from(line.0[0]), Location::from(line.0[1])];
+        let center = start.waypoint.location;
+        for end in [a, b] {
+            let distance = EarthModel::Wgs84.distance(&center, &end);
+            assert!((distance - 10_000.0).abs() < 1e-6, "{distance}");
+        }
+        // On the sphere the same line is measurably shorter or longer
+        let sphere = EarthModel::FaiSphere.distance(&center, &a);
+        assert!((sphere - 10_000.0).abs() > 1.0, "{sphere}");
+    }
+
+    #[test]
+    fn zone_polygons() {
+        let task = crate::from_str(include_str!("../fixtures/all-oz-types.tsk")).unwrap();
+        let rendered = task
+            .zone_polygons()
+            .iter()
+            .zip(&task.points)
+            .map(|(geometry, point)| format!("{}: {}", point.waypoint.name, render(geometry)))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_snapshot!(rendered);
+    }
+
+    #[test]
+    fn optional_starts_face_the_first_leg() {
+        let task = crate::from_str(include_str!("../fixtures/optional-start-task.tsk")).unwrap();
+        let polygons = task.zone_polygons();
+        let [.., finish, optional_start] = polygons.as_slice() else {
+            panic!("expected a finish and an optional start");
+        };
+
+        let points = &task.points;
+        let expected = points[4].zone_polygon(Some(&points[3]), None);
+        assert_eq!(render(finish), render(&expected));
+        let expected = points[5].zone_polygon(None, Some(&points[1]));
+        assert_eq!(render(optional_start), render(&expected));
+    }
+}
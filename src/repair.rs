@@ -0,0 +1,296 @@
+//! Deterministic fixes for tasks that break the rules of their type.
+//!
+//! [`Task::repair`] fixes what [`Task::validate`] reports wherever there is
+//! an obvious answer, and returns a log of what it changed:
+//!
+//! ```
+//! use xcsoar_tasks::TaskType;
+//!
+//! let xml = std::fs::read_to_string("fixtures/aat-task.tsk").unwrap();
+//! let mut task = xcsoar_tasks::from_str(&xml).unwrap();
+//! task.task_type = TaskType::RT;
+//! for repair in task.repair() {
+//!     println!("{repair}");
+//! }
+//! assert!(task.validate().is_empty());
+//! ```
+
+use crate::constraints::{Role, TaskConstraints, role_point_type, roles};
+use crate::format::{self, UnitSystem};
+use crate::{ObservationZone, PointType, Task, TaskType, ZoneKind};
+use std::fmt;
+use std::time::Duration;
+
+/// Radius of replacement cylinders and sectors on AAT areas.
+const AREA_RADIUS: f64 = 10_000.0;
+
+/// Radius of replacement cylinders on turnpoints.
+const TURNPOINT_RADIUS: f64 = 500.0;
+
+/// Radius of replacement cylinders on start and finish.
+const START_FINISH_RADIUS: f64 = 1000.0;
+
+/// Length of replacement start and finish lines.
+const LINE_LENGTH: f64 = 1000.0;
+
+/// Options for [`Task::repair_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepairOptions {
+    /// Minimum time set on AAT and MAT tasks that don't have one.
+    pub aat_min_time: Duration,
+}
+
+impl Default for RepairOptions {
+    /// Three hours, which XCSoar also uses for new tasks.
+    fn default() -> Self {
+        RepairOptions {
+            aat_min_time: Duration::from_secs(3 * 3600),
+        }
+    }
+}
+
+/// A change made by [`Task::repair`]. Indices are zero-based positions in
+/// `Task::points`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Repair {
+    PointType {
+        index: usize,
+        name: String,
+        old: PointType,
+        new: PointType,
+    },
+
+    Zone {
+        index: usize,
+        name: String,
+        old: ObservationZone,
+        new: ObservationZone,
+    },
+
+    AatMinTime(Duration),
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let units = UnitSystem::METRIC;
+        match self {
+            Repair::PointType {
+                index,
+                name,
+                old,
+                new,
+            } => write!(
+                f,
+                "point {} ({name}): changed {old:?} to {new:?}",
+                index + 1
+            ),
+            Repair::Zone {
+                index,
+                name,
+                old,
+                new,
+            } => write!(
+                f,
+                "point {} ({name}): replaced {} with {}",
+                index + 1,
+                format::zone(old, units),
+                format::zone(new, units)
+            ),
+            Repair::AatMinTime(duration) => {
+                write!(f, "set AAT minimum time to {}", format::duration(*duration))
+            }
+        }
+    }
+}
+
+impl Task {
+    /// Repairs the task with the default [`RepairOptions`].
+    pub fn repair(&mut self) -> Vec<Repair> {
+        self.repair_with_options(RepairOptions::default())
+    }
+
+    /// Applies deterministic fixes for the violations found by
+    /// [`Task::validate`]:
+    ///
+    /// - the first and last point become `Start` and `Finish`
+    /// - intermediate points get a point type allowed for the task type,
+    ///   e.g. `Area` becomes `Turn` in a racing task
+    /// - zones that aren't allowed for a point are replaced with the default
+    ///   zone of its role, see [`TaskConstraints`]
+    /// - if the task type requires homogeneous turnpoints, all of them get
+    ///   the zone of the first one
+    /// - AAT and MAT tasks without a minimum time get
+    ///   [`RepairOptions::aat_min_time`]
+    ///
+    /// The number of points and unclosed courses are left alone, since
+    /// fixing them means adding, removing or moving points. Returns the
+    /// changes in the order they were made.
+    pub fn repair_with_options(&mut self, options: RepairOptions) -> Vec<Repair> {
+        let constraints = self.task_type.constraints();
+        let roles = roles(self);
+        let mut repairs = Vec::new();
+        let mut homogeneous_zone: Option<ObservationZone> = None;
+
+        for (index, (point, &role)) in self.points.iter_mut().zip(&roles).enumerate() {
+            let zone = point.observation_zone.kind();
+            if point.point_type != PointType::OptionalStart {
+                let point_type = allowed_point_type(constraints, role, point.point_type, zone);
+                if point_type != point.point_type {
+                    repairs.push(Repair::PointType {
+                        index,
+                        name: point.waypoint.name.clone(),
+                        old: point.point_type,
+                        new: point_type,
+                    });
+                    point.point_type = point_type;
+                }
+            }
+
+            let point_type = role_point_type(point.point_type);
+            let homogeneous = role == Role::Intermediate && constraints.homogeneous_turnpoints;
+            let replacement = match &homogeneous_zone {
+                Some(first) if homogeneous => (first.kind() != zone).then(|| first.clone()),
+                _ if constraints.is_allowed(role, point_type, zone) => None,
+                _ => constraints
+                    .zones(role, point_type)
+                    .next()
+                    .map(|default| default_zone(default, point_type)),
+            };
+            if let Some(new) = replacement {
+                repairs.push(Repair::Zone {
+                    index,
+                    name: point.waypoint.name.clone(),
+                    old: std::mem::replace(&mut point.observation_zone, new.clone()),
+                    new,
+                });
+            }
+
+            if homogeneous && homogeneous_zone.is_none() {
+                homogeneous_zone = Some(point.observation_zone.clone());
+            }
+        }
+
+        if matches!(self.task_type, TaskType::AAT | TaskType::MAT) && self.aat_min_time.is_none() {
+            self.aat_min_time = Some(options.aat_min_time);
+            repairs.push(Repair::AatMinTime(options.aat_min_time));
+        }
+
+        repairs
+    }
+}
+
+/// Keeps `point_type` if the role allows it, otherwise picks the first
+/// allowed point type that accepts the current zone, or the role's default.
+fn allowed_point_type(
+    constraints: &TaskConstraints,
+    role: Role,
+    point_type: PointType,
+    zone: ZoneKind,
+) -> PointType {
+    let allowed = constraints.allowed(role);
+    if allowed.iter().any(|kind| kind.point_type == point_type) {
+        return point_type;
+    }
+    allowed
+        .iter()
+        .find(|kind| kind.zone == zone)
+        .or(allowed.first())
+        .map_or(point_type, |kind| kind.point_type)
+}
+
+fn default_zone(kind: ZoneKind, point_type: PointType) -> ObservationZone {
+    let radius = match point_type {
+        PointType::Area => AREA_RADIUS,
+        PointType::Turn => TURNPOINT_RADIUS,
+        PointType::Start | PointType::OptionalStart | PointType::Finish => START_FINISH_RADIUS,
+    };
+    match kind {
+        ZoneKind::Cylinder => ObservationZone::Cylinder { radius },
+        ZoneKind::Line => ObservationZone::Line {
+            length: LINE_LENGTH,
+        },
+        ZoneKind::Keyhole => ObservationZone::Keyhole,
+        ZoneKind::FAISector => ObservationZone::FAISector,
+        ZoneKind::Sector => ObservationZone::Sector {
+            radius,
+            start_radial: 0.0,
+            end_radial: 360.0,
+            inner_radius: None,
+        },
+        ZoneKind::SymmetricQuadrant => ObservationZone::SymmetricQuadrant {
+            radius: None,
+            angle: None,
+        },
+        ZoneKind::CustomKeyhole => ObservationZone::CustomKeyhole {
+            radius: None,
+            angle: None,
+            inner_radius: None,
+        },
+        ZoneKind::MatCylinder => ObservationZone::MatCylinder,
+        ZoneKind::BGAStartSector => ObservationZone::BGAStartSector,
+        ZoneKind::BGAFixedCourse => ObservationZone::BGAFixedCourse,
+        ZoneKind::BGAEnhancedOption => ObservationZone::BGAEnhancedOption,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    fn render(repairs: &[Repair]) -> String {
+        repairs
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn valid_tasks_are_unchanged() {
+        for xml in [
+            include_str!("../fixtures/aat-task.tsk"),
+            include_str!("../fixtures/fai-task.tsk"),
+            include_str!("../fixtures/racing-task.tsk"),
+        ] {
+            let mut task = crate::from_str(xml).unwrap();
+            let original = task.clone();
+            assert_eq!(task.repair(), vec![]);
+            assert_eq!(task, original);
+        }
+    }
+
+    #[test]
+    fn aat_task_as_racing_task() {
+        let mut task = crate::from_str(include_str!("../fixtures/aat-task.tsk")).unwrap();
+        task.task_type = TaskType::RT;
+        task.points.swap(0, 5);
+        assert_snapshot!(render(&task.repair()));
+        assert_eq!(task.validate(), vec![]);
+    }
+
+    #[test]
+    fn mixed_task_as_fai_task() {
+        let mut task = crate::from_str(include_str!("../fixtures/all-oz-types.tsk")).unwrap();
+        task.task_type = TaskType::FAIGeneral;
+        task.points.truncate(9);
+        task.points.push(task.points[0].clone());
+        task.points[9].point_type = PointType::Finish;
+        assert_snapshot!(render(&task.repair()));
+        assert_eq!(task.validate(), vec![]);
+    }
+
+    #[test]
+    fn fills_aat_min_time() {
+        let mut task = crate::from_str(include_str!("../fixtures/aat-task.tsk")).unwrap();
+        task.aat_min_time = None;
+        let options = RepairOptions {
+            aat_min_time: Duration::from_secs(2 * 3600),
+        };
+        assert_eq!(
+            task.repair_with_options(options),
+            vec![Repair::AatMinTime(Duration::from_secs(2 * 3600))]
+        );
+        assert_eq!(task.aat_min_time, Some(Duration::from_secs(2 * 3600)));
+    }
+}
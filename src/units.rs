@@ -0,0 +1,164 @@
+//! Typed quantities used by the task model.
+
+use crate::AltitudeReference;
+use std::fmt;
+
+const FEET_PER_METER: f64 = 1.0 / 0.3048;
+const METERS_PER_SECOND_PER_KNOT: f64 = 1852.0 / 3600.0;
+const METERS_PER_SECOND_PER_MPH: f64 = 1609.344 / 3600.0;
+
+/// A time of day, counted in seconds since midnight UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeOfDay(u32);
+
+impl TimeOfDay {
+    pub const MIDNIGHT: TimeOfDay = TimeOfDay(0);
+
+    /// Returns `None` for 86400 seconds (24:00:00) and later.
+    pub fn from_seconds(seconds: u32) -> Option<Self> {
+        (seconds < 86_400).then_some(TimeOfDay(seconds))
+    }
+
+    /// Returns `None` if any component is out of range.
+    pub fn from_hms(hours: u32, minutes: u32, seconds: u32) -> Option<Self> {
+        if hours < 24 && minutes < 60 && seconds < 60 {
+            Some(TimeOfDay(hours * 3600 + minutes * 60 + seconds))
+        } else {
+            None
+        }
+    }
+
+    pub fn as_seconds(self) -> u32 {
+        self.0
+    }
+
+    pub fn hours(self) -> u32 {
+        self.0 / 3600
+    }
+
+    pub fn minutes(self) -> u32 {
+        self.0 / 60 % 60
+    }
+
+    pub fn seconds(self) -> u32 {
+        self.0 % 60
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}:{:02}",
+            self.hours(),
+            self.minutes(),
+            self.seconds()
+        )
+    }
+}
+
+/// A speed, stored in meters per second like XCSoar does.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Speed(f64);
+
+impl Speed {
+    pub fn from_meters_per_second(value: f64) -> Self {
+        Speed(value)
+    }
+
+    pub fn from_kilometers_per_hour(value: f64) -> Self {
+        Speed(value / 3.6)
+    }
+
+    pub fn from_knots(value: f64) -> Self {
+        Speed(value * METERS_PER_SECOND_PER_KNOT)
+    }
+
+    pub fn from_miles_per_hour(value: f64) -> Self {
+        Speed(value * METERS_PER_SECOND_PER_MPH)
+    }
+
+    pub fn meters_per_second(self) -> f64 {
+        self.0
+    }
+
+    pub fn kilometers_per_hour(self) -> f64 {
+        self.0 * 3.6
+    }
+
+    pub fn knots(self) -> f64 {
+        self.0 / METERS_PER_SECOND_PER_KNOT
+    }
+
+    pub fn miles_per_hour(self) -> f64 {
+        self.0 / METERS_PER_SECOND_PER_MPH
+    }
+}
+
+/// A height limit together with the reference it is measured from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Altitude {
+    pub meters: u32,
+    pub reference: AltitudeReference,
+}
+
+impl Altitude {
+    pub fn agl(meters: u32) -> Self {
+        Altitude {
+            meters,
+            reference: AltitudeReference::AGL,
+        }
+    }
+
+    pub fn msl(meters: u32) -> Self {
+        Altitude {
+            meters,
+            reference: AltitudeReference::MSL,
+        }
+    }
+
+    /// Converts from feet, rounding to the nearest meter.
+    pub fn from_feet(feet: f64, reference: AltitudeReference) -> Self {
+        Altitude {
+            meters: (feet / FEET_PER_METER).round() as u32,
+            reference,
+        }
+    }
+
+    pub fn feet(self) -> f64 {
+        f64::from(self.meters) * FEET_PER_METER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn time_of_day() {
+        let time = TimeOfDay::from_hms(13, 5, 9).unwrap();
+        assert_eq!(time.as_seconds(), 47109);
+        assert_eq!(time.to_string(), "13:05:09");
+        assert_eq!(TimeOfDay::from_hms(24, 0, 0), None);
+        assert_eq!(
+            TimeOfDay::from_seconds(86_399),
+            TimeOfDay::from_hms(23, 59, 59)
+        );
+        assert_eq!(TimeOfDay::from_seconds(86_400), None);
+    }
+
+    #[test]
+    fn speed_conversions() {
+        let speed = Speed::from_kilometers_per_hour(180.0);
+        assert_eq!(speed.meters_per_second(), 50.0);
+        assert!((speed.knots() - 97.192).abs() < 0.001);
+        assert!((Speed::from_knots(100.0).miles_per_hour() - 115.078).abs() < 0.001);
+    }
+
+    #[test]
+    fn altitude_conversions() {
+        let altitude = Altitude::from_feet(5000.0, AltitudeReference::MSL);
+        assert_eq!(altitude, Altitude::msl(1524));
+        assert!((Altitude::agl(1000).feet() - 3280.84).abs() < 0.01);
+    }
+}
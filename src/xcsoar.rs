@@ -0,0 +1,303 @@
+//! Serializer reproducing the exact output of XCSoar's task `Serialiser`.
+//!
+//! The serde-based writer orders attributes by struct field. XCSoar instead
+//! writes waypoint attributes as `name, id, comment, altitude`. Current
+//! versions indent by two spaces and keep a decimal part on integral values
+//! (`length="20000.0"`), while XCSoar 6 indented with tabs, left it off
+//! (`radius="1000"`) and wrote height references as numbers.
+
+use crate::{
+    AltitudeReference, ObservationZone, Point, PointType, Speed, Task, TaskType, TimeOfDay,
+    duration_to_seconds,
+};
+use quick_xml::escape::escape;
+use std::fmt::{self, Write as FmtWrite};
+
+/// The XCSoar version whose output is reproduced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Flavor {
+    Current,
+    XCSoar6,
+}
+
+impl Flavor {
+    fn indent(self) -> &'static str {
+        match self {
+            Flavor::Current => "  ",
+            Flavor::XCSoar6 => "\t",
+        }
+    }
+
+    /// Formats a floating point value the way this version does. Values
+    /// are written in their shortest form that reads back unchanged, and
+    /// integral values keep a decimal part in current versions.
+    fn format_f64(self, value: f64) -> String {
+        match self {
+            Flavor::Current if value.is_finite() && value.fract() == 0.0 => format!("{value:.1}"),
+            Flavor::Current | Flavor::XCSoar6 => value.to_string(),
+        }
+    }
+}
+
+pub(crate) fn write_task(writer: &mut impl FmtWrite, task: &Task, flavor: Flavor) -> fmt::Result {
+    let mut attributes = vec![("type", task_type_name(task.task_type).to_string())];
+    push_opt_bool(&mut attributes, "task_scored", task.task_scored);
+    let aat_min_time = task.aat_min_time.map(duration_to_seconds);
+    push_opt(&mut attributes, "aat_min_time", aat_min_time);
+    push_opt_bool(
+        &mut attributes,
+        "start_requires_arm",
+        task.start_requires_arm,
+    );
+    push_opt_bool(&mut attributes, "start_score_exit", task.start_score_exit);
+    let start_max_speed = task.start_max_speed.map(Speed::meters_per_second);
+    push_opt(&mut attributes, "start_max_speed", start_max_speed);
+    if let Some(altitude) = task.start_max_height {
+        attributes.push(("start_max_height", altitude.meters.to_string()));
+        let reference = match (flavor, altitude.reference) {
+            // XCSoar 6 wrote this reference as a number
+            (Flavor::XCSoar6, AltitudeReference::AGL) => "0",
+            (Flavor::XCSoar6, AltitudeReference::MSL) => "1",
+            (Flavor::Current, reference) => reference_name(reference),
+        };
+        attributes.push(("start_max_height_ref", reference.to_string()));
+    }
+    let start_open_time = task.start_open_time.map(TimeOfDay::as_seconds);
+    push_opt(&mut attributes, "start_open_time", start_open_time);
+    let start_close_time = task.start_close_time.map(TimeOfDay::as_seconds);
+    push_opt(&mut attributes, "start_close_time", start_close_time);
+    if let Some(altitude) = task.finish_min_height {
+        attributes.push(("finish_min_height", altitude.meters.to_string()));
+        // XCSoar 6 didn't write this reference, so it defaults to AGL
+        if flavor != Flavor::XCSoar6 || altitude.reference != AltitudeReference::AGL {
+            let reference = reference_name(altitude.reference).to_string();
+            attributes.push(("finish_min_height_ref", reference));
+        }
+    }
+    push_opt_bool(&mut attributes, "fai_finish", task.fai_finish);
+    push_opt(&mut attributes, "min_points", task.min_points);
+    push_opt(&mut attributes, "max_points", task.max_points);
+    push_opt_bool(&mut attributes, "homogeneous_tps", task.homogeneous_tps);
+    push_opt_bool(&mut attributes, "is_closed", task.is_closed);
+    let pev_start_wait_time = task.pev_start_wait_time.map(duration_to_seconds);
+    push_opt(&mut attributes, "pev_start_wait_time", pev_start_wait_time);
+    let pev_start_window = task.pev_start_window.map(duration_to_seconds);
+    push_opt(&mut attributes, "pev_start_window", pev_start_window);
+
+    if task.points.is_empty() {
+        return empty_element(writer, flavor, 0, "Task", &attributes);
+    }
+
+    start_element(writer, flavor, 0, "Task", &attributes)?;
+    for point in &task.points {
+        write_point(writer, flavor, point)?;
+    }
+    end_element(writer, flavor, 0, "Task")
+}
+
+fn write_point(writer: &mut impl FmtWrite, flavor: Flavor, point: &Point) -> fmt::Result {
+    let mut attributes = vec![("type", point_type_name(point.point_type).to_string())];
+    push_opt_bool(&mut attributes, "score_exit", point.score_exit);
+    start_element(writer, flavor, 1, "Point", &attributes)?;
+
+    let waypoint = &point.waypoint;
+    let mut attributes = vec![("name", waypoint.name.clone())];
+    push_opt(&mut attributes, "id", waypoint.id.as_ref());
+    push_opt(&mut attributes, "comment", waypoint.comment.as_ref());
+    push_opt_f64(&mut attributes, flavor, "altitude", waypoint.altitude);
+    start_element(writer, flavor, 2, "Waypoint", &attributes)?;
+
+    let location = waypoint.location;
+    let attributes = [
+        ("longitude", flavor.format_f64(location.longitude)),
+        ("latitude", flavor.format_f64(location.latitude)),
+    ];
+    empty_element(writer, flavor, 3, "Location", &attributes)?;
+    end_element(writer, flavor, 2, "Waypoint")?;
+
+    let attributes = zone_attributes(flavor, &point.observation_zone);
+    empty_element(writer, flavor, 2, "ObservationZone", &attributes)?;
+    end_element(writer, flavor, 1, "Point")
+}
+
+fn zone_attributes(flavor: Flavor, zone: &ObservationZone) -> Vec<(&'static str, String)> {
+    let mut attributes = Vec::new();
+    match zone {
+        ObservationZone::Cylinder { radius } => {
+            attributes.push(("type", "Cylinder".to_string()));
+            attributes.push(("radius", flavor.format_f64(*radius)));
+        }
+        ObservationZone::Line { length } => {
+            attributes.push(("type", "Line".to_string()));
+            attributes.push(("length", flavor.format_f64(*length)));
+        }
+        ObservationZone::Keyhole => attributes.push(("type", "Keyhole".to_string())),
+        ObservationZone::FAISector => attributes.push(("type", "FAISector".to_string())),
+        ObservationZone::Sector {
+            radius,
+            start_radial,
+            end_radial,
+            inner_radius,
+        } => {
+            attributes.push(("type", "Sector".to_string()));
+            attributes.push(("radius", flavor.format_f64(*radius)));
+            attributes.push(("start_radial", flavor.format_f64(*start_radial)));
+            attributes.push(("end_radial", flavor.format_f64(*end_radial)));
+            push_opt_f64(&mut attributes, flavor, "inner_radius", *inner_radius);
+        }
+        ObservationZone::SymmetricQuadrant { radius, angle } => {
+            attributes.push(("type", "SymmetricQuadrant".to_string()));
+            push_opt_f64(&mut attributes, flavor, "radius", *radius);
+            push_opt_f64(&mut attributes, flavor, "angle", *angle);
+        }
+        ObservationZone::CustomKeyhole {
+            radius,
+            angle,
+            inner_radius,
+        } => {
+            attributes.push(("type", "CustomKeyhole".to_string()));
+            push_opt_f64(&mut attributes, flavor, "radius", *radius);
+            push_opt_f64(&mut attributes, flavor, "angle", *angle);
+            push_opt_f64(&mut attributes, flavor, "inner_radius", *inner_radius);
+        }
+        ObservationZone::MatCylinder => attributes.push(("type", "MatCylinder".to_string())),
+        ObservationZone::BGAStartSector => {
+            attributes.push(("type", "BGAStartSector".to_string()));
+        }
+        ObservationZone::BGAFixedCourse => {
+            attributes.push(("type", "BGAFixedCourse".to_string()));
+        }
+        ObservationZone::BGAEnhancedOption => {
+            attributes.push(("type", "BGAEnhancedOption".to_string()));
+        }
+    }
+    attributes
+}
+
+fn start_element(
+    writer: &mut impl FmtWrite,
+    flavor: Flavor,
+    depth: usize,
+    name: &str,
+    attributes: &[(&str, String)],
+) -> fmt::Result {
+    open_tag(writer, flavor, depth, name, attributes)?;
+    writer.write_str(">\n")
+}
+
+fn empty_element(
+    writer: &mut impl FmtWrite,
+    flavor: Flavor,
+    depth: usize,
+    name: &str,
+    attributes: &[(&str, String)],
+) -> fmt::Result {
+    open_tag(writer, flavor, depth, name, attributes)?;
+    writer.write_str("/>\n")
+}
+
+fn end_element(
+    writer: &mut impl FmtWrite,
+    flavor: Flavor,
+    depth: usize,
+    name: &str,
+) -> fmt::Result {
+    indent(writer, flavor, depth)?;
+    writeln!(writer, "</{name}>")
+}
+
+fn open_tag(
+    writer: &mut impl FmtWrite,
+    flavor: Flavor,
+    depth: usize,
+    name: &str,
+    attributes: &[(&str, String)],
+) -> fmt::Result {
+    indent(writer, flavor, depth)?;
+    write!(writer, "<{name}")?;
+    for (key, value) in attributes {
+        write!(writer, " {key}=\"{}\"", escape(value.as_str()))?;
+    }
+    Ok(())
+}
+
+fn indent(writer: &mut impl FmtWrite, flavor: Flavor, depth: usize) -> fmt::Result {
+    for _ in 0..depth {
+        writer.write_str(flavor.indent())?;
+    }
+    Ok(())
+}
+
+fn push_opt(attributes: &mut Vec<(&str, String)>, key: &'static str, value: Option<impl ToString>) {
+    if let Some(value) = value {
+        attributes.push((key, value.to_string()));
+    }
+}
+
+fn push_opt_f64(
+    attributes: &mut Vec<(&str, String)>,
+    flavor: Flavor,
+    key: &'static str,
+    value: Option<f64>,
+) {
+    push_opt(attributes, key, value.map(|value| flavor.format_f64(value)));
+}
+
+fn push_opt_bool(attributes: &mut Vec<(&str, String)>, key: &'static str, value: Option<bool>) {
+    push_opt(
+        attributes,
+        key,
+        value.map(|value| if value { "1" } else { "0" }),
+    );
+}
+
+fn reference_name(reference: AltitudeReference) -> &'static str {
+    match reference {
+        AltitudeReference::AGL => "AGL",
+        AltitudeReference::MSL => "MSL",
+    }
+}
+
+fn task_type_name(task_type: TaskType) -> &'static str {
+    match task_type {
+        TaskType::AAT => "AAT",
+        TaskType::RT => "RT",
+        TaskType::FAIGeneral => "FAIGeneral",
+        TaskType::FAITriangle => "FAITriangle",
+        TaskType::FAIOR => "FAIOR",
+        TaskType::FAIGoal => "FAIGoal",
+        TaskType::MAT => "MAT",
+        TaskType::Mixed => "Mixed",
+        TaskType::Touring => "Touring",
+    }
+}
+
+fn point_type_name(point_type: PointType) -> &'static str {
+    match point_type {
+        PointType::Start => "Start",
+        PointType::Turn => "Turn",
+        PointType::Area => "Area",
+        PointType::Finish => "Finish",
+        PointType::OptionalStart => "OptionalStart",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn number_formatting() {
+        assert_eq!(Flavor::Current.format_f64(20000.0), "20000.0");
+        assert_eq!(Flavor::Current.format_f64(-0.0), "-0.0");
+        assert_eq!(Flavor::Current.format_f64(6.9852833), "6.9852833");
+        assert_eq!(Flavor::Current.format_f64(51.1), "51.1");
+    }
+
+    #[test]
+    fn xcsoar6_number_formatting() {
+        assert_eq!(Flavor::XCSoar6.format_f64(1000.0), "1000");
+        assert_eq!(Flavor::XCSoar6.format_f64(-0.0), "-0");
+        assert_eq!(Flavor::XCSoar6.format_f64(6.39361), "6.39361");
+    }
+}
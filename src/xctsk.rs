@@ -0,0 +1,106 @@
+//! XCTrack `.xctsk` task files (format version 1).
+//!
+//! XCTrack only supports cylinders, so every observation zone is written as
+//! the cylinder a pilot can rely on: the zone's radius for cylinders and
+//! sectors, half the length for lines and the inner cylinder for keyholes.
+//! Optional starts are left out, XCTrack only knows a single start.
+
+use crate::{ObservationZone, PointType, Task, ZoneShape};
+use serde_json::{Map, Value, json};
+
+pub fn to_value(task: &Task) -> Value {
+    let course = task.course();
+    let turnpoints = course
+        .iter()
+        .map(|(_, point)| {
+            let waypoint = &point.waypoint;
+            let mut turnpoint = Map::new();
+            match point.point_type {
+                PointType::Start => {
+                    turnpoint.insert("type".into(), "SSS".into());
+                }
+                PointType::Finish => {
+                    turnpoint.insert("type".into(), "ESS".into());
+                }
+                PointType::Turn | PointType::Area | PointType::OptionalStart => {}
+            }
+            turnpoint.insert(
+                "radius".into(),
+                (cylinder_radius(&point.observation_zone).round() as i64).into(),
+            );
+            turnpoint.insert(
+                "waypoint".into(),
+                json!({
+                    "name": waypoint.name,
+                    "lat": waypoint.location.latitude,
+                    "lon": waypoint.location.longitude,
+                    "altSmoothed": waypoint.altitude.unwrap_or_default().round() as i64,
+                    "description": waypoint.comment.as_deref().unwrap_or_default(),
+                }),
+            );
+            Value::Object(turnpoint)
+        })
+        .collect::<Vec<_>>();
+
+    let mut sss = json!({
+        "type": "RACE",
+        "direction": "EXIT",
+    });
+    if let Some(open_time) = task.start_open_time {
+        sss["timeGates"] = json!([format!("{open_time}Z")]);
+    }
+
+    let goal_type = match course.last().map(|(_, point)| &point.observation_zone) {
+        Some(ObservationZone::Line { .. }) => "LINE",
+        _ => "CYLINDER",
+    };
+
+    json!({
+        "taskType": "CLASSIC",
+        "version": 1,
+        "earthModel": "FAI_SPHERE",
+        "turnpoints": turnpoints,
+        "sss": sss,
+        "goal": { "type": goal_type },
+    })
+}
+
+pub fn to_string(task: &Task) -> String {
+    serde_json::to_string_pretty(&to_value(task)).expect("task values always serialize")
+}
+
+fn cylinder_radius(zone: &ObservationZone) -> f64 {
+    let zone = zone.resolve();
+    match zone.shape {
+        ZoneShape::Keyhole => zone.inner_radius,
+        ZoneShape::Cylinder | ZoneShape::Line | ZoneShape::Sector => zone.finite_radius(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_json_snapshot;
+
+    #[test]
+    fn xctsk_racing_task() {
+        let xml = include_str!("../fixtures/racing-task.tsk");
+        let task = crate::from_str(xml).unwrap();
+        assert_json_snapshot!(to_value(&task));
+    }
+
+    #[test]
+    fn optional_starts_are_left_out() {
+        let xml = include_str!("../fixtures/optional-start-task.tsk");
+        let value = to_value(&crate::from_str(xml).unwrap());
+        let turnpoints = value["turnpoints"].as_array().unwrap();
+        assert_eq!(turnpoints.len(), 5);
+        let starts = turnpoints
+            .iter()
+            .filter(|turnpoint| turnpoint["type"] == "SSS")
+            .count();
+        assert_eq!(starts, 1);
+        assert_eq!(turnpoints[4]["waypoint"]["name"], "002Zielkreis");
+        assert_eq!(turnpoints[4]["type"], "ESS");
+    }
+}
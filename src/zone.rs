@@ -0,0 +1,314 @@
+//! Explicit geometry for every observation zone variant.
+
+use crate::{EarthModel, Location, ObservationZone};
+
+/// Outer radius of XCSoar's keyhole presets and the default of
+/// `SymmetricQuadrant` and `CustomKeyhole`.
+const KEYHOLE_RADIUS: f64 = 10_000.0;
+
+/// Radius of the cylinder around the point in keyholes.
+const KEYHOLE_INNER_RADIUS: f64 = 500.0;
+
+const DEFAULT_ANGLE: f64 = 90.0;
+
+/// One statute mile.
+const MAT_CYLINDER_RADIUS: f64 = 1609.344;
+
+/// Radius used for FAI sectors in formats that can't express infinite ones.
+const FINITE_SECTOR_RADIUS: f64 = 20_000.0;
+
+const BGA_START_SECTOR_RADIUS: f64 = 5000.0;
+const BGA_FIXED_COURSE_RADIUS: f64 = 20_000.0;
+
+/// Parameters of an observation zone with all presets and defaults filled
+/// in, see [`ObservationZone::resolve`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResolvedZone {
+    pub shape: ZoneShape,
+
+    /// Outer radius in meters. Half the length for lines, and infinite for
+    /// FAI sectors.
+    pub radius: f64,
+
+    /// Radius of the cylinder of a keyhole, or of the hole of an annular
+    /// sector, in meters. `0` if there is none.
+    pub inner_radius: f64,
+
+    /// Opening angle of sectors and keyholes in degrees, `360` for
+    /// cylinders and `180` for lines.
+    pub angle: f64,
+
+    pub orientation: Orientation,
+    pub scoring: Scoring,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ZoneShape {
+    /// A circle around the point.
+    Cylinder,
+
+    /// A line through the point, perpendicular to the orientation.
+    Line,
+
+    /// A sector with its apex at the point. With an inner radius, the part
+    /// within that radius is cut out.
+    Sector,
+
+    /// A sector together with a cylinder of the inner radius.
+    Keyhole,
+}
+
+/// Which way a sector, keyhole or line faces.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Orientation {
+    /// Centered on the bisector of the incoming and outgoing leg, facing
+    /// away from the course. Start zones face away from the first leg and
+    /// finish zones along the last one.
+    Bisector,
+
+    /// Centered on a fixed true bearing from the point, in degrees.
+    Fixed(f64),
+}
+
+/// Where distances to the zone are measured to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scoring {
+    /// The center of a cylinder or keyhole.
+    Center,
+
+    /// The apex of a sector.
+    Corner,
+}
+
+impl ResolvedZone {
+    /// True bearing in degrees that the zone faces from `location` on the
+    /// FAI sphere, given the neighbouring points of the task.
+    ///
+    /// For [`Orientation::Bisector`] this points away from the course on the
+    /// outside of the turn, or away from the only neighbour for start and
+    /// finish zones. A point without neighbours faces north.
+    pub fn direction(
+        &self,
+        location: &Location,
+        previous: Option<&Location>,
+        next: Option<&Location>,
+    ) -> f64 {
+        self.direction_with_model(EarthModel::FaiSphere, location, previous, next)
+    }
+
+    /// Like [`ResolvedZone::direction`], with the legs' bearings measured on
+    /// `model`.
+    pub fn direction_with_model(
+        &self,
+        model: EarthModel,
+        location: &Location,
+        previous: Option<&Location>,
+        next: Option<&Location>,
+    ) -> f64 {
+        if let Orientation::Fixed(bearing) = self.orientation {
+            return bearing;
+        }
+
+        let incoming = previous.map(|previous| model.bearing(previous, location));
+        let outgoing = next.map(|next| model.bearing(location, next));
+        match (incoming, outgoing) {
+            (Some(incoming), Some(outgoing)) => {
+                // Signed turn in -180..180, positive to the right
+                let turn = (outgoing - incoming + 180.0).rem_euclid(360.0) - 180.0;
+                let outside = if turn >= 0.0 { -90.0 } else { 90.0 };
+                (incoming + turn / 2.0 + outside).rem_euclid(360.0)
+            }
+            (Some(incoming), None) => incoming,
+            (None, Some(outgoing)) => (outgoing + 180.0).rem_euclid(360.0),
+            (None, None) => 0.0,
+        }
+    }
+
+    /// The radius, with infinite FAI sectors cut off at 20 km for formats
+    /// that need a finite one.
+    pub(crate) fn finite_radius(&self) -> f64 {
+        if self.radius.is_finite() {
+            self.radius
+        } else {
+            FINITE_SECTOR_RADIUS
+        }
+    }
+}
+
+impl ObservationZone {
+    /// Returns the zone's geometry with the sizes of presets like
+    /// [`ObservationZone::Keyhole`] and the defaults of optional parameters
+    /// filled in.
+    ///
+    /// ```
+    /// use xcsoar_tasks::{ObservationZone, ZoneShape};
+    ///
+    /// let zone = ObservationZone::BGAFixedCourse.resolve();
+    /// assert_eq!(zone.shape, ZoneShape::Keyhole);
+    /// assert_eq!((zone.radius, zone.inner_radius, zone.angle), (20000.0, 500.0, 90.0));
+    /// ```
+    pub fn resolve(&self) -> ResolvedZone {
+        let cylinder = |radius| ResolvedZone {
+            shape: ZoneShape::Cylinder,
+            radius,
+            inner_radius: 0.0,
+            angle: 360.0,
+            orientation: Orientation::Bisector,
+            scoring: Scoring::Center,
+        };
+        let sector = |radius, angle| ResolvedZone {
+            shape: ZoneShape::Sector,
+            radius,
+            inner_radius: 0.0,
+            angle,
+            orientation: Orientation::Bisector,
+            scoring: Scoring::Corner,
+        };
+        let keyhole = |radius, inner_radius, angle| ResolvedZone {
+            shape: ZoneShape::Keyhole,
+            radius,
+            inner_radius,
+            angle,
+            orientation: Orientation::Bisector,
+            scoring: Scoring::Center,
+        };
+
+        match *self {
+            ObservationZone::Cylinder { radius } => cylinder(radius),
+            ObservationZone::Line { length } => ResolvedZone {
+                shape: ZoneShape::Line,
+                radius: length / 2.0,
+                angle: 180.0,
+                ..cylinder(0.0)
+            },
+            ObservationZone::Keyhole => keyhole(KEYHOLE_RADIUS, KEYHOLE_INNER_RADIUS, 90.0),
+            ObservationZone::FAISector => sector(f64::INFINITY, 90.0),
+            ObservationZone::Sector {
+                radius,
+                start_radial,
+                end_radial,
+                inner_radius,
+            } => {
+                // Equal radials describe a full circle rather than nothing
+                let sweep = match (end_radial - start_radial).rem_euclid(360.0) {
+                    0.0 => 360.0,
+                    sweep => sweep,
+                };
+                ResolvedZone {
+                    inner_radius: inner_radius.unwrap_or(0.0),
+                    orientation: Orientation::Fixed((start_radial + sweep / 2.0).rem_euclid(360.0)),
+                    ..sector(radius, sweep)
+                }
+            }
+            ObservationZone::SymmetricQuadrant { radius, angle } => sector(
+                radius.unwrap_or(KEYHOLE_RADIUS),
+                angle.unwrap_or(DEFAULT_ANGLE),
+            ),
+            ObservationZone::CustomKeyhole {
+                radius,
+                angle,
+                inner_radius,
+            } => keyhole(
+                radius.unwrap_or(KEYHOLE_RADIUS),
+                inner_radius.unwrap_or(KEYHOLE_INNER_RADIUS),
+                angle.unwrap_or(DEFAULT_ANGLE),
+            ),
+            ObservationZone::MatCylinder => cylinder(MAT_CYLINDER_RADIUS),
+            ObservationZone::BGAStartSector => sector(BGA_START_SECTOR_RADIUS, 180.0),
+            ObservationZone::BGAFixedCourse => {
+                keyhole(BGA_FIXED_COURSE_RADIUS, KEYHOLE_INNER_RADIUS, 90.0)
+            }
+            ObservationZone::BGAEnhancedOption => {
+                keyhole(KEYHOLE_RADIUS, KEYHOLE_INNER_RADIUS, 180.0)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn resolve_all_oz_types() {
+        let xml = include_str!("../fixtures/all-oz-types.tsk");
+        let task = crate::from_str(xml).unwrap();
+        let rendered = task
+            .points
+            .iter()
+            .map(|point| format!("{:?}", point.observation_zone.resolve()))
+            .collect::<Vec<_>>()
+            .join("\n");
+        assert_snapshot!(rendered);
+    }
+
+    #[test]
+    fn bisector_direction() {
+        let zone = ObservationZone::FAISector.resolve();
+        let at = |longitude, latitude| Location {
+            longitude,
+            latitude,
+        };
+        let point = at(0.0, 0.0);
+        let west = at(-1.0, 0.0);
+        let north = at(0.0, 1.0);
+        let south = at(0.0, -1.0);
+
+        // Turning left from eastbound to northbound faces south-east
+        let direction = zone.direction(&point, Some(&west), Some(&north));
+        assert!((direction - 135.0).abs() < 1e-9, "{direction}");
+        // Turning right to southbound faces north-east
+        let direction = zone.direction(&point, Some(&west), Some(&south));
+        assert!((direction - 45.0).abs() < 1e-9, "{direction}");
+        // Going back faces beyond the point
+        let direction = zone.direction(&point, Some(&west), Some(&west));
+        assert!((direction - 90.0).abs() < 1e-9, "{direction}");
+
+        assert!((zone.direction(&point, None, Some(&north)) - 180.0).abs() < 1e-9);
+        assert!((zone.direction(&point, Some(&north), None) - 180.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn direction_follows_earth_model() {
+        let zone = ObservationZone::FAISector.resolve();
+        let at = |longitude, latitude| Location {
+            longitude,
+            latitude,
+        };
+        let (start, next) = (at(7.0, 60.0), at(9.0, 61.0));
+        for model in [EarthModel::FaiSphere, EarthModel::Wgs84] {
+            let expected = (model.bearing(&start, &next) + 180.0).rem_euclid(360.0);
+            let direction = zone.direction_with_model(model, &start, None, Some(&next));
+            assert!(
+                (direction - expected).abs() < 1e-9,
+                "{model:?}: {direction}"
+            );
+        }
+        assert_ne!(
+            zone.direction(&start, None, Some(&next)),
+            zone.direction_with_model(EarthModel::Wgs84, &start, None, Some(&next))
+        );
+    }
+
+    #[test]
+    fn sector_orientation() {
+        let zone = ObservationZone::Sector {
+            radius: 5000.0,
+            start_radial: 300.0,
+            end_radial: 60.0,
+            inner_radius: None,
+        };
+        let resolved = zone.resolve();
+        assert_eq!(resolved.angle, 120.0);
+        assert_eq!(resolved.orientation, Orientation::Fixed(0.0));
+
+        let full = ObservationZone::Sector {
+            radius: 5000.0,
+            start_radial: 0.0,
+            end_radial: 360.0,
+            inner_radius: None,
+        };
+        assert_eq!(full.resolve().angle, 360.0);
+    }
+}
@@ -0,0 +1,240 @@
+//! Runs the `xcsoar-tasks` binary on the fixtures and checks what it prints
+//! and how it exits.
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_xcsoar-tasks"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+fn path(path: &Path) -> &str {
+    path.to_str().unwrap()
+}
+
+#[test]
+fn info() {
+    let output = run(&["info", "fixtures/racing-task.tsk"]);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "Racing, 138.1 km\n\
+         Start  095Monheim AB Dreieck (Start line 20 km)\n\
+         TP1    084Linnich Kontrollpunkt (DAeC keyhole), leg 46.7 km\n\
+         TP2    113Ritzeroder Duenen (DAeC keyhole), leg 20.6 km\n\
+         TP3    021Bergheim (DAeC keyhole), leg 37.4 km\n\
+         Finish 002Zielkreis (Finish cylinder 2 km), leg 33.4 km\n\
+         Start max speed: 216 km/h\n"
+    );
+}
+
+#[test]
+fn info_missing_file() {
+    let output = run(&["info", "fixtures/missing.tsk"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("failed to read fixtures/missing.tsk"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn legs() {
+    let output = run(&["legs", "fixtures/racing-task.tsk", "--declination", "2"]);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output).lines().next(),
+        Some(
+            "1. 095Monheim AB Dreieck - 084Linnich Kontrollpunkt: 259° (257° M), 46.7 km \
+             (adjusted 46.7 km), total 46.7 km (34%)"
+        )
+    );
+    assert_eq!(stdout(&output).lines().count(), 4);
+
+    // The bundled WMM2025 by default
+    let output = run(&["legs", "fixtures/racing-task.tsk", "--year", "2026"]);
+    assert!(output.status.success());
+    assert!(
+        stdout(&output).starts_with(
+            "1. 095Monheim AB Dreieck - 084Linnich Kontrollpunkt: 259° (256° M), 46.7 km"
+        ),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn waypoints() {
+    let output = run(&[
+        "waypoints",
+        "fixtures/racing-task.tsk",
+        "--coordinates",
+        "igc",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output).lines().next(),
+        Some("095Monheim AB Dreieck     5104917N00655933E  50 m")
+    );
+    assert_eq!(stdout(&output).lines().count(), 5);
+}
+
+#[test]
+fn validate() {
+    let output = run(&["validate", "fixtures/racing-task.tsk"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "");
+
+    // Warnings don't fail validation
+    let output = run(&["validate", "fixtures/aat-task.tsk"]);
+    assert!(output.status.success());
+    assert!(stdout(&output).starts_with("fixtures/aat-task.tsk: warning: "));
+
+    let dir = tempfile::tempdir().unwrap();
+    let invalid = dir.path().join("invalid.tsk");
+    std::fs::write(&invalid, r#"<Task type="RT"/>"#).unwrap();
+    let output = run(&["validate", "fixtures/racing-task.tsk", path(&invalid)]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{}: Racing tasks need at least 2 points, found 0\n",
+            invalid.display()
+        )
+    );
+
+    // Out of range values are reported once, by the strict parser
+    let xml = include_str!("../fixtures/racing-task.tsk").replacen(
+        r#"latitude="51.0"#,
+        r#"latitude="95.0"#,
+        1,
+    );
+    std::fs::write(&invalid, xml).unwrap();
+    let output = run(&["validate", path(&invalid)]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output).lines().count(), 1, "{}", stdout(&output));
+
+    // The lenient parser finds what the strict one reads without complaint
+    let xml = include_str!("../fixtures/fai-task.tsk")
+        .replace(r#"start_max_height_ref="0""#, r#"start_max_height_ref="2""#);
+    std::fs::write(&invalid, xml).unwrap();
+    let output = run(&["validate", path(&invalid)]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stdout(&output).contains(": Task: unknown start_max_height_ref"),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[test]
+fn convert() {
+    let output = run(&["convert", "fixtures/racing-task.tsk", "--to", "cup"]);
+    assert!(output.status.success());
+    assert!(stdout(&output).starts_with("name,code,country,lat,lon,elev,style"));
+
+    let dir = tempfile::tempdir().unwrap();
+    let geojson = dir.path().join("task.geojson");
+    let output = run(&[
+        "convert",
+        "fixtures/racing-task.tsk",
+        "--to",
+        "geojson",
+        "--output",
+        path(&geojson),
+    ]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+    let written = std::fs::read_to_string(&geojson).unwrap();
+    assert!(written.contains("\"FeatureCollection\""));
+}
+
+#[test]
+fn fmt() {
+    // Saved by XCSoar 6 and a current version
+    let output = run(&[
+        "fmt",
+        "--check",
+        "fixtures/fai-task.tsk",
+        "fixtures/racing-task.tsk",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "");
+
+    let dir = tempfile::tempdir().unwrap();
+    let task = dir.path().join("task.tsk");
+    let xml = include_str!("../fixtures/racing-task.tsk")
+        .replace("  ", "    ")
+        .replace(r#"radius="2000.0""#, r#"radius="2000""#);
+    std::fs::write(&task, &xml).unwrap();
+
+    let output = run(&["fmt", "--check", path(&task)]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        format!("{} is not formatted\n", task.display())
+    );
+    assert_eq!(std::fs::read_to_string(&task).unwrap(), xml);
+
+    let output = run(&["fmt", path(&task)]);
+    assert!(output.status.success());
+    assert_eq!(
+        std::fs::read_to_string(&task).unwrap(),
+        include_str!("../fixtures/racing-task.tsk")
+    );
+}
+
+#[test]
+fn fmt_refuses_to_lose_data() {
+    let dir = tempfile::tempdir().unwrap();
+    let task = dir.path().join("task.tsk");
+    let xml = include_str!("../fixtures/racing-task.tsk").replacen(
+        "<Task ",
+        r#"<!-- Day 3 --><Task contest="Kleve" "#,
+        1,
+    );
+    std::fs::write(&task, &xml).unwrap();
+
+    let output = run(&["fmt", path(&task)]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{} can't be formatted without losing Task@contest, comments\n",
+            task.display()
+        )
+    );
+    assert_eq!(std::fs::read_to_string(&task).unwrap(), xml);
+}
+
+#[test]
+fn diff() {
+    let output = run(&[
+        "diff",
+        "fixtures/racing-task.tsk",
+        "fixtures/racing-task.tsk",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "");
+
+    let output = run(&[
+        "diff",
+        "fixtures/racing-task.tsk",
+        "fixtures/optional-start-task.tsk",
+    ]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        "Added Langenfeld-Wiescheid at position 6\n"
+    );
+}